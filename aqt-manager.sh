@@ -11,6 +11,7 @@ PID_FILE="$PROJECT_NAME.pid"
 LOG_FILE="logs/application.log"
 CONFIG_FILE="config.yaml"
 LOG_CONFIG="log4rs.yaml"
+BREAKER_FILE="circuit_breaker.latch"  # 与 config.yaml 中 breaker.latch_file 保持一致
 
 # 颜色定义
 RED='\033[0;31m'
@@ -249,6 +250,10 @@ status() {
     else
         echo "  日志文件不存在"
     fi
+
+    echo
+    print_info "熔断状态"
+    breaker_status
 }
 
 # 查看日志
@@ -325,6 +330,27 @@ health_check() {
     print_success "健康检查完成"
 }
 
+# 查看熔断状态
+breaker_status() {
+    if [ -f "$BREAKER_FILE" ]; then
+        print_warning "交易已熔断: $(cat "$BREAKER_FILE")"
+        print_info "确认风险后使用 '$0 reset-breaker' 解除熔断"
+    else
+        print_success "未熔断"
+    fi
+}
+
+# 解除熔断，运行中的服务在下一次检查时恢复开仓
+reset_breaker() {
+    if [ ! -f "$BREAKER_FILE" ]; then
+        print_info "未熔断，无需解除"
+        return 0
+    fi
+    print_info "熔断原因: $(cat "$BREAKER_FILE")"
+    rm -f "$BREAKER_FILE"
+    print_success "熔断已解除"
+}
+
 # 显示帮助信息
 show_help() {
     print_header
@@ -340,6 +366,8 @@ show_help() {
     echo "  clean-logs             清理日志文件"
     echo "  monitor                监控模式"
     echo "  health                 健康检查"
+    echo "  breaker                查看熔断状态"
+    echo "  reset-breaker          解除熔断"
     echo "  help                   显示帮助信息"
     echo
     echo "示例:"
@@ -381,6 +409,12 @@ main() {
         health)
             health_check
             ;;
+        breaker)
+            breaker_status
+            ;;
+        reset-breaker)
+            reset_breaker
+            ;;
         help|--help|-h)
             show_help
             ;;
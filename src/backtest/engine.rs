@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use log::{info, warn};
+use longport::{decimal, Decimal};
+use serde::Serialize;
+use time::OffsetDateTime;
+use crate::backtest::loader::{candle_file, load_candles};
+use crate::backtest::market::BacktestMarket;
+use crate::config::config::{BacktestConfig, PaperConfig, SymbolConfig};
+use crate::indicators::candle::{period_seconds, Candle};
+use crate::models::bar::{BarEvent, BarEventKind};
+use crate::models::market::MarketData;
+use crate::services::paper_broker::{Fill, PaperBroker};
+use crate::strategys::strategy::Strategy;
+
+/// 净值曲线上的一个点
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityPoint {
+    /// 时间戳（秒）
+    pub ts: u64,
+    /// 账户净值
+    pub equity: f64,
+}
+
+/// 回测结果
+pub struct BacktestResult {
+    /// 初始资金
+    pub initial_cash: f64,
+    /// 净值曲线
+    pub equity_curve: Vec<EquityPoint>,
+    /// 全部成交记录
+    pub fills: Vec<Fill>,
+    /// 参与回测的K线，按股票代码索引
+    pub candles: HashMap<String, Vec<Candle>>,
+}
+
+/// 回测中的行情事件：K线开盘只用于撮合，K线收盘同时驱动策略
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EventKind {
+    Close,
+    Open,
+}
+
+struct Event {
+    ts: u64,
+    kind: EventKind,
+    symbol: usize,
+    index: usize,
+}
+
+/// 回测引擎：按模拟时钟重放历史K线，驱动策略并由 `PaperBroker` 模拟成交。
+///
+/// 所有股票共享同一个资金池，与实盘账户一致。
+pub struct Backtest {
+    config: BacktestConfig,
+    symbols: Vec<SymbolConfig>,
+}
+
+impl Backtest {
+    pub fn new(config: BacktestConfig, symbols: Vec<SymbolConfig>) -> Self {
+        Backtest { config, symbols }
+    }
+
+    /// 加载每个股票的K线文件
+    pub fn load(&self) -> Result<HashMap<String, Vec<Candle>>, Box<dyn Error>> {
+        let mut data = HashMap::new();
+        for sym in &self.symbols {
+            let path = candle_file(&self.config.data_dir, &sym.symbol, &sym.period);
+            let candles = load_candles(&path, &sym.symbol)?;
+            info!("加载 {} 的K线 {} 根", sym.symbol, candles.len());
+            data.insert(sym.symbol.clone(), candles);
+        }
+        Ok(data)
+    }
+
+    /// 运行回测
+    pub async fn run<T>(&self) -> Result<BacktestResult, Box<dyn Error>>
+    where
+        T: Strategy<Broker = PaperBroker, Market = BacktestMarket>,
+    {
+        let data = self.load()?;
+
+        let mut market = BacktestMarket::new();
+        let mut events = Vec::new();
+        for (i, sym) in self.symbols.iter().enumerate() {
+            let candles = &data[&sym.symbol];
+            let interval = period_seconds(&sym.period)
+                .ok_or_else(|| format!("无法识别的K线周期: {}", sym.period))?;
+            for (index, c) in candles.iter().enumerate() {
+                events.push(Event { ts: c.timestamp, kind: EventKind::Open, symbol: i, index });
+                events.push(Event { ts: c.timestamp + interval, kind: EventKind::Close, symbol: i, index });
+            }
+            market.insert(&sym.symbol, &sym.period, candles.clone());
+        }
+        // 同一时刻先处理收盘（策略下单），再处理下一根K线开盘（撮合成交）
+        events.sort_by_key(|e| (e.ts, e.kind, e.symbol));
+
+        let broker = Arc::new(PaperBroker::new(&PaperConfig {
+            initial_cash: self.config.initial_cash,
+            currency: self.config.currency.clone(),
+            participation: self.config.participation,
+            short_margin: self.config.short_margin,
+        }));
+        let market = Arc::new(market);
+        let mut strategy = T::new(broker.clone(), market.clone());
+        strategy.run().await?;
+
+        let mut volumes = vec![0i64; self.symbols.len()];
+        let mut equity_curve: Vec<EquityPoint> = Vec::new();
+        for event in events {
+            let sym = &self.symbols[event.symbol];
+            let candle = &data[&sym.symbol][event.index];
+            let ts = OffsetDateTime::from_unix_timestamp(event.ts as i64)?;
+            match event.kind {
+                EventKind::Open => {
+                    let open = decimal!(candle.open);
+                    broker.on_market_data(&MarketData {
+                        symbol: sym.symbol.clone(),
+                        price: open,
+                        change: Decimal::ZERO,
+                        volume: volumes[event.symbol],
+                        high: open,
+                        low: open,
+                        open,
+                        close: open,
+                        ts,
+                    });
+                }
+                EventKind::Close => {
+                    volumes[event.symbol] += candle.volume as i64;
+                    market.set_time(event.ts);
+                    let data = MarketData {
+                        symbol: sym.symbol.clone(),
+                        price: decimal!(candle.close),
+                        change: decimal!(candle.close - candle.open),
+                        volume: volumes[event.symbol],
+                        high: decimal!(candle.high),
+                        low: decimal!(candle.low),
+                        open: decimal!(candle.open),
+                        close: decimal!(candle.close),
+                        ts,
+                    };
+                    broker.on_market_data(&data);
+                    // 与实盘一致，先发出K线收盘事件，再推送收盘行情
+                    let bar = BarEvent::new(&sym.symbol, &sym.period, BarEventKind::Closed, candle.clone());
+                    if let Err(e) = strategy.on_bar(&bar).await {
+                        warn!("回测策略处理K线出错: {:?}", e);
+                    }
+                    if let Err(e) = strategy.execute(&data).await {
+                        warn!("回测策略执行出错: {:?}", e);
+                    }
+
+                    let equity = f64::try_from(broker.equity()).unwrap_or_default();
+                    match equity_curve.last_mut() {
+                        Some(p) if p.ts == event.ts => p.equity = equity,
+                        _ => equity_curve.push(EquityPoint { ts: event.ts, equity }),
+                    }
+                }
+            }
+        }
+        strategy.stop()?;
+
+        Ok(BacktestResult {
+            initial_cash: self.config.initial_cash,
+            equity_curve,
+            fills: broker.fills(),
+            candles: data,
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::indicators::candle::Candle;
+
+/// 返回指定股票、周期的K线文件路径：`{data_dir}/{symbol}_{period}.csv`
+pub fn candle_file(data_dir: &str, symbol: &str, period: &str) -> PathBuf {
+    Path::new(data_dir).join(format!("{}_{}.csv", symbol, period))
+}
+
+/// 解析一行 CSV：`timestamp,open,high,low,close,volume`
+pub fn parse_candle(symbol: &str, line: &str) -> Result<Candle, Box<dyn Error>> {
+    let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+    if fields.len() < 6 {
+        return Err(format!("K线数据列数不足: {}", line).into());
+    }
+    Ok(Candle {
+        symbol: Some(symbol.to_string()),
+        timestamp: fields[0].parse()?,
+        open: fields[1].parse()?,
+        high: fields[2].parse()?,
+        low: fields[3].parse()?,
+        close: fields[4].parse()?,
+        volume: fields[5].parse()?,
+    })
+}
+
+/// 将K线格式化为一行 CSV
+pub fn format_candle(candle: &Candle) -> String {
+    format!(
+        "{},{},{},{},{},{}",
+        candle.timestamp, candle.open, candle.high, candle.low, candle.close, candle.volume
+    )
+}
+
+/// 从 CSV 文件加载K线，首行可以是表头，结果按时间升序排列
+pub fn load_candles(path: &Path, symbol: &str) -> Result<Vec<Candle>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("读取K线文件 {} 出错: {}", path.display(), e))?;
+    let mut candles = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("timestamp") {
+            continue;
+        }
+        candles.push(parse_candle(symbol, line)?);
+    }
+    candles.sort_by_key(|c| c.timestamp);
+    Ok(candles)
+}
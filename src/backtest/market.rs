@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use longport::quote::MarketTemperature;
+use time::OffsetDateTime;
+use crate::indicators::candle::Candle;
+use crate::services::broker::MarketDataSource;
+use crate::utils::error::AqtError;
+
+/// 每次返回给策略的K线数量，与 `Service::get_candlesticks` 保持一致
+const CANDLE_COUNT: usize = 365;
+
+/// 回测使用的行情数据来源，只返回模拟时钟之前已经收盘的K线
+pub struct BacktestMarket {
+    candles: HashMap<(String, String), Vec<Candle>>,
+    clock: AtomicU64,
+}
+
+impl BacktestMarket {
+    pub fn new() -> Self {
+        BacktestMarket {
+            candles: HashMap::new(),
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    /// 添加某个股票、周期的全部历史K线
+    pub fn insert(&mut self, symbol: &str, period: &str, candles: Vec<Candle>) {
+        self.candles.insert((symbol.to_string(), period.to_string()), candles);
+    }
+
+    /// 设置模拟时钟，`ts` 之前开始的K线对策略可见
+    pub fn set_time(&self, ts: u64) {
+        self.clock.store(ts, Ordering::SeqCst);
+    }
+}
+
+impl Default for BacktestMarket {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketDataSource for BacktestMarket {
+    async fn get_candlesticks(&self, symbol: String, period: String) -> Result<Vec<Candle>, AqtError> {
+        let now = self.clock.load(Ordering::SeqCst);
+        Ok(match self.candles.get(&(symbol, period)) {
+            Some(candles) => {
+                let end = candles.partition_point(|c| c.timestamp < now);
+                candles[end.saturating_sub(CANDLE_COUNT)..end].to_vec()
+            }
+            None => Vec::new(),
+        })
+    }
+
+    async fn get_candlesticks_since(&self, symbol: String, period: String, since: Option<u64>) -> Result<Vec<Candle>, AqtError> {
+        let now = self.clock.load(Ordering::SeqCst);
+        Ok(match self.candles.get(&(symbol, period)) {
+            Some(candles) => candles
+                .iter()
+                .filter(|c| c.timestamp < now && since.is_none_or(|ts| c.timestamp >= ts))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+
+    /// 历史市场温度不可得，返回中性值
+    async fn get_market_temperature(&self) -> Result<MarketTemperature, AqtError> {
+        Ok(MarketTemperature {
+            temperature: 50,
+            description: "backtest".to_string(),
+            valuation: 40,
+            sentiment: 50,
+            timestamp: OffsetDateTime::from_unix_timestamp(self.clock.load(Ordering::SeqCst) as i64)
+                .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+        })
+    }
+}
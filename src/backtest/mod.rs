@@ -0,0 +1,5 @@
+pub mod engine;
+pub mod loader;
+pub mod market;
+pub mod optimizer;
+pub mod report;
@@ -0,0 +1,385 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+use crate::backtest::engine::EquityPoint;
+use crate::backtest::report::{drawdown, ratios, SECONDS_PER_YEAR};
+use crate::computes::indicator_state::IndicatorState;
+use crate::computes::params::IndicatorParams;
+use crate::config::config::{OptimizeMetric, OptimizerConfig, ParamGrid, SearchMode};
+use crate::indicators::candle::Candle;
+
+/// 一组参数在一段K线上的表现
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Score {
+    pub total_return: f64,
+    pub sharpe: f64,
+    pub max_drawdown: f64,
+    /// 开仓次数
+    pub trades: usize,
+}
+
+impl Score {
+    /// 按排名指标取值，越大越好
+    pub fn value(&self, metric: OptimizeMetric) -> f64 {
+        match metric {
+            OptimizeMetric::Sharpe => self.sharpe,
+            OptimizeMetric::TotalReturn => self.total_return,
+            OptimizeMetric::Calmar => {
+                if self.max_drawdown > 0.0 {
+                    self.total_return / self.max_drawdown
+                } else {
+                    self.total_return
+                }
+            }
+        }
+    }
+
+    /// 多个股票的平均表现
+    fn average(scores: &[Score]) -> Score {
+        if scores.is_empty() {
+            return Score::default();
+        }
+        let n = scores.len() as f64;
+        Score {
+            total_return: scores.iter().map(|s| s.total_return).sum::<f64>() / n,
+            sharpe: scores.iter().map(|s| s.sharpe).sum::<f64>() / n,
+            max_drawdown: scores.iter().map(|s| s.max_drawdown).sum::<f64>() / n,
+            trades: scores.iter().map(|s| s.trades).sum(),
+        }
+    }
+}
+
+/// 排名表中的一行
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedParams {
+    pub params: IndicatorParams,
+    pub score: Score,
+}
+
+/// 滚动验证的一个窗口：样本内选出最优参数，再在紧随其后的样本外区间检验
+#[derive(Debug, Clone, Serialize)]
+pub struct WalkForwardWindow {
+    /// 样本内起始K线下标
+    pub start: usize,
+    /// 样本外起始K线下标
+    pub split: usize,
+    /// 样本外结束K线下标（不包含）
+    pub end: usize,
+    pub params: IndicatorParams,
+    pub in_sample: Score,
+    pub out_of_sample: Score,
+}
+
+/// 在历史K线上搜索指标参数。
+///
+/// 评估时每组参数独立模拟单一股票的全仓多头/空仓交易：K线收盘计算信号，
+/// 下一根K线开盘成交，不使用 `PaperBroker`，因此可以在多个线程中并行运行。
+pub struct Optimizer {
+    config: OptimizerConfig,
+    data: Vec<Vec<Candle>>,
+}
+
+impl Optimizer {
+    /// `data` 为每个股票按时间升序排列的K线
+    pub fn new(config: OptimizerConfig, data: Vec<Vec<Candle>>) -> Self {
+        Optimizer { config, data }
+    }
+
+    /// 根据搜索方式生成候选参数
+    pub fn candidates(&self) -> Vec<IndicatorParams> {
+        match self.config.search {
+            SearchMode::Grid => grid_candidates(&self.config.grid),
+            SearchMode::Random => random_candidates(&self.config.grid, self.config.samples, self.config.seed),
+        }
+    }
+
+    /// 在全部K线上评估候选参数，按排名指标从高到低排序
+    pub fn rank(&self) -> Vec<RankedParams> {
+        let candidates = self.candidates();
+        let len = self.min_len();
+        let scores = self.evaluate_all(&candidates, 0, len);
+        let mut ranked: Vec<RankedParams> = candidates
+            .into_iter()
+            .zip(scores)
+            .map(|(params, score)| RankedParams { params, score })
+            .collect();
+        let metric = self.config.metric;
+        ranked.sort_by(|a, b| b.score.value(metric).total_cmp(&a.score.value(metric)));
+        ranked
+    }
+
+    /// 滚动验证：窗口每次向前移动一个样本外长度，未配置样本区间时返回空
+    pub fn walk_forward(&self) -> Vec<WalkForwardWindow> {
+        let (Some(in_sample), Some(out_of_sample)) = (self.config.in_sample, self.config.out_of_sample) else {
+            return Vec::new();
+        };
+        if in_sample == 0 || out_of_sample == 0 {
+            return Vec::new();
+        }
+        let candidates = self.candidates();
+        let len = self.min_len();
+        let metric = self.config.metric;
+        let mut windows = Vec::new();
+        let mut start = 0;
+        while start + in_sample + out_of_sample <= len {
+            let split = start + in_sample;
+            let end = split + out_of_sample;
+            let scores = self.evaluate_all(&candidates, start, split);
+            let best = scores
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.value(metric).total_cmp(&b.1.value(metric)))
+                .map(|(i, _)| i);
+            if let Some(best) = best {
+                let params = candidates[best].clone();
+                let out_of_sample = self.evaluate_symbols(&params, split, end);
+                windows.push(WalkForwardWindow {
+                    start,
+                    split,
+                    end,
+                    params,
+                    in_sample: scores[best].clone(),
+                    out_of_sample,
+                });
+            }
+            start += out_of_sample;
+        }
+        windows
+    }
+
+    /// 导出排名表 CSV
+    pub fn write_csv(ranked: &[RankedParams], path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut out = String::from(
+            "rank,kdj_k,kdj_d,kdj_j,macd_fast,macd_slow,macd_signal,stc_length,stc_fast,stc_slow,\
+             utbot_key,utbot_atr,utbot_heikin,threshold,total_return,sharpe,max_drawdown,trades\n",
+        );
+        for (i, r) in ranked.iter().enumerate() {
+            let p = &r.params;
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                i + 1,
+                p.kdj_k,
+                p.kdj_d,
+                p.kdj_j,
+                p.macd_fast,
+                p.macd_slow,
+                p.macd_signal,
+                p.stc_length,
+                p.stc_fast,
+                p.stc_slow,
+                p.utbot_key,
+                p.utbot_atr,
+                p.utbot_heikin,
+                p.threshold,
+                r.score.total_return,
+                r.score.sharpe,
+                r.score.max_drawdown,
+                r.score.trades
+            ));
+        }
+        fs::write(path, out)?;
+        Ok(())
+    }
+
+    /// 各股票K线数量的最小值，滚动窗口按该长度划分
+    fn min_len(&self) -> usize {
+        self.data.iter().map(|c| c.len()).min().unwrap_or_default()
+    }
+
+    /// 在 `[start, end)` 区间评估一组参数，取所有股票的平均表现
+    fn evaluate_symbols(&self, params: &IndicatorParams, start: usize, end: usize) -> Score {
+        let scores: Vec<Score> = self
+            .data
+            .iter()
+            .map(|candles| evaluate(params, candles, start, end))
+            .collect();
+        Score::average(&scores)
+    }
+
+    /// 把候选参数分到多个线程中并行评估，结果顺序与候选参数一致
+    fn evaluate_all(&self, candidates: &[IndicatorParams], start: usize, end: usize) -> Vec<Score> {
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+        let threads = self
+            .config
+            .threads
+            .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+            .max(1);
+        let chunk = candidates.len().div_ceil(threads);
+        std::thread::scope(|s| {
+            let handles: Vec<_> = candidates
+                .chunks(chunk)
+                .map(|group| {
+                    s.spawn(move || {
+                        group
+                            .iter()
+                            .map(|p| self.evaluate_symbols(p, start, end))
+                            .collect::<Vec<Score>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().expect("参数评估线程异常退出"))
+                .collect()
+        })
+    }
+}
+
+/// 模拟一组参数在 `candles[start..end]` 上的交易。
+///
+/// 信号只使用当前K线及之前的数据，`start` 之前的K线仅用于指标预热。
+pub fn evaluate(params: &IndicatorParams, candles: &[Candle], start: usize, end: usize) -> Score {
+    let end = end.min(candles.len());
+    let first = start.max(params.warmup() - 1);
+    if first >= end {
+        return Score::default();
+    }
+
+    let mut cash = 1.0;
+    let mut units = 0.0;
+    let mut trades = 0;
+    let mut pending = 0;
+    let mut curve = Vec::with_capacity(end - first);
+    // 指标逐根K线增量推进，结果与每次对 `candles[..=i]` 整体计算一致
+    let profile = params.profile();
+    let mut state = IndicatorState::new();
+    for i in first..end {
+        let candle = &candles[i];
+        // 上一根K线收盘产生的信号在本根K线开盘成交
+        if pending > 0 && units == 0.0 && candle.open > 0.0 {
+            units = cash / candle.open;
+            cash = 0.0;
+            trades += 1;
+        } else if pending < 0 && units > 0.0 {
+            cash = units * candle.open;
+            units = 0.0;
+        }
+        curve.push(EquityPoint { ts: candle.timestamp, equity: cash + units * candle.close });
+        pending = if i + 1 < end {
+            state.sync(&profile, &candles[..=i]);
+            let held = if units > 0.0 { 1 } else { 0 };
+            profile.build_with_state(Some(&state), &candles[..=i], None, None).execute_rules_holding(held).signal
+        } else {
+            0
+        };
+    }
+
+    let returns: Vec<f64> = curve
+        .windows(2)
+        .filter(|w| w[0].equity > 0.0)
+        .map(|w| w[1].equity / w[0].equity - 1.0)
+        .collect();
+    let span = curve.last().map(|p| p.ts).unwrap_or_default().saturating_sub(curve[0].ts);
+    let periods_per_year = if span > 0 {
+        returns.len() as f64 / (span as f64 / SECONDS_PER_YEAR)
+    } else {
+        0.0
+    };
+    let (sharpe, _) = ratios(&returns, periods_per_year);
+    let (max_drawdown, _) = drawdown(&curve);
+    Score {
+        total_return: curve.last().map(|p| p.equity).unwrap_or(1.0) - 1.0,
+        sharpe,
+        max_drawdown,
+        trades,
+    }
+}
+
+/// 网格搜索：参数网格的全部有效组合
+pub fn grid_candidates(grid: &ParamGrid) -> Vec<IndicatorParams> {
+    let mut out = vec![IndicatorParams::default()];
+    out = expand(out, &grid.kdj_k, |p, v| p.kdj_k = *v);
+    out = expand(out, &grid.kdj_d, |p, v| p.kdj_d = *v);
+    out = expand(out, &grid.kdj_j, |p, v| p.kdj_j = *v);
+    out = expand(out, &grid.macd_fast, |p, v| p.macd_fast = *v);
+    out = expand(out, &grid.macd_slow, |p, v| p.macd_slow = *v);
+    out = expand(out, &grid.macd_signal, |p, v| p.macd_signal = *v);
+    out = expand(out, &grid.stc_length, |p, v| p.stc_length = *v);
+    out = expand(out, &grid.stc_fast, |p, v| p.stc_fast = *v);
+    out = expand(out, &grid.stc_slow, |p, v| p.stc_slow = *v);
+    out = expand(out, &grid.utbot_key, |p, v| p.utbot_key = *v);
+    out = expand(out, &grid.utbot_atr, |p, v| p.utbot_atr = *v);
+    out = expand(out, &grid.utbot_heikin, |p, v| p.utbot_heikin = *v);
+    out = expand(out, &grid.threshold, |p, v| p.threshold = *v);
+    out.retain(|p| p.is_valid());
+    out
+}
+
+/// 随机搜索：从参数网格中抽取最多 `samples` 组不重复的有效参数
+pub fn random_candidates(grid: &ParamGrid, samples: usize, seed: u64) -> Vec<IndicatorParams> {
+    let mut rng = XorShift::new(seed);
+    let mut out: Vec<IndicatorParams> = Vec::new();
+    // 网格组合数可能少于抽样数量，限制尝试次数避免死循环
+    for _ in 0..samples.saturating_mul(20) {
+        if out.len() >= samples {
+            break;
+        }
+        let mut p = IndicatorParams::default();
+        pick(&mut rng, &grid.kdj_k, &mut p.kdj_k);
+        pick(&mut rng, &grid.kdj_d, &mut p.kdj_d);
+        pick(&mut rng, &grid.kdj_j, &mut p.kdj_j);
+        pick(&mut rng, &grid.macd_fast, &mut p.macd_fast);
+        pick(&mut rng, &grid.macd_slow, &mut p.macd_slow);
+        pick(&mut rng, &grid.macd_signal, &mut p.macd_signal);
+        pick(&mut rng, &grid.stc_length, &mut p.stc_length);
+        pick(&mut rng, &grid.stc_fast, &mut p.stc_fast);
+        pick(&mut rng, &grid.stc_slow, &mut p.stc_slow);
+        pick(&mut rng, &grid.utbot_key, &mut p.utbot_key);
+        pick(&mut rng, &grid.utbot_atr, &mut p.utbot_atr);
+        pick(&mut rng, &grid.utbot_heikin, &mut p.utbot_heikin);
+        pick(&mut rng, &grid.threshold, &mut p.threshold);
+        if p.is_valid() && !out.contains(&p) {
+            out.push(p);
+        }
+    }
+    out
+}
+
+/// 笛卡尔积展开一个参数维度，候选值为空时保留原值
+fn expand<T>(
+    params: Vec<IndicatorParams>,
+    values: &[T],
+    set: impl Fn(&mut IndicatorParams, &T),
+) -> Vec<IndicatorParams> {
+    if values.is_empty() {
+        return params;
+    }
+    let mut out = Vec::with_capacity(params.len() * values.len());
+    for p in &params {
+        for v in values {
+            let mut next = p.clone();
+            set(&mut next, v);
+            out.push(next);
+        }
+    }
+    out
+}
+
+fn pick<T: Copy>(rng: &mut XorShift, values: &[T], target: &mut T) {
+    if !values.is_empty() {
+        *target = values[rng.next() as usize % values.len()];
+    }
+}
+
+/// 简单的 xorshift64 随机数生成器，保证相同种子得到相同的抽样结果
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // 种子为 0 时 xorshift 会一直输出 0
+        XorShift(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
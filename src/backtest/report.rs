@@ -0,0 +1,316 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use longport::trade::OrderSide;
+use serde::Serialize;
+use crate::backtest::engine::{BacktestResult, EquityPoint};
+
+/// 一年的秒数，用于年化
+pub(crate) const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// 一笔完整的交易（开仓到平仓），按先进先出配对成交记录得到
+#[derive(Debug, Clone, Serialize)]
+pub struct Trade {
+    pub symbol: String,
+    /// 空头交易：卖出开仓、买入平仓
+    pub short: bool,
+    pub entry_ts: u64,
+    pub exit_ts: u64,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub quantity: f64,
+    pub pnl: f64,
+    pub return_pct: f64,
+}
+
+/// 单个股票的统计
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolReport {
+    pub symbol: String,
+    pub trades: usize,
+    pub win_rate: f64,
+    pub pnl: f64,
+    pub profit_factor: f64,
+    /// 同期买入持有收益率
+    pub benchmark_return: f64,
+}
+
+/// 回测绩效报告
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub initial_cash: f64,
+    pub final_equity: f64,
+    pub total_return: f64,
+    pub cagr: f64,
+    pub sharpe: f64,
+    pub sortino: f64,
+    pub max_drawdown: f64,
+    /// 最长回撤持续时间（秒）
+    pub max_drawdown_duration: u64,
+    pub trades: usize,
+    pub win_rate: f64,
+    pub profit_factor: f64,
+    /// 平均持仓时间（秒）
+    pub avg_holding_time: f64,
+    /// 持仓时间占回测区间的比例
+    pub exposure: f64,
+    pub symbols: Vec<SymbolReport>,
+    pub equity_curve: Vec<EquityPoint>,
+    pub trade_list: Vec<Trade>,
+}
+
+impl BacktestReport {
+    /// 根据回测结果计算绩效报告
+    pub fn from_result(result: &BacktestResult) -> Self {
+        let curve = &result.equity_curve;
+        let final_equity = curve.last().map(|p| p.equity).unwrap_or(result.initial_cash);
+        let total_return = if result.initial_cash > 0.0 {
+            final_equity / result.initial_cash - 1.0
+        } else {
+            0.0
+        };
+
+        let span = match (curve.first(), curve.last()) {
+            (Some(first), Some(last)) => last.ts.saturating_sub(first.ts),
+            _ => 0,
+        };
+        let years = span as f64 / SECONDS_PER_YEAR;
+        let cagr = if years > 0.0 && final_equity > 0.0 && result.initial_cash > 0.0 {
+            (final_equity / result.initial_cash).powf(1.0 / years) - 1.0
+        } else {
+            0.0
+        };
+
+        let returns: Vec<f64> = curve
+            .windows(2)
+            .filter(|w| w[0].equity > 0.0)
+            .map(|w| w[1].equity / w[0].equity - 1.0)
+            .collect();
+        let periods_per_year = if years > 0.0 { returns.len() as f64 / years } else { 0.0 };
+        let (sharpe, sortino) = ratios(&returns, periods_per_year);
+        let (max_drawdown, max_drawdown_duration) = drawdown(curve);
+
+        let trade_list = match_trades(result);
+        let (win_rate, profit_factor) = win_stats(&trade_list);
+        let avg_holding_time = if trade_list.is_empty() {
+            0.0
+        } else {
+            trade_list.iter().map(|t| (t.exit_ts - t.entry_ts) as f64).sum::<f64>()
+                / trade_list.len() as f64
+        };
+        let exposure = exposure(result, curve);
+
+        let mut symbols = Vec::new();
+        let mut names: Vec<&String> = result.candles.keys().collect();
+        names.sort();
+        for symbol in names {
+            let trades: Vec<Trade> = trade_list.iter().filter(|t| &t.symbol == symbol).cloned().collect();
+            let (win_rate, profit_factor) = win_stats(&trades);
+            let candles = &result.candles[symbol];
+            let benchmark_return = match (candles.first(), candles.last()) {
+                (Some(first), Some(last)) if first.close > 0.0 => last.close / first.close - 1.0,
+                _ => 0.0,
+            };
+            symbols.push(SymbolReport {
+                symbol: symbol.clone(),
+                trades: trades.len(),
+                win_rate,
+                pnl: trades.iter().map(|t| t.pnl).sum(),
+                profit_factor,
+                benchmark_return,
+            });
+        }
+
+        BacktestReport {
+            initial_cash: result.initial_cash,
+            final_equity,
+            total_return,
+            cagr,
+            sharpe,
+            sortino,
+            max_drawdown,
+            max_drawdown_duration,
+            trades: trade_list.len(),
+            win_rate,
+            profit_factor,
+            avg_holding_time,
+            exposure,
+            symbols,
+            equity_curve: curve.clone(),
+            trade_list,
+        }
+    }
+
+    /// 导出净值曲线 CSV
+    pub fn write_equity_csv(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut out = String::from("ts,equity\n");
+        for p in &self.equity_curve {
+            out.push_str(&format!("{},{}\n", p.ts, p.equity));
+        }
+        fs::write(path, out)?;
+        Ok(())
+    }
+
+    /// 导出交易列表 CSV
+    pub fn write_trades_csv(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut out = String::from("symbol,short,entry_ts,exit_ts,entry_price,exit_price,quantity,pnl,return_pct\n");
+        for t in &self.trade_list {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                t.symbol, t.short, t.entry_ts, t.exit_ts, t.entry_price, t.exit_price, t.quantity, t.pnl, t.return_pct
+            ));
+        }
+        fs::write(path, out)?;
+        Ok(())
+    }
+
+    /// 导出完整报告 JSON（包含净值曲线和交易列表）
+    pub fn write_json(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 将报告导出到目录：`equity.csv`、`trades.csv`、`report.json`
+    pub fn export(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        self.write_equity_csv(&dir.join("equity.csv"))?;
+        self.write_trades_csv(&dir.join("trades.csv"))?;
+        self.write_json(&dir.join("report.json"))?;
+        Ok(())
+    }
+}
+
+/// 计算年化 Sharpe 与 Sortino（无风险利率取 0）
+pub(crate) fn ratios(returns: &[f64], periods_per_year: f64) -> (f64, f64) {
+    if returns.len() < 2 {
+        return (0.0, 0.0);
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    let downside = (returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / n).sqrt();
+    let scale = periods_per_year.sqrt();
+    let sharpe = if std > 0.0 { mean / std * scale } else { 0.0 };
+    let sortino = if downside > 0.0 { mean / downside * scale } else { 0.0 };
+    (sharpe, sortino)
+}
+
+/// 计算最大回撤（比例）及最长回撤持续时间（秒）
+pub(crate) fn drawdown(curve: &[EquityPoint]) -> (f64, u64) {
+    let mut peak = f64::MIN;
+    let mut peak_ts = 0;
+    let mut max_dd = 0.0;
+    let mut max_duration = 0;
+    for p in curve {
+        if p.equity >= peak {
+            peak = p.equity;
+            peak_ts = p.ts;
+        } else {
+            if peak > 0.0 {
+                max_dd = f64::max(max_dd, (peak - p.equity) / peak);
+            }
+            max_duration = max_duration.max(p.ts - peak_ts);
+        }
+    }
+    (max_dd, max_duration)
+}
+
+/// 胜率与盈亏比
+fn win_stats(trades: &[Trade]) -> (f64, f64) {
+    if trades.is_empty() {
+        return (0.0, 0.0);
+    }
+    let wins = trades.iter().filter(|t| t.pnl > 0.0).count();
+    let gross_profit: f64 = trades.iter().filter(|t| t.pnl > 0.0).map(|t| t.pnl).sum();
+    let gross_loss: f64 = trades.iter().filter(|t| t.pnl < 0.0).map(|t| -t.pnl).sum();
+    let profit_factor = if gross_loss > 0.0 {
+        gross_profit / gross_loss
+    } else if gross_profit > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+    (wins as f64 / trades.len() as f64, profit_factor)
+}
+
+/// 一个股票未平仓的批次：(是否空头, [(时间, 价格, 剩余数量)])，同一股票的批次方向相同
+type OpenLots = (bool, VecDeque<(u64, f64, f64)>);
+
+/// 按先进先出把开仓和平仓成交配对成交易
+///
+/// 空仓或持有空头时卖出开空，持有空头时买入平空；一笔成交超过反向持仓时，平仓后剩余部分反向开仓。
+fn match_trades(result: &BacktestResult) -> Vec<Trade> {
+    let mut lots: HashMap<String, OpenLots> = HashMap::new();
+    let mut trades = Vec::new();
+    for fill in &result.fills {
+        let short = match fill.side {
+            OrderSide::Buy => false,
+            OrderSide::Sell => true,
+            _ => continue,
+        };
+        let ts = fill.ts.unix_timestamp() as u64;
+        let price = f64::try_from(fill.price).unwrap_or_default();
+        let mut quantity = f64::try_from(fill.quantity).unwrap_or_default();
+        let (held_short, queue) = lots.entry(fill.symbol.clone()).or_default();
+        // 与持仓方向相反的成交先平仓
+        if *held_short != short {
+            while quantity > 0.0 {
+                let Some(lot) = queue.front_mut() else { break };
+                let matched = lot.2.min(quantity);
+                let (pnl, return_pct) = if *held_short {
+                    ((lot.1 - price) * matched, if lot.1 > 0.0 { 1.0 - price / lot.1 } else { 0.0 })
+                } else {
+                    ((price - lot.1) * matched, if lot.1 > 0.0 { price / lot.1 - 1.0 } else { 0.0 })
+                };
+                trades.push(Trade {
+                    symbol: fill.symbol.clone(),
+                    short: *held_short,
+                    entry_ts: lot.0,
+                    exit_ts: ts,
+                    entry_price: lot.1,
+                    exit_price: price,
+                    quantity: matched,
+                    pnl,
+                    return_pct,
+                });
+                lot.2 -= matched;
+                quantity -= matched;
+                if lot.2 <= 0.0 {
+                    queue.pop_front();
+                }
+            }
+        }
+        if quantity > 0.0 {
+            *held_short = short;
+            queue.push_back((ts, price, quantity));
+        }
+    }
+    trades
+}
+
+/// 持仓时间占比：在净值曲线的每个区间内是否持有任意仓位（多头或空头）
+fn exposure(result: &BacktestResult, curve: &[EquityPoint]) -> f64 {
+    let span = match (curve.first(), curve.last()) {
+        (Some(first), Some(last)) if last.ts > first.ts => (last.ts - first.ts) as f64,
+        _ => return 0.0,
+    };
+    let mut holdings: BTreeMap<String, f64> = BTreeMap::new();
+    let mut fills = result.fills.iter().peekable();
+    let mut exposed = 0.0;
+    for w in curve.windows(2) {
+        while let Some(fill) = fills.next_if(|f| f.ts.unix_timestamp() as u64 <= w[0].ts) {
+            let quantity = f64::try_from(fill.quantity).unwrap_or_default();
+            let held = holdings.entry(fill.symbol.clone()).or_default();
+            match fill.side {
+                OrderSide::Buy => *held += quantity,
+                OrderSide::Sell => *held -= quantity,
+                _ => {}
+            }
+        }
+        if holdings.values().any(|q| *q != 0.0) {
+            exposed += (w[1].ts - w[0].ts) as f64;
+        }
+    }
+    exposed / span
+}
@@ -0,0 +1,53 @@
+use aqt_stock::backtest::engine::Backtest;
+use aqt_stock::backtest::market::BacktestMarket;
+use aqt_stock::backtest::report::BacktestReport;
+use aqt_stock::config::config;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::strategys::vecor_v1::VecorStrategy;
+use log::info;
+use std::error::Error;
+use std::path::Path;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    log4rs::init_file("log4rs.yaml", Default::default())?;
+    let cfg = config::Configs::load()?;
+    let bt_cfg = cfg.backtest.ok_or("config.yaml 中缺少 backtest 配置")?;
+
+    let output_dir = bt_cfg.output_dir.clone();
+    let backtest = Backtest::new(bt_cfg, cfg.symbols);
+    let result = backtest
+        .run::<VecorStrategy<PaperBroker, BacktestMarket>>()
+        .await?;
+
+    let report = BacktestReport::from_result(&result);
+    report.export(Path::new(&output_dir))?;
+    info!("回测报告已导出到 {}", output_dir);
+
+    println!("初始资金:       {:.2}", report.initial_cash);
+    println!("最终净值:       {:.2}", report.final_equity);
+    println!("总收益率:       {:.2}%", report.total_return * 100.0);
+    println!("年化收益率:     {:.2}%", report.cagr * 100.0);
+    println!("Sharpe:         {:.2}", report.sharpe);
+    println!("Sortino:        {:.2}", report.sortino);
+    println!("最大回撤:       {:.2}%", report.max_drawdown * 100.0);
+    println!("最长回撤时间:   {:.1} 天", report.max_drawdown_duration as f64 / 86400.0);
+    println!("交易次数:       {}", report.trades);
+    println!("胜率:           {:.2}%", report.win_rate * 100.0);
+    println!("盈亏比:         {:.2}", report.profit_factor);
+    println!("平均持仓时间:   {:.1} 小时", report.avg_holding_time / 3600.0);
+    println!("持仓时间占比:   {:.2}%", report.exposure * 100.0);
+    println!();
+    println!("股票\t交易\t胜率\t盈亏\t买入持有");
+    for s in &report.symbols {
+        println!(
+            "{}\t{}\t{:.2}%\t{:.2}\t{:.2}%",
+            s.symbol,
+            s.trades,
+            s.win_rate * 100.0,
+            s.pnl,
+            s.benchmark_return * 100.0
+        );
+    }
+    Ok(())
+}
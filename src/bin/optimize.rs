@@ -0,0 +1,76 @@
+use aqt_stock::backtest::engine::Backtest;
+use aqt_stock::backtest::optimizer::Optimizer;
+use aqt_stock::config::config;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// 参数评估会对每根K线重新计算指标，指标计算的日志量很大，这里不初始化日志
+fn main() -> Result<(), Box<dyn Error>> {
+    let cfg = config::Configs::load()?;
+    let bt_cfg = cfg.backtest.ok_or("config.yaml 中缺少 backtest 配置")?;
+    let opt_cfg = cfg.optimizer.ok_or("config.yaml 中缺少 optimizer 配置")?;
+
+    let output_dir = bt_cfg.output_dir.clone();
+    let data = Backtest::new(bt_cfg, cfg.symbols.clone()).load()?;
+    let data = cfg.symbols.iter().map(|s| data[&s.symbol].clone()).collect();
+    let top = opt_cfg.top;
+    let optimizer = Optimizer::new(opt_cfg, data);
+    println!("候选参数: {} 组", optimizer.candidates().len());
+
+    let ranked = optimizer.rank();
+    fs::create_dir_all(&output_dir)?;
+    let path = Path::new(&output_dir).join("optimizer.csv");
+    Optimizer::write_csv(&ranked, &path)?;
+
+    println!("排名\tKDJ\tMACD\tSTC\tUTBot\t阈值\t收益率\tSharpe\t最大回撤\t开仓");
+    for (i, r) in ranked.iter().take(top).enumerate() {
+        let p = &r.params;
+        println!(
+            "{}\t{},{},{}\t{},{},{}\t{},{},{}\t{},{},{}\t{}\t{:.2}%\t{:.2}\t{:.2}%\t{}",
+            i + 1,
+            p.kdj_k,
+            p.kdj_d,
+            p.kdj_j,
+            p.macd_fast,
+            p.macd_slow,
+            p.macd_signal,
+            p.stc_length,
+            p.stc_fast,
+            p.stc_slow,
+            p.utbot_key,
+            p.utbot_atr,
+            p.utbot_heikin,
+            p.threshold,
+            r.score.total_return * 100.0,
+            r.score.sharpe,
+            r.score.max_drawdown * 100.0,
+            r.score.trades
+        );
+    }
+    println!("完整排名已导出到 {}", path.display());
+
+    let windows = optimizer.walk_forward();
+    if !windows.is_empty() {
+        println!();
+        println!("滚动验证\t样本内\t样本外\t样本内收益\t样本外收益\t样本外Sharpe");
+        for (i, w) in windows.iter().enumerate() {
+            println!(
+                "{}\t{}..{}\t{}..{}\t{:.2}%\t{:.2}%\t{:.2}",
+                i + 1,
+                w.start,
+                w.split,
+                w.split,
+                w.end,
+                w.in_sample.total_return * 100.0,
+                w.out_of_sample.total_return * 100.0,
+                w.out_of_sample.sharpe
+            );
+        }
+        let compounded = windows
+            .iter()
+            .fold(1.0, |acc, w| acc * (1.0 + w.out_of_sample.total_return));
+        println!("样本外累计收益: {:.2}%", (compounded - 1.0) * 100.0);
+    }
+    Ok(())
+}
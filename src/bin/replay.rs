@@ -0,0 +1,21 @@
+use aqt_stock::collect::replayer::Pacing;
+use aqt_stock::config::config;
+use aqt_stock::tasks::replay;
+use dotenv::dotenv;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// 用法: `replay <录制文件> [realtime|fast|倍速]`，默认尽快回放
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    dotenv().ok();
+    log4rs::init_file("log4rs.yaml", Default::default())?;
+    let mut args = std::env::args().skip(1);
+    let path = PathBuf::from(args.next().ok_or("用法: replay <录制文件> [realtime|fast|倍速]")?);
+    let pacing = match args.next() {
+        Some(value) => Pacing::parse(&value).ok_or_else(|| format!("无法识别的回放速度: {}", value))?,
+        None => Pacing::AsFastAsPossible,
+    };
+    let cfg = config::Configs::load()?;
+    replay::start_replay(cfg, &path, pacing).await
+}
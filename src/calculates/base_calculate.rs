@@ -1,5 +1,12 @@
+use crate::calculates::signal::Signal;
+
 pub trait BaseCalculate {
     fn calculate(&self) -> i64;
     fn get_name(&self) -> String;
     fn get_description(&self) -> String;
-}
\ No newline at end of file
+
+    /// 带指标值和原因的完整信号，默认只包含投票结果
+    fn signal(&self) -> Signal {
+        Signal::new(self.get_name(), self.calculate())
+    }
+}
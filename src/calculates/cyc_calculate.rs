@@ -1,16 +1,39 @@
 use log::info;
+use std::sync::Arc;
 use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::signal::Signal;
 use crate::indicators::candle::Candle;
 use crate::indicators::cyc::CYC;
 
 pub struct CycCalculate {
-    pub candles: Vec<Candle>
+    pub candles: Arc<Vec<Candle>>,
+    indicator: Option<Arc<CYC>>, // 已增量计算好的指标
+}
+
+impl CycCalculate {
+    pub fn new(candles: Arc<Vec<Candle>>) -> Self {
+        CycCalculate { candles, indicator: None }
+    }
+
+    /// 使用已增量计算好的指标，不再重新计算
+    pub fn with_indicator(mut self, cyc: Arc<CYC>) -> Self {
+        self.indicator = Some(cyc);
+        self
+    }
 }
 
 impl BaseCalculate for CycCalculate {
     fn calculate(&self) -> i64 {
-        let mut cyc = CYC::default();
-        let (short_cyc, mid_cyc, long_cyc) = cyc.calculate(&self.candles.clone());
+        self.signal().vote
+    }
+
+    fn signal(&self) -> Signal {
+        let cyc = self.indicator.clone().unwrap_or_else(|| {
+            let mut cyc = CYC::default();
+            cyc.calculate(&self.candles);
+            Arc::new(cyc)
+        });
+        let (short_cyc, mid_cyc, long_cyc) = (cyc.short_cyc(), cyc.mid_cyc(), cyc.long_cyc());
         // 获取当前价格
         let current_price = self.candles.last().unwrap().close;
         let threshold = 5.0; // 超买/超卖阈值 5%
@@ -18,7 +41,17 @@ impl BaseCalculate for CycCalculate {
         let signal = cyc.generate_signal(current_price, threshold);
         info!("短期 CYC: {:.2}, 中期 CYC: {:.2}, 长期 CYC: {:.2}", short_cyc.last().unwrap(), mid_cyc.last().unwrap(), long_cyc.last().unwrap());
         info!("当前价格: {:.2}, 信号: {:?}", current_price, signal);
-        signal
+        let reason = match signal {
+            s if s > 0 => "价格低于成本均线（超卖）或均线金叉",
+            s if s < 0 => "价格高于成本均线（超买）或均线死叉",
+            _ => "价格在成本均线附近",
+        };
+        Signal::new(self.get_name(), signal)
+            .with_value("Short", *short_cyc.last().unwrap())
+            .with_value("Mid", *mid_cyc.last().unwrap())
+            .with_value("Long", *long_cyc.last().unwrap())
+            .with_value("Close", current_price)
+            .with_reason(reason)
     }
 
     fn get_name(&self) -> String {
@@ -1,29 +1,65 @@
+use std::sync::Arc;
 use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::signal::Signal;
 use crate::indicators::candle::Candle;
 use crate::indicators::kdj::KDJ;
 
 pub struct KdjCalculate {
-    pub(crate) candles: Vec<Candle>
+    pub(crate) candles: Arc<Vec<Candle>>,
+    pub k_period: usize, // RSV 计算周期
+    pub d_period: usize, // D 值平滑系数
+    pub j_period: usize, // J 值计算系数
+    indicator: Option<Arc<KDJ>>, // 已增量计算好的指标
+}
+
+impl KdjCalculate {
+    /// 使用默认参数 (9, 3, 3) 创建
+    pub fn new(candles: Arc<Vec<Candle>>) -> Self {
+        Self::with_params(candles, 9, 3, 3)
+    }
+
+    pub fn with_params(candles: Arc<Vec<Candle>>, k_period: usize, d_period: usize, j_period: usize) -> Self {
+        KdjCalculate { candles, k_period, d_period, j_period, indicator: None }
+    }
+
+    /// 使用已增量计算好的指标，不再重新计算
+    pub fn with_indicator(mut self, kdj: Arc<KDJ>) -> Self {
+        self.indicator = Some(kdj);
+        self
+    }
 }
 
 impl BaseCalculate for KdjCalculate {
     fn calculate(&self) -> i64 {
-        let mut kdj = KDJ::default();
-        kdj.calculate(&self.candles);
+        self.signal().vote
+    }
+
+    fn signal(&self) -> Signal {
+        let kdj = self.indicator.clone().unwrap_or_else(|| {
+            let mut kdj = KDJ::new(self.k_period, self.d_period, self.j_period);
+            kdj.calculate(&self.candles);
+            Arc::new(kdj)
+        });
+        let Some((k, d, j)) = kdj.latest() else {
+            return Signal::new(self.get_name(), 0).with_reason("K线数量不足");
+        };
         // 检查交易信号
-        if kdj.is_golden_cross() {
-            return 1;
-        }
-        if kdj.is_oversold(20.0) {
-            return 1;
-        }
-        if kdj.is_death_cross() {
-            return -1;
-        }
-        if kdj.is_overbought(80.0) {
-            return -1;
-        }
-        0
+        let (vote, reason) = if kdj.is_golden_cross() {
+            (1, "K线上穿D线（金叉）")
+        } else if kdj.is_oversold(20.0) {
+            (1, "超卖（K、D < 20）")
+        } else if kdj.is_death_cross() {
+            (-1, "K线下穿D线（死叉）")
+        } else if kdj.is_overbought(80.0) {
+            (-1, "超买（K、D > 80）")
+        } else {
+            (0, "无交叉且未超买超卖")
+        };
+        Signal::new(self.get_name(), vote)
+            .with_value("K", k)
+            .with_value("D", d)
+            .with_value("J", j)
+            .with_reason(reason)
     }
 
     fn get_name(&self) -> String {
@@ -1,43 +1,90 @@
+use std::sync::Arc;
 use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::signal::Signal;
 use crate::indicators::candle::Candle;
 use crate::indicators::macd::MACD;
 pub struct MacdCalculate {
-    pub(crate) candles: Vec<Candle>,
+    pub(crate) candles: Arc<Vec<Candle>>,
+    pub fast_length: usize,   // 快线周期
+    pub slow_length: usize,   // 慢线周期
+    pub signal_length: usize, // 信号线周期
+    indicator: Option<Arc<MACD>>, // 已增量计算好的指标
 }
 
-impl BaseCalculate for MacdCalculate {
-    fn calculate(&self) -> i64 {
-        // 创建MACD指标
-        let mut macd = MACD::new(5, 10, 5);
-        // 设置显示选项
-        macd.set_display_options(true, true, true, true, true);
+impl MacdCalculate {
+    /// 使用默认参数 (5, 10, 5) 创建
+    pub fn new(candles: Arc<Vec<Candle>>) -> Self {
+        Self::with_params(candles, 5, 10, 5)
+    }
 
-        // 计算MACD
-        let (buy_signals, sell_signals) = macd.calculate(&self.candles.clone());
+    pub fn with_params(candles: Arc<Vec<Candle>>, fast_length: usize, slow_length: usize, signal_length: usize) -> Self {
+        MacdCalculate { candles, fast_length, slow_length, signal_length, indicator: None }
+    }
 
-        let l = self.candles.len();
-        if  l < 3 {
-            return 0;
-        }
+    /// 使用已增量计算好的指标，不再重新计算
+    pub fn with_indicator(mut self, macd: Arc<MACD>) -> Self {
+        self.indicator = Some(macd);
+        self
+    }
+
+    // 检查最近三根K线的买卖点和快慢线位置
+    fn vote(macd: &MACD, buy_signals: &[bool], sell_signals: &[bool], l: usize) -> (i64, &'static str) {
         for i in (l - 3)..l {
             if i < macd.macd_line().len() && i < macd.signal_line().len() && i < macd.histogram().len() {
                 if buy_signals[i] {
-                    return 1;
+                    return (1, "出现买点");
                 }
                 if sell_signals[i] {
-                    return -1;
+                    return (-1, "出现卖点");
                 }
 
                 if macd.macd_line()[i-1] > macd.signal_line()[i-1] && macd.macd_line()[i] > macd.signal_line()[i]{
-                    return 1;
+                    return (1, "MACD线持续在信号线上方");
                 }
 
                 if macd.macd_line()[i-1] < macd.signal_line()[i-1] && macd.macd_line()[i] < macd.signal_line()[i]{
-                    return -1;
+                    return (-1, "MACD线持续在信号线下方");
                 }
             }
         }
-        0
+        (0, "无明确方向")
+    }
+}
+
+impl BaseCalculate for MacdCalculate {
+    fn calculate(&self) -> i64 {
+        self.signal().vote
+    }
+
+    fn signal(&self) -> Signal {
+        let macd = self.indicator.clone().unwrap_or_else(|| {
+            // 创建MACD指标
+            let mut macd = MACD::new(self.fast_length, self.slow_length, self.signal_length);
+            // 设置显示选项
+            macd.set_display_options(true, true, true, true, true);
+            // 计算MACD
+            macd.calculate(&self.candles);
+            Arc::new(macd)
+        });
+        let (buy_signals, sell_signals) = (macd.buy_signals(), macd.sell_signals());
+
+        let l = buy_signals.len();
+        if  l < 3 || macd.histogram().is_empty() {
+            return Signal::new(self.get_name(), 0).with_reason("K线数量不足");
+        }
+        let (vote, reason) = Self::vote(&macd, buy_signals, sell_signals, l);
+        let last = macd.histogram().len() - 1;
+        let (line, signal, hist) = (macd.macd_line()[last], macd.signal_line()[last], macd.histogram()[last]);
+        let mut result = Signal::new(self.get_name(), vote)
+            .with_value("MACD", line)
+            .with_value("Signal", signal)
+            .with_value("Hist", hist)
+            .with_reason(reason);
+        // 柱体相对 MACD 线越大，信号越强
+        if vote != 0 && line != 0.0 {
+            result = result.with_strength((hist / line).abs());
+        }
+        result
     }
 
     fn get_name(&self) -> String {
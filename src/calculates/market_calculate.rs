@@ -1,5 +1,6 @@
 use longport::quote::MarketTemperature;
 use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::signal::Signal;
 
 pub struct MarketCalculate {
     pub market: MarketTemperature
@@ -7,26 +8,30 @@ pub struct MarketCalculate {
 
 impl BaseCalculate for MarketCalculate {
     fn calculate(&self) -> i64 {
+        self.signal().vote
+    }
 
-        if self.market.sentiment > 70 {
-            return -2;
-        }
-        if self.market.sentiment < 24 {
-            return 2;
-        }
-        if self.market.temperature < 30 {
-            return 2;
-        }
-        if self.market.temperature > 55 {
-            return -2;
-        }
-        if self.market.valuation > 50 {
-            return -2;
-        }
-        if self.market.valuation < 30 {
-            return 2;
-        }
-        0
+    fn signal(&self) -> Signal {
+        let (vote, reason) = if self.market.sentiment > 70 {
+            (-2, "市场情绪过热")
+        } else if self.market.sentiment < 24 {
+            (2, "市场情绪低迷")
+        } else if self.market.temperature < 30 {
+            (2, "市场温度偏低")
+        } else if self.market.temperature > 55 {
+            (-2, "市场温度偏高")
+        } else if self.market.valuation > 50 {
+            (-2, "市场估值偏高")
+        } else if self.market.valuation < 30 {
+            (2, "市场估值偏低")
+        } else {
+            (0, "市场温度适中")
+        };
+        Signal::new(self.get_name(), vote)
+            .with_value("Temperature", self.market.temperature as f64)
+            .with_value("Valuation", self.market.valuation as f64)
+            .with_value("Sentiment", self.market.sentiment as f64)
+            .with_reason(reason)
     }
 
     fn get_name(&self) -> String {
@@ -6,3 +6,4 @@ pub mod stc_calculate;
 pub mod utbot_calculate;
 pub mod market_calculate;
 pub mod technicals_calculate;
+pub mod signal;
@@ -0,0 +1,87 @@
+use std::fmt;
+use longport::trade::OrderSide;
+use serde::Serialize;
+
+/// 单个指标给出的信号，保留关键指标值和判断原因，便于事后解释交易
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Signal {
+    /// 指标名称，与 `BaseCalculate::get_name` 一致
+    pub name: String,
+    /// 投票：正数看多，负数看空，0 为中性
+    pub vote: i64,
+    /// 信号强度，0 ~ 1
+    pub strength: f64,
+    /// 投票权重，由指标组合配置
+    pub weight: i64,
+    /// 关键指标值，例如 K/D/J、MACD 柱、UTBot 止损线
+    pub values: Vec<(String, f64)>,
+    /// 判断原因
+    pub reason: String,
+}
+
+impl Signal {
+    /// 创建信号，非零投票的强度默认为 1
+    pub fn new(name: impl Into<String>, vote: i64) -> Self {
+        Signal {
+            name: name.into(),
+            vote,
+            strength: if vote == 0 { 0.0 } else { 1.0 },
+            weight: 1,
+            values: Vec::new(),
+            reason: String::new(),
+        }
+    }
+
+    pub fn with_value(mut self, key: &str, value: f64) -> Self {
+        self.values.push((key.to_string(), value));
+        self
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = reason.into();
+        self
+    }
+
+    /// 设置信号强度，超出 0 ~ 1 的部分会被截断
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        self.strength = if strength.is_nan() { 0.0 } else { strength.clamp(0.0, 1.0) };
+        self
+    }
+
+    /// 信号方向
+    pub fn direction(&self) -> OrderSide {
+        match self.vote {
+            v if v > 0 => OrderSide::Buy,
+            v if v < 0 => OrderSide::Sell,
+            _ => OrderSide::Unknown,
+        }
+    }
+
+    /// 乘以权重后参与投票的值
+    pub fn weighted_vote(&self) -> i64 {
+        self.vote * self.weight
+    }
+
+    /// 取出指定名称的指标值
+    pub fn value(&self, key: &str) -> Option<f64> {
+        self.values.iter().find(|(k, _)| k == key).map(|(_, v)| *v)
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 投票 {}", self.name, self.vote)?;
+        if self.weight != 1 {
+            write!(f, "×{}", self.weight)?;
+        }
+        write!(f, " 强度 {:.2}", self.strength)?;
+        if !self.values.is_empty() {
+            let values: Vec<String> = self.values.iter().map(|(k, v)| format!("{}={:.2}", k, v)).collect();
+            write!(f, " [{}]", values.join(", "))?;
+        }
+        if !self.reason.is_empty() {
+            write!(f, " {}", self.reason)?;
+        }
+        Ok(())
+    }
+}
@@ -1,29 +1,60 @@
+use std::sync::Arc;
 use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::signal::Signal;
 use crate::indicators::candle::Candle;
 use crate::indicators::schaff_trend_cycle::SchaffTrendCycle;
 
 pub struct STCCalculate {
-   pub candles: Vec<Candle>,
+   pub candles: Arc<Vec<Candle>>,
+   pub length: usize,      // 随机指标周期
+   pub fast_length: usize, // 快速 EMA 周期
+   pub slow_length: usize, // 慢速 EMA 周期
+   indicator: Option<Arc<SchaffTrendCycle>>, // 已增量计算好的指标
+}
+
+impl STCCalculate {
+    /// 使用默认参数 (12, 26, 50) 创建
+    pub fn new(candles: Arc<Vec<Candle>>) -> Self {
+        Self::with_params(candles, 12, 26, 50)
+    }
+
+    pub fn with_params(candles: Arc<Vec<Candle>>, length: usize, fast_length: usize, slow_length: usize) -> Self {
+        STCCalculate { candles, length, fast_length, slow_length, indicator: None }
+    }
+
+    /// 使用已增量计算好的指标，不再重新计算
+    pub fn with_indicator(mut self, stc: Arc<SchaffTrendCycle>) -> Self {
+        self.indicator = Some(stc);
+        self
+    }
 }
 
 impl BaseCalculate for STCCalculate {
     fn calculate(&self) -> i64 {
+        self.signal().vote
+    }
+
+    fn signal(&self) -> Signal {
         // 创建STC指标
-        let mut stc = SchaffTrendCycle::new(12, 26, 50);
-        let signals = stc.calculate(&self.candles.clone());
-        let last_signal = signals.last();
+        let stc = self.indicator.clone().unwrap_or_else(|| {
+            let mut stc = SchaffTrendCycle::new(self.length, self.fast_length, self.slow_length);
+            stc.calculate(&self.candles);
+            Arc::new(stc)
+        });
+        let last_signal = stc.signals().last();
         if last_signal.is_none() {
-            return 0;
+            return Signal::new(self.get_name(), 0).with_reason("K线数量不足");
         }
         // 直接使用最后一个信号进行判断
         let (stc_value, red_signal, green_signal) = last_signal.unwrap();
+        let signal = Signal::new(self.get_name(), 0).with_value("STC", *stc_value);
         if *red_signal && stc_value > &75.0 {
-            return -1;
+            return Signal { vote: -1, ..signal }.with_strength(1.0).with_reason("高位拐头向下");
         } 
         if *green_signal  && stc_value < &25.0 {
-            return 1;
+            return Signal { vote: 1, ..signal }.with_strength(1.0).with_reason("低位拐头向上");
         }
-        0
+        signal.with_reason("未在高低位拐头")
     }
 
     fn get_name(&self) -> String {
@@ -1,4 +1,5 @@
 use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::signal::Signal;
 use crate::indicators::tradingview_technicals::TradingTechnicals;
 
 pub struct TechnicalsCalculate {
@@ -7,16 +8,25 @@ pub struct TechnicalsCalculate {
 
 impl BaseCalculate for TechnicalsCalculate {
     fn calculate(&self) -> i64 {
+        self.signal().vote
+    }
+
+    fn signal(&self) -> Signal {
         let (summary_signal, ma_signal, osc_signal) = self.technicals.calculate();
-        if summary_signal >= 1.0 || ma_signal >= 1.0 || osc_signal >= 1.0 {
-            3
+        let (vote, reason) = if summary_signal >= 1.0 || ma_signal >= 1.0 || osc_signal >= 1.0 {
+            (3, "技术评级为强烈买入")
         }
         else if summary_signal <= -1.0 || ma_signal <= -1.0 || osc_signal <= -1.0 {
-            -3
+            (-3, "技术评级为强烈卖出")
         }
         else {
-            0
-        }
+            (0, "技术评级中性")
+        };
+        Signal::new(self.get_name(), vote)
+            .with_value("Summary", summary_signal)
+            .with_value("MA", ma_signal)
+            .with_value("Oscillators", osc_signal)
+            .with_reason(reason)
     }
 
     fn get_name(&self) -> String {
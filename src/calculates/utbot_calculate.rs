@@ -1,44 +1,70 @@
 use log::info;
+use std::sync::Arc;
 use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::signal::Signal;
 use crate::indicators::candle::Candle;
 use crate::indicators::utbot::UTBot;
 
 pub struct UTBotCalculate {
-    pub(crate) candles: Vec<Candle>,
+    pub(crate) candles: Arc<Vec<Candle>>,
+    pub key_value: f64,        // 灵敏度系数
+    pub atr_period: usize,     // ATR 计算周期
+    pub use_heikin_ashi: bool, // 是否使用平均K线
+    indicator: Option<Arc<UTBot>>, // 已增量计算好的指标
+}
+
+impl UTBotCalculate {
+    /// 使用默认参数 (1.0, 10, false) 创建
+    pub fn new(candles: Arc<Vec<Candle>>) -> Self {
+        Self::with_params(candles, 1.0, 10, false)
+    }
+
+    pub fn with_params(candles: Arc<Vec<Candle>>, key_value: f64, atr_period: usize, use_heikin_ashi: bool) -> Self {
+        UTBotCalculate { candles, key_value, atr_period, use_heikin_ashi, indicator: None }
+    }
+
+    /// 使用已增量计算好的指标，不再重新计算
+    pub fn with_indicator(mut self, ubot: Arc<UTBot>) -> Self {
+        self.indicator = Some(ubot);
+        self
+    }
 }
 
 impl BaseCalculate for UTBotCalculate {
     fn calculate(&self) -> i64 {
-        // 创建STC指标
-        let mut ubot = UTBot::new(1.0, 10, false);
-        // 不再保存返回值，直接调用计算方法
-        ubot.calculate(&self.candles);
+        self.signal().vote
+    }
+
+    fn signal(&self) -> Signal {
+        let ubot = self.indicator.clone().unwrap_or_else(|| {
+            let mut ubot = UTBot::new(self.key_value, self.atr_period, self.use_heikin_ashi);
+            // 不再保存返回值，直接调用计算方法
+            ubot.calculate(&self.candles);
+            Arc::new(ubot)
+        });
+        let mut signal = Signal::new(self.get_name(), 0);
         // 获取最新状态
         if let Some(stop) = ubot.latest_stop() {
             info!("最新跟踪止损线: {:.2}", stop);
+            signal = signal.with_value("Stop", stop);
         }
-
-        if ubot.is_long() {
-            return 1
+        if let Some(close) = self.candles.last().map(|c| c.close) {
+            signal = signal.with_value("Close", close);
         }
 
-        if ubot.is_short() {
-           return -1
-        }
-        // 检查最新信号
-        let buy_signals = ubot.buy_signals();  // 使用方法获取信号数组
-        if let Some(&buy) = buy_signals.last() {
-            if buy {
-                return 1
-            }
-        }
-        let sell_signals = ubot.sell_signals();  // 使用方法获取信号数组
-        if let Some(&sell) = sell_signals.last() {
-            if sell {
-                return -1
-            }
-        }
-        0
+        let (vote, reason) = if ubot.is_long() {
+            (1, "价格在跟踪止损线上方（多头）")
+        } else if ubot.is_short() {
+            (-1, "价格在跟踪止损线下方（空头）")
+        } else if ubot.buy_signals().last() == Some(&true) {
+            // 检查最新信号
+            (1, "出现买入信号")
+        } else if ubot.sell_signals().last() == Some(&true) {
+            (-1, "出现卖出信号")
+        } else {
+            (0, "无明确方向")
+        };
+        Signal { vote, strength: vote.abs() as f64, ..signal }.with_reason(reason)
     }
     fn get_name(&self) -> String {
         "UTBOT".to_string()
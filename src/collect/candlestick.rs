@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use log::{error, info};
+use longport::Config;
+use longport::quote::{PushEventDetail, PushEvent, QuoteContext, TradeSessions};
+use tokio::sync::mpsc;
+use crate::indicators::candle::Candle;
+use crate::models::bar::{BarEvent, BarEventKind};
+use crate::services::service::{to_candles, to_period};
+
+/// BarTracker 根据推送的K线判断K线的更新与收盘
+///
+/// 推送带有确认标志时直接视为收盘；没有收到确认但出现了更新的K线时，上一根K线同样视为收盘。
+#[derive(Default)]
+pub struct BarTracker {
+    // (股票代码, 周期) -> (最近一根K线, 是否已收盘)
+    last: HashMap<(String, String), (Candle, bool)>,
+}
+
+impl BarTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一根推送K线，返回需要发出的事件
+    pub fn on_candle(&mut self, symbol: &str, period: &str, candle: Candle, confirmed: bool) -> Vec<BarEvent> {
+        let key = (symbol.to_string(), period.to_string());
+        let mut events = Vec::new();
+        if let Some((prev, closed)) = self.last.get(&key) {
+            // 过期的推送或已收盘K线的重复推送直接忽略
+            if candle.timestamp < prev.timestamp || (candle.timestamp == prev.timestamp && *closed) {
+                return events;
+            }
+            if candle.timestamp > prev.timestamp && !closed {
+                events.push(BarEvent::new(symbol, period, BarEventKind::Closed, prev.clone()));
+            }
+        }
+        events.push(BarEvent::new(symbol, period, BarEventKind::Updated, candle.clone()));
+        if confirmed {
+            events.push(BarEvent::new(symbol, period, BarEventKind::Closed, candle.clone()));
+        }
+        self.last.insert(key, (candle, confirmed));
+        events
+    }
+}
+
+/// CandlestickCollectors 订阅长桥的K线推送，按配置的周期发出K线更新和收盘事件
+pub struct CandlestickCollectors {
+    ctx: QuoteContext, // 行情上下文
+    receiver: mpsc::UnboundedReceiver<PushEvent>, // 接收推送事件的消息接收器
+    subscriptions: Vec<(String, String)>, // 订阅列表 (股票代码, K线周期)
+    tracker: BarTracker,
+}
+
+impl CandlestickCollectors {
+    /// 创建一个新的 CandlestickCollectors 实例
+    ///
+    /// # 参数
+    ///
+    /// * `subscriptions` - 要订阅的 (股票代码, K线周期) 列表
+    pub async fn new(subscriptions: Vec<(String, String)>) -> Self {
+        let config = Arc::new(Config::from_env().unwrap()); // 从环境变量中加载配置
+        let (ctx, receiver) = QuoteContext::try_new(config).await.expect("QuoteContext init err");
+        CandlestickCollectors {
+            ctx,
+            receiver,
+            subscriptions,
+            tracker: BarTracker::new(),
+        }
+    }
+
+    /// 订阅K线推送并把K线事件发送给执行器
+    pub async fn subscribe(&mut self, sender: mpsc::Sender<BarEvent>) {
+        for (symbol, period) in &self.subscriptions {
+            match self.ctx.subscribe_candlesticks(symbol, to_period(period), TradeSessions::All).await {
+                Ok(initial) => {
+                    info!("订阅 {} 的 {} K线推送", symbol, period);
+                    // 用订阅时返回的最新K线初始化状态，以便识别第一根K线的收盘
+                    if let Some(last) = to_candles(symbol, &initial).pop() {
+                        self.tracker.on_candle(symbol, period, last, false);
+                    }
+                }
+                Err(e) => error!("订阅 {} 的 {} K线失败: {:?}", symbol, period, e),
+            }
+        }
+        while let Some(msg) = self.receiver.recv().await {
+            let PushEventDetail::Candlestick(push) = msg.detail else {
+                continue;
+            };
+            let Some((_, period)) = self
+                .subscriptions
+                .iter()
+                .find(|(s, p)| *s == msg.symbol && to_period(p) == push.period)
+            else {
+                continue;
+            };
+            let Some(candle) = to_candles(&msg.symbol, &[push.candlestick]).pop() else {
+                continue;
+            };
+            for event in self.tracker.on_candle(&msg.symbol, period, candle, push.is_confirmed) {
+                if let Err(e) = sender.send(event).await {
+                    error!("Failed to send bar event: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 取消全部K线订阅
+    pub async fn unsubscribe(&mut self) {
+        for (symbol, period) in &self.subscriptions {
+            if let Err(e) = self.ctx.unsubscribe_candlesticks(symbol, to_period(period)).await {
+                error!("取消订阅 {} 的 {} K线失败: {:?}", symbol, period, e);
+            }
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use longport::{Config, quote::{QuoteContext, SubFlags}};
 use std::sync::Arc;
 use log::info;
-use longport::quote::PushEvent;
+use longport::quote::{PushEvent, PushEventDetail};
 use tokio::sync::mpsc;
+use crate::services::pricing::DepthBook;
 
 /// `DepthCollectors` 结构体用于管理深度订阅的上下文和接收器。
 /// 它封装了与长桥API交互的核心逻辑，包括订阅、取消订阅以及接收推送数据。
@@ -11,6 +12,7 @@ pub struct DepthCollectors {
     receiver: mpsc::UnboundedReceiver<PushEvent>, // 接收推送事件的消息接收器
     symbols: Vec<String>, // 当前关注的股票代码列表
     sub_flags: SubFlags, // 订阅标志，指定订阅的数据类型
+    book: Option<Arc<DepthBook>>, // 盘口推送更新到的买一、卖一价（可选）
 }
 
 impl DepthCollectors {
@@ -32,20 +34,29 @@ impl DepthCollectors {
             receiver,
             symbols,
             sub_flags: SubFlags::DEPTH, // 默认订阅深度数据
+            book: None,
         }
     }
+
+    /// 设置盘口，订阅到的深度推送会更新其中的买一、卖一价，供委托定价使用
+    pub fn set_book(&mut self, book: Arc<DepthBook>) {
+        self.book = Some(book);
+    }
     
     /// 订阅指定的股票代码列表。
     /// 
     /// 该方法会调用 `QuoteContext` 的 `subscribe` 方法进行订阅，
-    /// 并通过 `receiver` 接收推送的消息，打印到控制台。
+    /// 并通过 `receiver` 接收推送的消息；设置了盘口时更新盘口，否则打印到控制台。
     /// 
     /// # 注意
     /// 该方法会阻塞当前任务，直到接收器关闭。
     pub async fn subscribe(&mut self) {
         self.ctx.subscribe(&self.symbols, self.sub_flags, true).await.unwrap(); // 订阅深度数据
         while let Some(msg) = self.receiver.recv().await {
-            info!("{:?}", msg); // 处理接收到的推送消息
+            match (&self.book, &msg.detail) {
+                (Some(book), PushEventDetail::Depth(depth)) => book.on_depth(&msg.symbol, depth),
+                _ => info!("{:?}", msg), // 处理接收到的推送消息
+            }
         }
     }
 
@@ -1,3 +1,6 @@
 pub mod quote;
 pub mod depth;
 pub mod brokers;
+pub mod recorder;
+pub mod replayer;
+pub mod candlestick;
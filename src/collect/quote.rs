@@ -3,6 +3,7 @@ use std::sync::Arc;
 use log::{error, info};
 use longport::quote::{PushEvent, PushEventDetail};
 use tokio::sync::mpsc;
+use crate::collect::recorder::QuoteRecorder;
 use crate::models::market::MarketData;
 
 /// QuoteCollector 结构体用于管理行情订阅的上下文和接收器
@@ -11,6 +12,7 @@ pub struct QuoteCollectors {
     receiver: mpsc::UnboundedReceiver<PushEvent>, // 接收推送事件的消息接收器
     symbols: Vec<String>, // 当前关注的股票代码列表
     sub_flags: SubFlags, // 订阅标志，指定订阅的数据类型
+    recorder: Option<Arc<QuoteRecorder>>, // 行情录制器（可选）
 }
 
 impl QuoteCollectors {
@@ -32,13 +34,22 @@ impl QuoteCollectors {
             receiver,
             symbols,
             sub_flags:SubFlags::QUOTE, // 默认订阅报价数据
+            recorder: None,
         }
     }
 
+    /// 设置行情录制器，订阅到的推送和行情都会被记录
+    pub fn set_recorder(&mut self, recorder: Arc<QuoteRecorder>) {
+        self.recorder = Some(recorder);
+    }
+
     /// 订阅当前保存的股票代码的行情数据
     pub async fn subscribe(&mut self, sender: mpsc::Sender<MarketData>) {
         self.ctx.subscribe(&self.symbols, self.sub_flags, true).await.unwrap();
         while let Some(msg) = self.receiver.recv().await {
+            if let Some(recorder) = &self.recorder {
+                recorder.record_raw(&msg);
+            }
             if let PushEventDetail::Quote(detail) = msg.detail {
                 if !detail.clone().last_done.is_zero() {
                     let market_data = MarketData {
@@ -53,6 +64,9 @@ impl QuoteCollectors {
                         ts: detail.timestamp,
                     };
                     // println!("{:?}", market_data.clone());
+                    if let Some(recorder) = &self.recorder {
+                        recorder.record_quote(&market_data);
+                    }
                     if let Err(e) = sender.send(market_data).await {
                         error!("Failed to send market data: {}", e);
                     }
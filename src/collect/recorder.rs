@@ -0,0 +1,87 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use log::error;
+use longport::quote::PushEvent;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use crate::models::market::MarketData;
+
+/// 录制文件中的一条记录，每条记录占一行 JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    /// 转换后发送给策略的行情
+    Quote {
+        /// 收到行情的本地时间（毫秒），回放时按该时间控制节奏
+        at: i64,
+        data: MarketData,
+    },
+    /// 原始推送事件，仅用于排查问题，回放时跳过
+    Raw {
+        at: i64,
+        symbol: String,
+        event: String,
+    },
+}
+
+/// 行情录制器：把每条行情追加写入文件，用于复现某个交易日。
+///
+/// 文件以追加方式打开，每写一行立即落盘，进程异常退出最多丢失最后一行。
+pub struct QuoteRecorder {
+    writer: Mutex<LineWriter<File>>,
+    raw: bool,
+}
+
+impl QuoteRecorder {
+    /// 打开（或创建）录制文件，`raw` 为 true 时同时记录原始推送事件
+    pub fn new(path: &Path, raw: bool) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(QuoteRecorder {
+            writer: Mutex::new(LineWriter::new(file)),
+            raw,
+        })
+    }
+
+    /// 记录一条转换后的行情
+    pub fn record_quote(&self, data: &MarketData) {
+        self.write(&Record::Quote {
+            at: now_millis(),
+            data: data.clone(),
+        });
+    }
+
+    /// 记录一条原始推送事件
+    pub fn record_raw(&self, event: &PushEvent) {
+        if !self.raw {
+            return;
+        }
+        self.write(&Record::Raw {
+            at: now_millis(),
+            symbol: event.symbol.clone(),
+            event: format!("{:?}", event.detail),
+        });
+    }
+
+    fn write(&self, record: &Record) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("序列化行情记录出错: {}", e);
+                return;
+            }
+        };
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{}", line) {
+            error!("写入行情记录出错: {}", e);
+        }
+    }
+}
+
+fn now_millis() -> i64 {
+    (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64
+}
@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use log::warn;
+use tokio::sync::mpsc;
+use crate::collect::recorder::Record;
+use crate::models::market::MarketData;
+
+/// 回放节奏
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pacing {
+    /// 按录制时的时间间隔回放
+    RealTime,
+    /// 按倍速回放，如 10.0 表示十倍速
+    Accelerated(f64),
+    /// 不等待，尽快回放
+    AsFastAsPossible,
+}
+
+impl Pacing {
+    /// 解析命令行参数：`realtime`、`fast` 或倍速数字
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "realtime" => Some(Pacing::RealTime),
+            "fast" => Some(Pacing::AsFastAsPossible),
+            _ => value.parse::<f64>().ok().filter(|s| *s > 0.0).map(Pacing::Accelerated),
+        }
+    }
+
+    /// 录制间隔 `gap` 毫秒对应的等待时间
+    fn wait(&self, gap: i64) -> Option<Duration> {
+        let gap = gap.max(0) as f64 / 1000.0;
+        match self {
+            Pacing::RealTime => Some(Duration::from_secs_f64(gap)),
+            Pacing::Accelerated(speed) => Some(Duration::from_secs_f64(gap / speed)),
+            Pacing::AsFastAsPossible => None,
+        }
+    }
+}
+
+/// 行情回放器：读取 [`QuoteRecorder`](crate::collect::recorder::QuoteRecorder) 的录制文件，
+/// 按录制顺序把行情发送给 `Executor` 使用的通道。
+pub struct QuoteReplayer {
+    records: Vec<Record>,
+}
+
+impl QuoteReplayer {
+    /// 加载录制文件，无法解析的行（如进程中断时写了一半的最后一行）会被跳过
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("读取录制文件 {} 出错: {}", path.display(), e))?;
+        let mut records = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("跳过录制文件第 {} 行: {}", i + 1, e),
+            }
+        }
+        Ok(QuoteReplayer { records })
+    }
+
+    /// 录制的全部行情
+    pub fn quotes(&self) -> impl Iterator<Item = &MarketData> {
+        self.records.iter().filter_map(|r| match r {
+            Record::Quote { data, .. } => Some(data),
+            Record::Raw { .. } => None,
+        })
+    }
+
+    /// 回放全部行情，返回发送的数量；接收端关闭时提前结束
+    pub async fn replay(&self, sender: &mpsc::Sender<MarketData>, pacing: Pacing) -> usize {
+        let mut prev: Option<i64> = None;
+        let mut sent = 0;
+        for record in &self.records {
+            let Record::Quote { at, data } = record else {
+                continue;
+            };
+            if let Some(wait) = prev.and_then(|p| pacing.wait(at - p)) {
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                }
+            }
+            prev = Some(*at);
+            if sender.send(data.clone()).await.is_err() {
+                break;
+            }
+            sent += 1;
+        }
+        sent
+    }
+}
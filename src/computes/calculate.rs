@@ -1,9 +1,44 @@
 use log::info;
+use longport::trade::OrderSide;
+use serde::Serialize;
 use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::signal::Signal;
 use crate::computes::defult_rules::CulRules;
 
+/// 投票决策：规则汇总后的信号，以及参与投票的每个指标信号
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Decision {
+    /// 规则汇总结果：1 买入，-1 卖出，0 不操作
+    pub signal: i64,
+    pub signals: Vec<Signal>,
+}
+
+impl Decision {
+    /// 决策方向
+    pub fn side(&self) -> OrderSide {
+        match self.signal {
+            1 => OrderSide::Buy,
+            -1 => OrderSide::Sell,
+            _ => OrderSide::Unknown,
+        }
+    }
+
+    /// 按名称取出指标信号
+    pub fn signal_of(&self, name: &str) -> Option<&Signal> {
+        self.signals.iter().find(|s| s.name == name)
+    }
+
+    /// 可读的决策说明，每个指标一行
+    pub fn explain(&self) -> String {
+        let mut lines = vec![format!("决策 {:?}（{}）", self.side(), self.signal)];
+        lines.extend(self.signals.iter().map(|s| format!("  {}", s)));
+        lines.join("\n")
+    }
+}
+
 pub struct Calculate {
-    calculators: Vec<Box<dyn BaseCalculate>>,
+    // 指标及其投票权重
+    calculators: Vec<(Box<dyn BaseCalculate>, i64)>,
     rules: Box<dyn CulRules>,
 }
 
@@ -17,19 +52,37 @@ impl Calculate {
     }
 
     pub fn add_calculator(&mut self, calculator: Box<dyn BaseCalculate>) {
-        self.calculators.push(calculator);
+        self.add_weighted_calculator(calculator, 1);
+    }
+
+    /// 添加带权重的指标，指标的投票结果乘以权重后交给规则
+    pub fn add_weighted_calculator(&mut self, calculator: Box<dyn BaseCalculate>, weight: i64) {
+        self.calculators.push((calculator, weight));
+    }
+
+    /// 已添加的指标名称
+    pub fn names(&self) -> Vec<String> {
+        self.calculators.iter().map(|(c, _)| c.get_name()).collect()
+    }
+
+    /// 计算所有指标并按规则汇总，保留每个指标的信号
+    pub fn execute_rules(&self) -> Decision {
+        self.execute_rules_holding(0)
     }
 
-    pub fn execute_rules(&self) -> i64 {
-        // 传递对 calculators 的引用
-        let mut  cul_res = Vec::new();
-        for  calculator in &self.calculators {
+    /// 结合当前持仓方向汇总，`held` 为 1 多头、-1 空头、0 空仓，规则据此区分开仓和平仓信号
+    pub fn execute_rules_holding(&self, held: i64) -> Decision {
+        let mut signals = Vec::new();
+        for (calculator, weight) in &self.calculators {
             let dc= calculator.get_description();
             info!("{}", dc);
-            let cul = calculator.calculate();
-            cul_res.push(cul);
+            let signal = Signal { weight: *weight, ..calculator.signal() };
+            info!("{}", signal);
+            signals.push(signal);
         }
+        let cul_res: Vec<(String, i64)> = signals.iter().map(|s| (s.name.clone(), s.weighted_vote())).collect();
         info!("cul_res: {:?}", cul_res);
-        self.rules.cul_rules(cul_res)
+        let signal = self.rules.cul_position_rules(cul_res, held);
+        Decision { signal, signals }
     }
-}
\ No newline at end of file
+}
@@ -3,29 +3,52 @@ pub trait CulRules {
     where
         Self: Sized;
     fn cul_rules(&self, res: Vec<i64>) -> i64;
+
+    /// 带指标名称的投票，需要区分指标的规则覆盖此方法
+    fn cul_named_rules(&self, res: Vec<(String, i64)>) -> i64 {
+        self.cul_rules(res.into_iter().map(|(_, vote)| vote).collect())
+    }
+
+    /// 结合当前持仓方向的投票，`held` 为 1 多头、-1 空头、0 空仓；需要区分开仓和平仓的规则覆盖此方法
+    fn cul_position_rules(&self, res: Vec<(String, i64)>, _held: i64) -> i64 {
+        self.cul_named_rules(res)
+    }
 }
 
-pub struct DefultRules {}
+/// 投票之和达到阈值时产生买卖信号
+pub(crate) fn threshold_signal(sum: i64, threshold: i64) -> i64 {
+    if sum >= threshold {
+        return 1;
+    }
+    if sum <= -threshold {
+        return -1;
+    }
+    0
+}
+
+/// 默认规则：所有指标投票求和，超过阈值即产生信号
+pub struct DefultRules {
+    pub threshold: i64, // 买卖阈值，默认 ±3
+}
+
+impl Default for DefultRules {
+    fn default() -> Self {
+        DefultRules { threshold: 3 }
+    }
+}
 
 impl CulRules for DefultRules {
     fn create(&self) -> Self
     where
         Self: Sized,
     {
-        DefultRules {}
+        DefultRules { threshold: self.threshold }
     }
     fn cul_rules(&self, res: Vec<i64>) -> i64 {
         let mut cul_res: i64 = 0;
         for i in res.iter() {
             cul_res += i;
         }
-
-        if cul_res >= 3 {
-            return 1;
-        }
-        if cul_res <= -3 {
-            return -1;
-        }
-        0
+        threshold_signal(cul_res, self.threshold)
     }
 }
\ No newline at end of file
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use crate::computes::profile::{IndicatorKind, IndicatorProfile};
+use crate::indicators::candle::Candle;
+use crate::indicators::cyc::CYC;
+use crate::indicators::kdj::KDJ;
+use crate::indicators::macd::MACD;
+use crate::indicators::schaff_trend_cycle::SchaffTrendCycle;
+use crate::indicators::utbot::UTBot;
+
+/// 可以逐根K线增量更新的指标
+#[derive(Clone)]
+pub enum StreamIndicator {
+    Kdj(Arc<KDJ>),
+    Macd(Arc<MACD>),
+    Stc(Arc<SchaffTrendCycle>),
+    Utbot(Arc<UTBot>),
+    Cyc(Arc<CYC>),
+}
+
+impl StreamIndicator {
+    /// 按指标类型创建，不需要K线的指标返回 `None`
+    fn new(kind: &IndicatorKind) -> Option<Self> {
+        Some(match kind {
+            IndicatorKind::Kdj(p) => StreamIndicator::Kdj(Arc::new(KDJ::new(p.k, p.d, p.j))),
+            IndicatorKind::Macd(p) => StreamIndicator::Macd(Arc::new(MACD::new(p.fast, p.slow, p.signal))),
+            IndicatorKind::Stc(p) => StreamIndicator::Stc(Arc::new(SchaffTrendCycle::new(p.length, p.fast, p.slow))),
+            IndicatorKind::Utbot(p) => StreamIndicator::Utbot(Arc::new(UTBot::new(p.key, p.atr, p.heikin))),
+            IndicatorKind::Cyc => StreamIndicator::Cyc(Arc::new(CYC::default())),
+            IndicatorKind::Technicals | IndicatorKind::Market => return None,
+        })
+    }
+
+    // 判断结束后计算器已释放，`make_mut` 直接修改，不会复制历史数据
+    fn update(&mut self, candle: &Candle, revise: bool) {
+        match self {
+            StreamIndicator::Kdj(i) => if revise { Arc::make_mut(i).update_last(candle) } else { Arc::make_mut(i).update(candle) },
+            StreamIndicator::Macd(i) => if revise { Arc::make_mut(i).update_last(candle) } else { Arc::make_mut(i).update(candle) },
+            StreamIndicator::Stc(i) => if revise { Arc::make_mut(i).update_last(candle) } else { Arc::make_mut(i).update(candle) },
+            StreamIndicator::Utbot(i) => if revise { Arc::make_mut(i).update_last(candle) } else { Arc::make_mut(i).update(candle) },
+            StreamIndicator::Cyc(i) => if revise { Arc::make_mut(i).update_last(candle) } else { Arc::make_mut(i).update(candle) },
+        }
+    }
+}
+
+/// 单个股票、周期的指标状态：每次判断前用最新K线增量推进，不再对整段K线重新计算。
+///
+/// 只处理上次之后的新K线，时间戳相同的最后一根K线有变化时修正；
+/// 指标组合变化或K线中找不到上次处理到的K线时从头计算。
+/// 每次拉取的K线数量固定时，指标包含窗口之前已经处理过的K线，递推指标（EMA 等）不会随窗口滑动而重新预热。
+#[derive(Default)]
+pub struct IndicatorState {
+    kinds: Vec<IndicatorKind>,
+    indicators: Vec<Option<StreamIndicator>>,
+    last: Option<Candle>,
+}
+
+impl IndicatorState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 用最新的K线（按时间升序）推进指标组合 `profile` 中基于K线的指标
+    pub fn sync(&mut self, profile: &IndicatorProfile, candles: &[Candle]) {
+        let kinds = kinds_of(profile);
+        if kinds != self.kinds {
+            self.reset(kinds);
+        }
+        let mut start = 0;
+        if let Some(last) = self.last.clone() {
+            match candles.iter().rposition(|c| c.timestamp == last.timestamp) {
+                Some(i) => {
+                    if !same_candle(&last, &candles[i]) {
+                        self.feed(&candles[i], true);
+                    }
+                    start = i + 1;
+                }
+                None => self.reset(kinds_of(profile)),
+            }
+        }
+        for candle in &candles[start..] {
+            self.feed(candle, false);
+        }
+    }
+
+    /// 已推进到的最后一根K线时间戳
+    pub fn last_timestamp(&self) -> Option<u64> {
+        self.last.as_ref().map(|c| c.timestamp)
+    }
+
+    /// 指标类型对应的增量指标
+    pub fn get(&self, kind: &IndicatorKind) -> Option<StreamIndicator> {
+        let index = self.kinds.iter().position(|k| k == kind)?;
+        self.indicators[index].clone()
+    }
+
+    fn reset(&mut self, kinds: Vec<IndicatorKind>) {
+        self.indicators = kinds.iter().map(StreamIndicator::new).collect();
+        self.kinds = kinds;
+        self.last = None;
+    }
+
+    fn feed(&mut self, candle: &Candle, revise: bool) {
+        for indicator in self.indicators.iter_mut().flatten() {
+            indicator.update(candle, revise);
+        }
+        self.last = Some(candle.clone());
+    }
+}
+
+fn kinds_of(profile: &IndicatorProfile) -> Vec<IndicatorKind> {
+    profile.indicators.iter().map(|i| i.kind.clone()).collect()
+}
+
+fn same_candle(a: &Candle, b: &Candle) -> bool {
+    (a.open, a.high, a.low, a.close, a.volume) == (b.open, b.high, b.low, b.close, b.volume)
+}
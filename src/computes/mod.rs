@@ -1,2 +1,6 @@
 pub mod defult_rules;
+pub mod indicator_state;
 pub mod calculate;
+pub mod params;
+pub mod profile;
+pub mod rules;
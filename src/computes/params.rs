@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use crate::computes::calculate::Calculate;
+use crate::computes::rules::RuleConfig;
+use crate::computes::profile::{IndicatorConfig, IndicatorKind, IndicatorProfile, KdjParams, MacdParams, StcParams, UtBotParams};
+use crate::indicators::candle::Candle;
+
+/// CYC 长期周期，计算预热长度时使用
+pub(crate) const CYC_LONG_PERIOD: usize = 34;
+
+/// 基于K线计算的指标参数，默认值与策略原先写死的参数一致
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndicatorParams {
+    pub kdj_k: usize,       // KDJ RSV 周期
+    pub kdj_d: usize,       // KDJ D 值平滑系数
+    pub kdj_j: usize,       // KDJ J 值系数
+    pub macd_fast: usize,   // MACD 快线周期
+    pub macd_slow: usize,   // MACD 慢线周期
+    pub macd_signal: usize, // MACD 信号线周期
+    pub stc_length: usize,  // STC 随机指标周期
+    pub stc_fast: usize,    // STC 快速 EMA 周期
+    pub stc_slow: usize,    // STC 慢速 EMA 周期
+    pub utbot_key: f64,     // UTBot 灵敏度系数
+    pub utbot_atr: usize,   // UTBot ATR 周期
+    pub utbot_heikin: bool, // UTBot 是否使用平均K线
+    pub threshold: i64,     // 投票买卖阈值
+}
+
+impl Default for IndicatorParams {
+    fn default() -> Self {
+        IndicatorParams {
+            kdj_k: 9,
+            kdj_d: 3,
+            kdj_j: 3,
+            macd_fast: 5,
+            macd_slow: 10,
+            macd_signal: 5,
+            stc_length: 12,
+            stc_fast: 26,
+            stc_slow: 50,
+            utbot_key: 1.0,
+            utbot_atr: 10,
+            utbot_heikin: false,
+            threshold: 3,
+        }
+    }
+}
+
+impl IndicatorParams {
+    /// 参数是否有效（快线周期必须小于慢线周期）
+    pub fn is_valid(&self) -> bool {
+        self.macd_fast < self.macd_slow && self.stc_fast < self.stc_slow && self.threshold > 0
+    }
+
+    /// 产生有效信号所需的最少K线数量
+    pub fn warmup(&self) -> usize {
+        [
+            self.kdj_k,
+            self.macd_slow + self.macd_signal,
+            self.stc_length.max(self.stc_slow),
+            self.utbot_atr,
+            CYC_LONG_PERIOD,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or_default()
+            + 1
+    }
+
+    /// 转换为对应的指标组合：KDJ、MACD、STC、UTBot、CYC
+    pub fn profile(&self) -> IndicatorProfile {
+        IndicatorProfile {
+            threshold: self.threshold,
+            rules: RuleConfig::Sum,
+            indicators: vec![
+                IndicatorConfig::new(IndicatorKind::Kdj(KdjParams { k: self.kdj_k, d: self.kdj_d, j: self.kdj_j })),
+                IndicatorConfig::new(IndicatorKind::Macd(MacdParams {
+                    fast: self.macd_fast,
+                    slow: self.macd_slow,
+                    signal: self.macd_signal,
+                })),
+                IndicatorConfig::new(IndicatorKind::Stc(StcParams {
+                    length: self.stc_length,
+                    fast: self.stc_fast,
+                    slow: self.stc_slow,
+                })),
+                IndicatorConfig::new(IndicatorKind::Utbot(UtBotParams {
+                    key: self.utbot_key,
+                    atr: self.utbot_atr,
+                    heikin: self.utbot_heikin,
+                })),
+                IndicatorConfig::new(IndicatorKind::Cyc),
+            ],
+        }
+    }
+
+    /// 按参数创建 KDJ、MACD、STC、UTBot、CYC 指标计算
+    pub fn calculate(&self, candles: &[Candle]) -> Calculate {
+        self.profile().build(candles, None, None)
+    }
+}
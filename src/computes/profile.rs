@@ -0,0 +1,252 @@
+use std::sync::Arc;
+use log::warn;
+use longport::quote::MarketTemperature;
+use serde::{Deserialize, Serialize};
+use crate::calculates::base_calculate::BaseCalculate;
+use crate::calculates::cyc_calculate::CycCalculate;
+use crate::calculates::kdj_calculate::KdjCalculate;
+use crate::calculates::macd_calculate::MacdCalculate;
+use crate::calculates::market_calculate::MarketCalculate;
+use crate::calculates::stc_calculate::STCCalculate;
+use crate::calculates::technicals_calculate::TechnicalsCalculate;
+use crate::calculates::utbot_calculate::UTBotCalculate;
+use crate::computes::calculate::Calculate;
+use crate::computes::defult_rules::DefultRules;
+use crate::computes::indicator_state::{IndicatorState, StreamIndicator};
+use crate::computes::rules::RuleConfig;
+use crate::computes::params::{IndicatorParams, CYC_LONG_PERIOD};
+use crate::indicators::candle::Candle;
+use crate::indicators::tradingview_technicals::TradingTechnicals;
+
+/// KDJ 参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KdjParams {
+    pub k: usize, // RSV 周期
+    pub d: usize, // D 值平滑系数
+    pub j: usize, // J 值系数
+}
+
+impl Default for KdjParams {
+    fn default() -> Self {
+        KdjParams { k: 9, d: 3, j: 3 }
+    }
+}
+
+/// MACD 参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MacdParams {
+    pub fast: usize,   // 快线周期
+    pub slow: usize,   // 慢线周期
+    pub signal: usize, // 信号线周期
+}
+
+impl Default for MacdParams {
+    fn default() -> Self {
+        MacdParams { fast: 5, slow: 10, signal: 5 }
+    }
+}
+
+/// STC 参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StcParams {
+    pub length: usize, // 随机指标周期
+    pub fast: usize,   // 快速 EMA 周期
+    pub slow: usize,   // 慢速 EMA 周期
+}
+
+impl Default for StcParams {
+    fn default() -> Self {
+        StcParams { length: 12, fast: 26, slow: 50 }
+    }
+}
+
+/// UTBot 参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UtBotParams {
+    pub key: f64,     // 灵敏度系数
+    pub atr: usize,   // ATR 周期
+    pub heikin: bool, // 是否使用平均K线
+}
+
+impl Default for UtBotParams {
+    fn default() -> Self {
+        UtBotParams { key: 1.0, atr: 10, heikin: false }
+    }
+}
+
+/// 指标类型，配置中用 `kind` 区分，参数未配置时使用默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IndicatorKind {
+    Kdj(KdjParams),
+    Macd(MacdParams),
+    Stc(StcParams),
+    Utbot(UtBotParams),
+    Cyc,
+    /// TradingView 技术评级，需要联网获取
+    Technicals,
+    /// 市场温度，需要从行情数据来源获取
+    Market,
+}
+
+/// 单个指标的配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndicatorConfig {
+    #[serde(flatten)]
+    pub kind: IndicatorKind,
+    #[serde(default = "default_weight")]
+    pub weight: i64, // 投票权重，指标结果乘以权重后参与投票
+}
+
+fn default_weight() -> i64 {
+    1
+}
+
+impl IndicatorKind {
+    /// 产生有效信号所需的最少K线数量，不需要K线的指标为 0
+    pub fn warmup(&self) -> usize {
+        match self {
+            IndicatorKind::Kdj(p) => p.k + 1,
+            IndicatorKind::Macd(p) => p.slow + p.signal + 1,
+            IndicatorKind::Stc(p) => p.length.max(p.slow) + 1,
+            IndicatorKind::Utbot(p) => p.atr + 1,
+            IndicatorKind::Cyc => CYC_LONG_PERIOD + 1,
+            IndicatorKind::Technicals | IndicatorKind::Market => 0,
+        }
+    }
+}
+
+impl IndicatorConfig {
+    pub fn new(kind: IndicatorKind) -> Self {
+        IndicatorConfig { kind, weight: default_weight() }
+    }
+}
+
+/// 指标组合：参与投票的指标、投票规则及买卖阈值
+///
+/// 可以在配置的 `profiles` 中命名后由多个股票引用，也可以直接写在股票配置里。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndicatorProfile {
+    #[serde(default = "default_threshold")]
+    pub threshold: i64, // 投票买卖阈值
+    #[serde(default)]
+    pub rules: RuleConfig, // 投票规则，默认求和
+    pub indicators: Vec<IndicatorConfig>,
+}
+
+fn default_threshold() -> i64 {
+    DefultRules::default().threshold
+}
+
+impl Default for IndicatorProfile {
+    /// 默认组合与策略原先写死的六个指标一致
+    fn default() -> Self {
+        let mut profile = IndicatorParams::default().profile();
+        profile.indicators.push(IndicatorConfig::new(IndicatorKind::Technicals));
+        profile
+    }
+}
+
+impl IndicatorProfile {
+    /// 是否需要 TradingView 技术评级
+    pub fn needs_technicals(&self) -> bool {
+        self.indicators.iter().any(|i| i.kind == IndicatorKind::Technicals)
+    }
+
+    /// 是否需要市场温度
+    pub fn needs_market(&self) -> bool {
+        self.indicators.iter().any(|i| i.kind == IndicatorKind::Market)
+    }
+
+    /// 组合中所有指标产生有效信号所需的最少K线数量
+    pub fn warmup(&self) -> usize {
+        self.indicators.iter().map(|i| i.kind.warmup()).max().unwrap_or_default()
+    }
+
+    /// 按组合创建指标计算，需要外部数据的指标在数据缺失时跳过
+    pub fn build(
+        &self,
+        candles: &[Candle],
+        technicals: Option<TradingTechnicals>,
+        market: Option<MarketTemperature>,
+    ) -> Calculate {
+        self.build_with_state(None, candles, technicals, market)
+    }
+
+    /// 与 [`build`](Self::build) 相同，`state` 中已增量计算好的指标直接使用，不再重新计算。
+    ///
+    /// `state` 需要先用同一组K线 [`sync`](IndicatorState::sync)。
+    pub fn build_with_state(
+        &self,
+        state: Option<&IndicatorState>,
+        candles: &[Candle],
+        technicals: Option<TradingTechnicals>,
+        market: Option<MarketTemperature>,
+    ) -> Calculate {
+        // 所有指标共享同一份K线
+        let candles = Arc::new(candles.to_vec());
+        let mut calculate = Calculate::new(self.rules.build(self.threshold));
+        for indicator in &self.indicators {
+            let stream = state.and_then(|s| s.get(&indicator.kind));
+            let calculator: Box<dyn BaseCalculate> = match (&indicator.kind, stream) {
+                (IndicatorKind::Kdj(p), stream) => {
+                    let calc = KdjCalculate::with_params(candles.clone(), p.k, p.d, p.j);
+                    match stream {
+                        Some(StreamIndicator::Kdj(kdj)) => Box::new(calc.with_indicator(kdj)),
+                        _ => Box::new(calc),
+                    }
+                }
+                (IndicatorKind::Macd(p), stream) => {
+                    let calc = MacdCalculate::with_params(candles.clone(), p.fast, p.slow, p.signal);
+                    match stream {
+                        Some(StreamIndicator::Macd(macd)) => Box::new(calc.with_indicator(macd)),
+                        _ => Box::new(calc),
+                    }
+                }
+                (IndicatorKind::Stc(p), stream) => {
+                    let calc = STCCalculate::with_params(candles.clone(), p.length, p.fast, p.slow);
+                    match stream {
+                        Some(StreamIndicator::Stc(stc)) => Box::new(calc.with_indicator(stc)),
+                        _ => Box::new(calc),
+                    }
+                }
+                (IndicatorKind::Utbot(p), stream) => {
+                    let calc = UTBotCalculate::with_params(candles.clone(), p.key, p.atr, p.heikin);
+                    match stream {
+                        Some(StreamIndicator::Utbot(ubot)) => Box::new(calc.with_indicator(ubot)),
+                        _ => Box::new(calc),
+                    }
+                }
+                (IndicatorKind::Cyc, stream) => {
+                    let calc = CycCalculate::new(candles.clone());
+                    match stream {
+                        Some(StreamIndicator::Cyc(cyc)) => Box::new(calc.with_indicator(cyc)),
+                        _ => Box::new(calc),
+                    }
+                }
+                (IndicatorKind::Technicals, _) => match &technicals {
+                    Some(technicals) => Box::new(TechnicalsCalculate { technicals: technicals.clone() }),
+                    None => {
+                        warn!("缺少 TradingView 技术评级，跳过该指标");
+                        continue;
+                    }
+                },
+                (IndicatorKind::Market, _) => match &market {
+                    Some(market) => Box::new(MarketCalculate { market: market.clone() }),
+                    None => {
+                        warn!("缺少市场温度，跳过该指标");
+                        continue;
+                    }
+                },
+            };
+            // 投票规则按名称配置的权重优先，只在这里乘一次
+            let weight = self.rules.weight_of(&calculator.get_name()).unwrap_or(indicator.weight);
+            calculate.add_weighted_calculator(calculator, weight);
+        }
+        calculate
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use log::info;
+use serde::{Deserialize, Serialize};
+use crate::computes::defult_rules::{threshold_signal, CulRules, DefultRules};
+
+/// 多数表决：超过半数的指标看多（看空）时产生信号，不看投票大小
+pub struct MajorityRules;
+
+impl CulRules for MajorityRules {
+    fn create(&self) -> Self
+    where
+        Self: Sized,
+    {
+        MajorityRules
+    }
+
+    fn cul_rules(&self, res: Vec<i64>) -> i64 {
+        let buys = res.iter().filter(|v| **v > 0).count();
+        let sells = res.iter().filter(|v| **v < 0).count();
+        if buys * 2 > res.len() {
+            return 1;
+        }
+        if sells * 2 > res.len() {
+            return -1;
+        }
+        0
+    }
+}
+
+/// 一致表决：所有指标方向相同时才产生信号
+pub struct UnanimityRules;
+
+impl CulRules for UnanimityRules {
+    fn create(&self) -> Self
+    where
+        Self: Sized,
+    {
+        UnanimityRules
+    }
+
+    fn cul_rules(&self, res: Vec<i64>) -> i64 {
+        if res.is_empty() {
+            return 0;
+        }
+        if res.iter().all(|v| *v > 0) {
+            return 1;
+        }
+        if res.iter().all(|v| *v < 0) {
+            return -1;
+        }
+        0
+    }
+}
+
+/// 法定票数：至少 `min_votes` 个指标给出非零投票时，才按阈值判断投票之和
+pub struct QuorumRules {
+    pub min_votes: usize,
+    pub threshold: i64,
+}
+
+impl CulRules for QuorumRules {
+    fn create(&self) -> Self
+    where
+        Self: Sized,
+    {
+        QuorumRules { min_votes: self.min_votes, threshold: self.threshold }
+    }
+
+    fn cul_rules(&self, res: Vec<i64>) -> i64 {
+        if res.iter().filter(|v| **v != 0).count() < self.min_votes {
+            return 0;
+        }
+        threshold_signal(res.iter().sum(), self.threshold)
+    }
+}
+
+/// 否决：先由内部规则给出信号，指定指标的投票方向与信号相反时取消开仓信号
+///
+/// 例如市场温度过热（看空）时否决买入。只否决开仓，平多的卖出和平空的买入不受影响。
+pub struct VetoRules {
+    pub vetoes: Vec<String>,
+    pub inner: Arc<dyn CulRules + Send + Sync>,
+}
+
+impl CulRules for VetoRules {
+    fn create(&self) -> Self
+    where
+        Self: Sized,
+    {
+        VetoRules { vetoes: self.vetoes.clone(), inner: self.inner.clone() }
+    }
+
+    fn cul_rules(&self, res: Vec<i64>) -> i64 {
+        self.inner.cul_rules(res)
+    }
+
+    fn cul_named_rules(&self, res: Vec<(String, i64)>) -> i64 {
+        self.cul_position_rules(res, 0)
+    }
+
+    fn cul_position_rules(&self, res: Vec<(String, i64)>, held: i64) -> i64 {
+        let signal = self.inner.cul_position_rules(res.clone(), held);
+        // 与持仓方向相反的信号用于平仓，不否决
+        if signal == 0 || signal == -held.signum() {
+            return signal;
+        }
+        let vetoed = res
+            .iter()
+            .find(|(name, vote)| self.vetoes.contains(name) && vote.signum() == -signal);
+        if let Some((name, vote)) = vetoed {
+            info!("{} 投票 {} 否决了信号 {}", name, vote, signal);
+            return 0;
+        }
+        signal
+    }
+}
+
+/// 投票规则配置，`type` 指定规则类型，阈值使用指标组合的 `threshold`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleConfig {
+    /// 投票求和，与原先的默认规则一致
+    #[default]
+    Sum,
+    /// 按指标名称配置权重后求和，配置的权重代替指标的 `weight`，未配置的指标沿用 `weight`
+    Weighted {
+        #[serde(default)]
+        weights: HashMap<String, i64>,
+    },
+    /// 多数表决
+    Majority,
+    /// 一致表决
+    Unanimity,
+    /// 非零投票数达到 `min_votes` 后再求和
+    Quorum { min_votes: usize },
+    /// 在内部规则之上由指定指标否决
+    Veto {
+        vetoes: Vec<String>,
+        #[serde(default)]
+        inner: Box<RuleConfig>,
+    },
+}
+
+impl RuleConfig {
+    /// 按配置创建规则
+    pub fn build(&self, threshold: i64) -> Box<dyn CulRules + Send + Sync> {
+        match self {
+            RuleConfig::Sum => Box::new(DefultRules { threshold }),
+            // 权重在指标计算时已经乘到投票上，这里只求和
+            RuleConfig::Weighted { .. } => Box::new(DefultRules { threshold }),
+            RuleConfig::Majority => Box::new(MajorityRules),
+            RuleConfig::Unanimity => Box::new(UnanimityRules),
+            RuleConfig::Quorum { min_votes } => Box::new(QuorumRules { min_votes: *min_votes, threshold }),
+            RuleConfig::Veto { vetoes, inner } => Box::new(VetoRules {
+                vetoes: vetoes.clone(),
+                inner: Arc::from(inner.build(threshold)),
+            }),
+        }
+    }
+
+    /// 规则为指标 `name` 配置的权重，未配置时返回 `None`
+    pub fn weight_of(&self, name: &str) -> Option<i64> {
+        match self {
+            RuleConfig::Weighted { weights } => weights.get(name).copied(),
+            RuleConfig::Veto { inner, .. } => inner.weight_of(name),
+            _ => None,
+        }
+    }
+}
@@ -1,9 +1,16 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
 use longport::Config;
-use std::error::Error;
+use crate::utils::error::AqtError;
+use crate::computes::params::IndicatorParams;
+use crate::computes::profile::{IndicatorConfig, IndicatorKind, IndicatorProfile, UtBotParams};
+use crate::computes::rules::RuleConfig;
+use crate::services::pricing::PricingPolicy;
+use crate::strategys::sizer::SizerConfig;
+use log::warn;
 
 // 新增: SymbolConfig 结构体，用于描述每个股票的配置
 #[derive(Debug, Deserialize,Clone)]
@@ -14,6 +21,71 @@ pub struct SymbolConfig {
     pub period: String,   // K线级别
     pub tp_ratio: i32,        // 止盈比例
     pub sl_ratio: i32,        // 止损比例
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: i64,   // 下单后的冷却时间（秒），期间不再开仓，默认 10 小时
+    #[serde(default)]
+    pub allow_short: bool,    // 是否允许做空：卖出信号在没有多头持仓时开空，买入信号平空
+    #[serde(default)]
+    pub profile: Option<String>, // 引用 `profiles` 中的指标组合名称
+    #[serde(default)]
+    pub indicators: Vec<IndicatorConfig>, // 直接配置的指标列表，优先于 `profile`
+    #[serde(default)]
+    pub threshold: Option<i64>, // 投票买卖阈值，覆盖指标组合中的值
+    #[serde(default)]
+    pub rules: Option<RuleConfig>, // 投票规则，覆盖指标组合中的值
+    #[serde(default)]
+    pub trend: Option<TrendFilterConfig>, // 高级别周期趋势过滤，配置后只在趋势向上时买入
+    #[serde(default)]
+    pub exit: ExitConfig,     // 跟踪止损、保本和最长持仓时间
+    #[serde(default)]
+    pub sizer: SizerConfig,   // 仓位模型，默认按 `volume` 比例开仓
+    #[serde(default)]
+    pub groups: Vec<String>,  // 所属分组或板块，如 tech、semis，用于集中度限制
+    #[serde(default)]
+    pub pricing: PricingPolicy, // 委托定价方式，默认按现价上下浮动 5% 限价委托
+    #[serde(default)]
+    pub tick_size: Option<f64>, // 最小价格变动单位，默认按市场和价格区间确定
+}
+
+/// 平仓配置，固定止损使用 `sl_ratio`，以下规则未配置时不启用
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ExitConfig {
+    #[serde(default)]
+    pub trailing: Option<UtBotParams>,    // UTBot（ATR）跟踪止损参数
+    #[serde(default)]
+    pub breakeven_ratio: Option<f64>,     // 盈利达到该百分比后，回落到成本价即平仓
+    #[serde(default)]
+    pub max_holding_secs: Option<u64>,    // 最长持仓时间（秒）
+}
+
+/// 高级别周期趋势过滤：入场周期给出买入信号时，还要求高级别周期的指标同样看多
+#[derive(Debug, Deserialize, Clone)]
+pub struct TrendFilterConfig {
+    pub period: String,                   // 高级别K线周期，如 1d
+    #[serde(default)]
+    pub indicators: Vec<IndicatorConfig>, // 判断趋势的指标，默认 UTBot
+    #[serde(default = "default_trend_threshold")]
+    pub threshold: i64,                   // 投票阈值
+    #[serde(default)]
+    pub rules: RuleConfig,                // 投票规则，默认求和
+    #[serde(default)]
+    pub aggregate: bool,                  // 由入场周期K线聚合得到，而不是单独获取高级别K线
+}
+
+fn default_trend_threshold() -> i64 {
+    1
+}
+
+impl TrendFilterConfig {
+    /// 判断趋势使用的指标组合
+    pub fn profile(&self) -> IndicatorProfile {
+        let indicators = if self.indicators.is_empty() {
+            vec![IndicatorConfig::new(IndicatorKind::Utbot(Default::default()))]
+        } else {
+            self.indicators.clone()
+        };
+        IndicatorProfile { threshold: self.threshold, rules: self.rules.clone(), indicators }
+    }
 }
 
 impl SymbolConfig {
@@ -25,27 +97,376 @@ impl SymbolConfig {
             period: "".to_string(),
             tp_ratio: 0,
             sl_ratio: 0,
+            cooldown_secs: default_cooldown_secs(),
+            allow_short: false,
+            profile: None,
+            indicators: Vec::new(),
+            threshold: None,
+            rules: None,
+            trend: None,
+            exit: ExitConfig::default(),
+            sizer: SizerConfig::default(),
+            groups: Vec::new(),
+            pricing: PricingPolicy::default(),
+            tick_size: None,
         }
     }
+
+    /// 该股票使用的指标组合：直接配置的指标 > 引用的组合 > 默认组合，
+    /// 配置了 `threshold`、`rules` 时覆盖组合中的值
+    pub fn indicator_profile(&self, profiles: &HashMap<String, IndicatorProfile>) -> IndicatorProfile {
+        let mut profile = if !self.indicators.is_empty() {
+            IndicatorProfile { indicators: self.indicators.clone(), ..Default::default() }
+        } else {
+            match &self.profile {
+                Some(name) => profiles.get(name).cloned().unwrap_or_else(|| {
+                    warn!("{} 引用的指标组合 {} 不存在，使用默认组合", self.symbol, name);
+                    IndicatorProfile::default()
+                }),
+                None => IndicatorProfile::default(),
+            }
+        };
+        if let Some(threshold) = self.threshold {
+            profile.threshold = threshold;
+        }
+        if let Some(rules) = &self.rules {
+            profile.rules = rules.clone();
+        }
+        profile
+    }
+}
+
+/// 策略实例配置，一个进程可以同时运行多个策略，每个策略负责自己的股票并使用各自的参数
+#[derive(Debug, Deserialize, Clone)]
+pub struct StrategyConfig {
+    pub name: String,                   // 策略名称，用于日志和启停
+    #[serde(default = "default_strategy")]
+    pub strategy: String,               // 策略类型，对应注册表中的名称，默认 vecor_v1
+    #[serde(default)]
+    pub params: serde_yaml::Value,      // 策略参数，由具体策略解析
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,                  // 是否启动该策略
+    pub symbols: Vec<SymbolConfig>,     // 该策略负责的股票配置
+}
+
+fn default_cooldown_secs() -> i64 {
+    10 * 60 * 60
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_strategy() -> String {
+    "vecor_v1".to_string()
+}
+
+/// 模拟盘配置，配置后策略将通过 `PaperBroker` 下单而不是真实账户
+#[derive(Debug, Deserialize, Clone)]
+pub struct PaperConfig {
+    pub initial_cash: f64,              // 初始资金
+    #[serde(default = "default_currency")]
+    pub currency: String,               // 资金币种
+    pub participation: Option<f64>,     // 每笔行情最多可成交的成交量占比，不配置则一次全部成交
+    #[serde(default)]
+    pub short_margin: Option<f64>,      // 做空保证金比例（如 0.5），配置后允许做空
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// 回测配置
+#[derive(Debug, Deserialize, Clone)]
+pub struct BacktestConfig {
+    pub data_dir: String,               // K线数据目录，文件名为 `{symbol}_{period}.csv`
+    pub initial_cash: f64,              // 初始资金
+    #[serde(default = "default_currency")]
+    pub currency: String,               // 资金币种
+    pub participation: Option<f64>,     // 每根K线最多可成交的成交量占比
+    #[serde(default)]
+    pub short_margin: Option<f64>,      // 做空保证金比例，配置后允许做空
+    #[serde(default = "default_output_dir")]
+    pub output_dir: String,             // 回测报告输出目录
+}
+
+fn default_output_dir() -> String {
+    "backtest_output".to_string()
+}
+
+/// 本地K线存储配置
+#[derive(Debug, Deserialize, Clone)]
+pub struct StoreConfig {
+    #[serde(default = "default_store_dir")]
+    pub dir: String,                    // K线文件目录，文件名为 `{symbol}_{period}.csv`
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        StoreConfig { dir: default_store_dir() }
+    }
+}
+
+fn default_store_dir() -> String {
+    "data".to_string()
+}
+
+/// 行情录制配置，配置后实盘/模拟盘收到的行情会写入 `{dir}/quotes-{日期}.jsonl`
+#[derive(Debug, Deserialize, Clone)]
+pub struct RecorderConfig {
+    #[serde(default = "default_recorder_dir")]
+    pub dir: String,                    // 录制文件目录
+    #[serde(default)]
+    pub raw: bool,                      // 是否同时记录原始推送事件
+}
+
+fn default_recorder_dir() -> String {
+    "recordings".to_string()
+}
+
+/// 下单前风控限制，金额以 `currency` 计价，未配置的限制不检查
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RiskConfig {
+    pub max_order_notional: Option<f64>,    // 单笔订单最大金额
+    pub max_symbol_notional: Option<f64>,   // 成交后单个股票最大持仓金额
+    pub max_gross_exposure: Option<f64>,    // 成交后多空持仓总金额上限
+    pub max_positions: Option<usize>,       // 最多同时持有的股票数量
+    pub max_daily_orders: Option<usize>,    // 每个股票每天最多下单次数
+    pub max_daily_loss: Option<f64>,        // 当日最大亏损金额（已实现 + 未实现）
+    pub group_limits: HashMap<String, f64>, // 成交后每个分组（股票配置的 `groups`）的持仓金额上限
+    pub max_correlation: Option<f64>,       // 新开仓与现有持仓收益率的平均相关系数上限
+    pub correlation_window: usize,          // 计算相关系数的收益率个数
+    pub correlation_period: String,         // 计算收益率的K线周期
+    pub currency: String,                   // 计算当日亏损使用的账户币种
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        RiskConfig {
+            max_order_notional: None,
+            max_symbol_notional: None,
+            max_gross_exposure: None,
+            max_positions: None,
+            max_daily_orders: None,
+            max_daily_loss: None,
+            group_limits: HashMap::new(),
+            max_correlation: None,
+            correlation_window: 30,
+            correlation_period: "1d".to_string(),
+            currency: default_currency(),
+        }
+    }
+}
+
+/// 熔断配置：当日净资产回撤或连续下单失败超过阈值后停止开仓，
+/// 熔断原因写入 `latch_file`，删除该文件（`aqt-manager.sh reset-breaker`）后恢复交易
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct BreakerConfig {
+    pub max_drawdown: Option<f64>,             // 当日净资产从高点回撤的百分比
+    pub max_consecutive_failures: Option<u32>, // 连续下单失败次数
+    pub cancel_orders: bool,                   // 熔断时撤销未完成订单
+    pub flatten: bool,                         // 熔断时平掉全部持仓
+    pub check_interval_secs: u64,              // 定期检查净资产的间隔（秒）
+    pub latch_file: String,                    // 熔断标记文件
+    pub currency: String,                      // 计算净资产使用的账户币种
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        BreakerConfig {
+            max_drawdown: None,
+            max_consecutive_failures: None,
+            cancel_orders: false,
+            flatten: false,
+            check_interval_secs: 60,
+            latch_file: "circuit_breaker.latch".to_string(),
+            currency: default_currency(),
+        }
+    }
+}
+
+/// 策略状态持久化配置，配置后每个策略的状态保存到 `{dir}/{策略名称}.json`，重启后恢复
+#[derive(Debug, Deserialize, Clone)]
+pub struct StateConfig {
+    #[serde(default = "default_state_dir")]
+    pub dir: String,                    // 状态文件目录
+}
+
+fn default_state_dir() -> String {
+    "state".to_string()
+}
+
+/// 参数搜索方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    #[default]
+    Grid,   // 网格搜索，遍历全部组合
+    Random, // 随机搜索，从网格中抽样
+}
+
+/// 参数排名使用的指标
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OptimizeMetric {
+    #[default]
+    Sharpe,
+    TotalReturn,
+    Calmar, // 总收益 / 最大回撤
+}
+
+/// 参数网格，每个参数给出候选值列表，未配置的参数使用策略默认值
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ParamGrid {
+    pub kdj_k: Vec<usize>,
+    pub kdj_d: Vec<usize>,
+    pub kdj_j: Vec<usize>,
+    pub macd_fast: Vec<usize>,
+    pub macd_slow: Vec<usize>,
+    pub macd_signal: Vec<usize>,
+    pub stc_length: Vec<usize>,
+    pub stc_fast: Vec<usize>,
+    pub stc_slow: Vec<usize>,
+    pub utbot_key: Vec<f64>,
+    pub utbot_atr: Vec<usize>,
+    pub utbot_heikin: Vec<bool>,
+    pub threshold: Vec<i64>,
+}
+
+impl Default for ParamGrid {
+    fn default() -> Self {
+        let p = IndicatorParams::default();
+        ParamGrid {
+            kdj_k: vec![p.kdj_k],
+            kdj_d: vec![p.kdj_d],
+            kdj_j: vec![p.kdj_j],
+            macd_fast: vec![p.macd_fast],
+            macd_slow: vec![p.macd_slow],
+            macd_signal: vec![p.macd_signal],
+            stc_length: vec![p.stc_length],
+            stc_fast: vec![p.stc_fast],
+            stc_slow: vec![p.stc_slow],
+            utbot_key: vec![p.utbot_key],
+            utbot_atr: vec![p.utbot_atr],
+            utbot_heikin: vec![p.utbot_heikin],
+            threshold: vec![p.threshold],
+        }
+    }
+}
+
+/// 参数优化配置，K线数据与初始资金沿用 `backtest` 配置
+#[derive(Debug, Deserialize, Clone)]
+pub struct OptimizerConfig {
+    #[serde(default)]
+    pub grid: ParamGrid,                // 参数网格
+    #[serde(default)]
+    pub search: SearchMode,             // 搜索方式
+    #[serde(default = "default_samples")]
+    pub samples: usize,                 // 随机搜索的抽样数量
+    #[serde(default)]
+    pub seed: u64,                      // 随机搜索的种子
+    #[serde(default)]
+    pub metric: OptimizeMetric,         // 排名指标
+    pub in_sample: Option<usize>,       // 滚动验证的样本内K线数量，不配置则只做全样本排名
+    pub out_of_sample: Option<usize>,   // 滚动验证的样本外K线数量
+    pub threads: Option<usize>,         // 并行线程数，默认使用全部 CPU 核心
+    #[serde(default = "default_top")]
+    pub top: usize,                     // 输出排名前几的参数
+}
+
+fn default_samples() -> usize {
+    100
+}
+
+fn default_top() -> usize {
+    20
 }
 
 /// `Configs` 结构体用于加载和解析配置文件。
 #[derive(Debug, Deserialize)]
 pub struct Configs {
-    pub symbols: Vec<SymbolConfig>, // 股票配置列表
+    #[serde(default)]
+    pub symbols: Vec<SymbolConfig>, // 股票配置列表，未配置 `strategies` 时由默认策略负责
+    #[serde(default = "default_strategy")]
+    pub strategy: String,           // 默认策略的类型
+    #[serde(default)]
+    pub params: serde_yaml::Value,  // 默认策略的参数
+    #[serde(default)]
+    pub strategies: Vec<StrategyConfig>, // 多策略配置（可选）
+    #[serde(default)]
+    pub profiles: HashMap<String, IndicatorProfile>, // 命名的指标组合
+    pub paper: Option<PaperConfig>, // 模拟盘配置（可选）
+    pub backtest: Option<BacktestConfig>, // 回测配置（可选）
+    pub optimizer: Option<OptimizerConfig>, // 参数优化配置（可选）
+    #[serde(default)]
+    pub store: StoreConfig,         // 本地K线存储配置
+    pub recorder: Option<RecorderConfig>, // 行情录制配置（可选）
+    pub state: Option<StateConfig>, // 策略状态持久化配置（可选）
+    pub risk: Option<RiskConfig>,   // 下单前风控配置（可选）
+    pub breaker: Option<BreakerConfig>, // 熔断配置（可选）
 }
 
 /// 加载配置文件的静态方法。
 ///
 /// # 返回值
-/// 返回一个包含配置的 `Result`，若发生错误则返回 `Box<dyn Error>`。
+/// 返回一个包含配置的 `Result`，若发生错误则返回 [`AqtError::Config`]。
 impl Configs {
-    pub fn load() -> Result<Self, Box<dyn Error>> {
+    pub fn load() -> Result<Self, AqtError> {
         let path = Path::new("config.yaml");
-        let content = fs::read_to_string(path)?; // 读取配置文件内容
-        let config: Configs = serde_yaml::from_str(&content)?; // 解析 YAML 格式的配置
+        let content = fs::read_to_string(path)
+            .map_err(|e| AqtError::Config(format!("读取 {} 失败: {}", path.display(), e)))?; // 读取配置文件内容
+        let config: Configs = serde_yaml::from_str(&content)
+            .map_err(|e| AqtError::Config(format!("解析 {} 失败: {}", path.display(), e)))?; // 解析 YAML 格式的配置
         Ok(config)
     }
+
+    /// 需要启动的策略：配置了 `strategies` 时返回其中启用的策略，
+    /// 否则由名为 `default` 的策略负责顶层 `symbols`
+    pub fn strategy_configs(&self) -> Vec<StrategyConfig> {
+        if self.strategies.is_empty() {
+            return vec![StrategyConfig {
+                name: "default".to_string(),
+                strategy: self.strategy.clone(),
+                params: self.params.clone(),
+                enabled: true,
+                symbols: self.symbols.clone(),
+            }];
+        }
+        self.strategies.iter().filter(|s| s.enabled).cloned().collect()
+    }
+
+    /// 所有启用策略的股票所属的分组
+    pub fn symbol_groups(&self) -> HashMap<String, Vec<String>> {
+        let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+        for strategy in self.strategy_configs() {
+            for sym in strategy.symbols {
+                let entry = groups.entry(sym.symbol).or_default();
+                for group in sym.groups {
+                    if !entry.contains(&group) {
+                        entry.push(group);
+                    }
+                }
+            }
+        }
+        groups
+    }
+
+    /// 所有启用策略的股票及K线周期（去重），用于订阅行情
+    pub fn subscriptions(&self) -> Vec<(String, String)> {
+        let mut subscriptions: Vec<(String, String)> = Vec::new();
+        for strategy in self.strategy_configs() {
+            for sym in strategy.symbols {
+                let key = (sym.symbol, sym.period);
+                if !subscriptions.contains(&key) {
+                    subscriptions.push(key);
+                }
+            }
+        }
+        subscriptions
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -58,6 +479,6 @@ pub struct LoggingConfig {
     pub level: String, // 日志级别
 }
 
-pub async fn load_config() -> Result<Config, Box<dyn Error>> {
-    Ok(Config::from_env()?) // 从环境变量加载长桥配置
+pub async fn load_config() -> Result<Config, AqtError> {
+    Config::from_env().map_err(|e| AqtError::Config(e.to_string())) // 从环境变量加载长桥配置
 }
\ No newline at end of file
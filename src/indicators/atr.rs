@@ -0,0 +1,21 @@
+use crate::indicators::candle::Candle;
+
+/// 最新的平均真实波幅（Wilder ATR），前 `period` 根真实波幅取简单平均，之后平滑。
+///
+/// K线数量不足 `period + 1` 根时返回 `None`。
+pub fn atr(candles: &[Candle], period: usize) -> Option<f64> {
+    if period == 0 || candles.len() < period + 1 {
+        return None;
+    }
+    let true_ranges: Vec<f64> = candles
+        .windows(2)
+        .map(|w| {
+            let (prev, cur) = (&w[0], &w[1]);
+            (cur.high - cur.low)
+                .max((cur.high - prev.close).abs())
+                .max((cur.low - prev.close).abs())
+        })
+        .collect();
+    let seed = true_ranges[..period].iter().sum::<f64>() / period as f64;
+    Some(true_ranges[period..].iter().fold(seed, |atr, tr| (atr * (period as f64 - 1.0) + tr) / period as f64))
+}
@@ -1,4 +1,6 @@
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Candle {
     pub symbol: Option<String>,
     pub timestamp: u64,
@@ -21,4 +23,43 @@ impl Default for Candle {
             volume: 0.0,
         }
     }
-}
\ No newline at end of file
+}
+/// 将K线周期（如 `15m`、`1h`、`1d`）转换为秒数
+pub fn period_seconds(period: &str) -> Option<u64> {
+    let (num, unit) = period.split_at(period.len().checked_sub(1)?);
+    let n: u64 = num.parse().ok()?;
+    match unit {
+        "m" => Some(n * 60),
+        "h" => Some(n * 60 * 60),
+        "d" => Some(n * 24 * 60 * 60),
+        "w" => Some(n * 7 * 24 * 60 * 60),
+        _ => None,
+    }
+}
+
+/// 1970-01-01 是周四，周线按往前 3 天的周一对齐
+const WEEK_OFFSET: u64 = 3 * 24 * 60 * 60;
+
+/// 将低级别K线聚合为 `period` 周期的K线（按 UTC 对齐，周线从周一开始），无法识别的周期返回空。
+///
+/// 最后一根聚合K线可能尚未走完，包含到最新一根低级别K线为止的数据。
+pub fn aggregate_candles(candles: &[Candle], period: &str) -> Vec<Candle> {
+    let Some(secs) = period_seconds(period) else {
+        return Vec::new();
+    };
+    let offset = if period.ends_with('w') { WEEK_OFFSET } else { 0 };
+    let mut result: Vec<Candle> = Vec::new();
+    for candle in candles {
+        let start = ((candle.timestamp + offset) / secs * secs).saturating_sub(offset);
+        match result.last_mut() {
+            Some(bar) if bar.timestamp == start => {
+                bar.high = bar.high.max(candle.high);
+                bar.low = bar.low.min(candle.low);
+                bar.close = candle.close;
+                bar.volume += candle.volume;
+            }
+            _ => result.push(Candle { timestamp: start, ..candle.clone() }),
+        }
+    }
+    result
+}
@@ -1,7 +1,9 @@
+use std::collections::VecDeque;
 use std::vec::Vec;
 use crate::indicators::candle::Candle;
 
 /// CYC 成本均线指标结构体
+#[derive(Clone)]
 pub struct CYC {
     // 配置参数
     short_period: usize,  // 短期 CYC 周期（如 5）
@@ -16,6 +18,10 @@ pub struct CYC {
     short_cyc: Vec<f64>,  // 短期 CYC 值
     mid_cyc: Vec<f64>,    // 中期 CYC 值
     long_cyc: Vec<f64>,   // 长期 CYC 值
+
+    // 增量计算状态
+    recent: VecDeque<Candle>, // 最近 long_period + 1 根K线
+    count: usize,             // 已处理的K线数量
 }
 
 impl CYC {
@@ -35,6 +41,8 @@ impl CYC {
             short_cyc: Vec::new(),
             mid_cyc: Vec::new(),
             long_cyc: Vec::new(),
+            recent: VecDeque::new(),
+            count: 0,
         }
     }
 
@@ -47,14 +55,16 @@ impl CYC {
     pub fn calculate(&mut self, candles: &[Candle]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
         // 重置状态
         self.reset();
+        self.count = candles.len();
+        self.recent = candles[candles.len().saturating_sub(self.long_period + 1)..].iter().cloned().collect();
 
         if candles.len() < self.long_period {
             return (vec![], vec![], vec![]);
         }
 
         // 计算每个周期的 CYC
-        for i in 0..candles.len() {
-            self.update_cyc(candles, i);
+        for candle in candles {
+            self.update_cyc(candle);
         }
 
         // 返回计算结果的克隆
@@ -65,11 +75,44 @@ impl CYC {
         )
     }
 
-    /// 更新指定位置的 CYC 值
-    fn update_cyc(&mut self, candles: &[Candle], index: usize) {
+    /// 增量计算：追加一根新K线，结果与 `calculate` 完全一致
+    pub fn update(&mut self, candle: &Candle) {
+        self.count += 1;
+        self.recent.push_back(candle.clone());
+        if self.recent.len() > self.long_period + 1 {
+            self.recent.pop_front();
+        }
+        // K线数量不足长期周期时没有结果，刚满周期时整体计算一次
+        if self.count <= self.long_period {
+            let candles: Vec<Candle> = self.recent.iter().cloned().collect();
+            self.calculate(&candles);
+            return;
+        }
+        self.update_cyc(candle);
+    }
+
+    /// 增量计算：用最新数据修正最后一根（尚未收盘的）K线
+    pub fn update_last(&mut self, candle: &Candle) {
+        if self.count > 0 {
+            self.count -= 1;
+            self.recent.pop_back();
+            if self.count >= self.long_period {
+                self.price_volume_sums.pop();
+                self.volume_sums.pop();
+                self.short_cyc.pop();
+                self.mid_cyc.pop();
+                self.long_cyc.pop();
+            }
+        }
+        self.update(candle);
+    }
+
+    /// 追加一根K线的 CYC 值
+    fn update_cyc(&mut self, candle: &Candle) {
         // 计算价格*成交量和成交量
-        let price = candles[index].close; // 可用 (high + low + close) / 3 替代
-        let volume = candles[index].volume;
+        let index = self.price_volume_sums.len();
+        let price = candle.close; // 可用 (high + low + close) / 3 替代
+        let volume = candle.volume;
         self.price_volume_sums.push(price * volume);
         self.volume_sums.push(volume);
 
@@ -114,6 +157,8 @@ impl CYC {
         self.short_cyc.clear();
         self.mid_cyc.clear();
         self.long_cyc.clear();
+        self.recent.clear();
+        self.count = 0;
     }
 
     /// 获取最新的 CYC 值
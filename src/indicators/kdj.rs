@@ -1,7 +1,8 @@
-use std::vec;
+use std::collections::VecDeque;
 use crate::indicators::candle::Candle;
 
 /// KDJ 指标结构体
+#[derive(Clone)]
 pub struct KDJ {
     // 配置参数
     k_period: usize,     // K值计算周期
@@ -17,6 +18,10 @@ pub struct KDJ {
     k_values: Vec<f64>,      // K值
     d_values: Vec<f64>,      // D值
     j_values: Vec<f64>,      // J值
+
+    // 增量计算状态
+    recent: VecDeque<Candle>, // 最近 k_period + 1 根K线
+    count: usize,             // 已处理的K线数量
 }
 
 impl KDJ {
@@ -37,6 +42,8 @@ impl KDJ {
             k_values: Vec::new(),
             d_values: Vec::new(),
             j_values: Vec::new(),
+            recent: VecDeque::new(),
+            count: 0,
         }
     }
 
@@ -47,30 +54,76 @@ impl KDJ {
 
     /// 计算一组K线的 KDJ 指标
     pub fn calculate(&mut self, candles: &[Candle]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        self.calculate_all(candles);
+
+        // 返回计算结果的克隆
+        (
+            self.k_values.clone(),
+            self.d_values.clone(),
+            self.j_values.clone()
+        )
+    }
+
+    /// 增量计算：追加一根新K线，结果与 `calculate` 完全一致
+    pub fn update(&mut self, candle: &Candle) {
+        self.count += 1;
+        self.recent.push_back(candle.clone());
+        if self.recent.len() > self.k_period + 1 {
+            self.recent.pop_front();
+        }
+        // K线数量不足周期时没有结果，刚满周期时整体计算一次
+        if self.count <= self.k_period {
+            let candles: Vec<Candle> = self.recent.iter().cloned().collect();
+            self.calculate_all(&candles);
+            return;
+        }
+
+        let start = self.recent.len() - self.k_period;
+        let window = &self.recent.make_contiguous()[start..];
+        let (highest, lowest) = Self::high_low(window);
+        let close = candle.close;
+        self.highest_high.push(highest);
+        self.lowest_low.push(lowest);
+        self.update_kdj(Self::rsv(close, highest, lowest));
+    }
+
+    /// 增量计算：用最新数据修正最后一根（尚未收盘的）K线
+    pub fn update_last(&mut self, candle: &Candle) {
+        if self.count > 0 {
+            self.count -= 1;
+            self.recent.pop_back();
+            if self.count >= self.k_period {
+                self.highest_high.pop();
+                self.lowest_low.pop();
+                self.rsv_values.pop();
+                self.k_values.pop();
+                self.d_values.pop();
+                self.j_values.pop();
+            }
+        }
+        self.update(candle);
+    }
+
+    /// 对一组K线整体计算
+    fn calculate_all(&mut self, candles: &[Candle]) {
         // 重置状态
         self.reset();
+        self.count = candles.len();
+        self.recent = candles[candles.len().saturating_sub(self.k_period + 1)..].iter().cloned().collect();
 
         if candles.len() < self.k_period {
-            return (vec![], vec![], vec![]);
+            return;
         }
 
         // 计算每个周期的KDJ
         for i in 0..candles.len() {
-            self.update_kdj(candles, i);
+            let rsv = self.calculate_rsv(candles, i);
+            self.update_kdj(rsv);
         }
-
-        // 返回计算结果的克隆
-        (
-            self.k_values.clone(),
-            self.d_values.clone(),
-            self.j_values.clone()
-        )
     }
 
-    /// 更新指定位置的KDJ值
-    fn update_kdj(&mut self, candles: &[Candle], index: usize) {
-        // 计算RSV值
-        let rsv = self.calculate_rsv(candles, index);
+    /// 根据RSV追加KDJ值
+    fn update_kdj(&mut self, rsv: f64) {
         self.rsv_values.push(rsv);
 
         // 计算K值 (第一个K值使用50作为初始值，后续使用SMA平滑)
@@ -102,19 +155,26 @@ impl KDJ {
 
         // 获取周期内的最高价和最低价
         let start = index + 1 - self.k_period;
-        let period_candles = &candles[start..=index];
-
-        let highest = period_candles.iter().map(|c| c.high).fold(f64::NEG_INFINITY, f64::max);
-        let lowest = period_candles.iter().map(|c| c.low).fold(f64::INFINITY, f64::min);
+        let (highest, lowest) = Self::high_low(&candles[start..=index]);
 
         self.highest_high.push(highest);
         self.lowest_low.push(lowest);
 
-        // 计算RSV
+        Self::rsv(candles[index].close, highest, lowest)
+    }
+
+    /// 周期内的最高价和最低价
+    fn high_low(period_candles: &[Candle]) -> (f64, f64) {
+        let highest = period_candles.iter().map(|c| c.high).fold(f64::NEG_INFINITY, f64::max);
+        let lowest = period_candles.iter().map(|c| c.low).fold(f64::INFINITY, f64::min);
+        (highest, lowest)
+    }
+
+    fn rsv(close: f64, highest: f64, lowest: f64) -> f64 {
         if (highest - lowest).abs() < f64::EPSILON {
             50.0 // 避免除以零
         } else {
-            100.0 * (candles[index].close - lowest) / (highest - lowest)
+            100.0 * (close - lowest) / (highest - lowest)
         }
     }
 
@@ -126,6 +186,8 @@ impl KDJ {
         self.k_values.clear();
         self.d_values.clear();
         self.j_values.clear();
+        self.recent.clear();
+        self.count = 0;
     }
 
     /// 获取最新的 KDJ 值
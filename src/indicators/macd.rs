@@ -1,13 +1,23 @@
+use std::collections::VecDeque;
 use crate::indicators::candle::Candle;
+#[derive(Clone)]
 pub struct MACD {
     fast_length: usize,
     slow_length: usize,
     signal_length: usize,
 
     // 存储计算结果
+    fast_ema: Vec<f64>,
+    slow_ema: Vec<f64>,
     macd_line: Vec<f64>,
     signal_line: Vec<f64>,
     histogram: Vec<f64>,
+    buy_signals: Vec<bool>,
+    sell_signals: Vec<bool>,
+
+    // 增量计算状态
+    recent: VecDeque<f64>, // 最近的收盘价，预热阶段用于整体重算
+    count: usize,          // 已处理的K线数量
 
     // 配置选项
     show_macd_signal: bool,
@@ -40,9 +50,15 @@ impl MACD {
             fast_length,
             slow_length,
             signal_length,
+            fast_ema: Vec::new(),
+            slow_ema: Vec::new(),
             macd_line: Vec::new(),
             signal_line: Vec::new(),
             histogram: Vec::new(),
+            buy_signals: Vec::new(),
+            sell_signals: Vec::new(),
+            recent: VecDeque::new(),
+            count: 0,
             show_macd_signal: true,
             show_dots: true,
             show_histogram: true,
@@ -70,13 +86,87 @@ impl MACD {
 
         // 提取收盘价
         let prices: Vec<f64> = candles.iter().map(|c| c.close).collect();
+        self.calculate_prices(&prices);
+
+        (self.buy_signals.clone(), self.sell_signals.clone())
+    }
+
+    /// 增量计算：追加一根新K线
+    ///
+    /// EMA 的初始值是前 N 个价格的平均值，预热阶段会整体重算，之后每根K线只做常数次计算，
+    /// 结果与 `calculate` 完全一致。
+    pub fn update(&mut self, candle: &Candle) {
+        self.count += 1;
+        self.recent.push_back(candle.close);
+        if self.recent.len() > self.warmup() + 1 {
+            self.recent.pop_front();
+        }
+        if self.count <= self.warmup() {
+            let prices: Vec<f64> = self.recent.iter().copied().collect();
+            self.calculate_prices(&prices);
+            return;
+        }
+
+        let i = self.macd_line.len();
+        let fast = Self::next_ema(self.fast_ema[i - 1], candle.close, self.fast_length);
+        let slow = Self::next_ema(self.slow_ema[i - 1], candle.close, self.slow_length);
+        self.fast_ema.push(fast);
+        self.slow_ema.push(slow);
+        self.macd_line.push(fast - slow);
+
+        let mut sum = 0.0;
+        for j in 0..self.signal_length {
+            sum += self.macd_line[i - j];
+        }
+        self.signal_line.push(sum / self.signal_length as f64);
+        self.histogram.push(self.macd_line[i] - self.signal_line[i]);
+        self.push_cross(i);
+    }
+
+    /// 增量计算：用最新数据修正最后一根（尚未收盘的）K线
+    pub fn update_last(&mut self, candle: &Candle) {
+        if self.count > 0 {
+            self.count -= 1;
+            self.recent.pop_back();
+            if self.count >= self.warmup() {
+                self.fast_ema.pop();
+                self.slow_ema.pop();
+                self.macd_line.pop();
+                self.signal_line.pop();
+                self.histogram.pop();
+                self.buy_signals.pop();
+                self.sell_signals.pop();
+            }
+        }
+        self.update(candle);
+    }
+
+    // 获取买入信号
+    pub fn buy_signals(&self) -> &[bool] {
+        &self.buy_signals
+    }
+
+    // 获取卖出信号
+    pub fn sell_signals(&self) -> &[bool] {
+        &self.sell_signals
+    }
+
+    // 预热长度：超过该数量后 EMA 初始值和信号线长度都不再变化
+    fn warmup(&self) -> usize {
+        self.fast_length.max(self.slow_length).max(self.signal_length)
+    }
+
+    // 对全部收盘价整体计算
+    fn calculate_prices(&mut self, prices: &[f64]) {
+        self.count = prices.len();
+        self.recent = prices[prices.len().saturating_sub(self.warmup() + 1)..].iter().copied().collect();
 
         // 计算EMA
-        let fast_ma = Self::calculate_ema(&prices, self.fast_length);
-        let slow_ma = Self::calculate_ema(&prices, self.slow_length);
+        self.fast_ema = Self::calculate_ema(prices, self.fast_length);
+        self.slow_ema = Self::calculate_ema(prices, self.slow_length);
 
         // 计算MACD线
-        self.macd_line = fast_ma.iter().zip(slow_ma.iter())
+        self.macd_line = self.fast_ema.iter().zip(self.slow_ema.iter())
             .map(|(fast, slow)| fast - slow)
             .collect();
 
@@ -89,25 +179,34 @@ impl MACD {
             .collect();
 
         // 生成交叉信号
-        let mut buy_signals = vec![false; candles.len()];
-        let mut sell_signals = vec![false; candles.len()];
+        self.buy_signals = vec![false; prices.len()];
+        self.sell_signals = vec![false; prices.len()];
 
         // 从第二个点开始检查交叉
         for i in 1..self.macd_line.len() {
-            // MACD线从下方穿过信号线 - 买入信号
-            if self.macd_line[i-1] < self.signal_line[i-1] &&
-                self.macd_line[i] >= self.signal_line[i] {
-                buy_signals[i] = true;
-            }
-
-            // MACD线从上方穿过信号线 - 卖出信号
-            if self.macd_line[i-1] > self.signal_line[i-1] &&
-                self.macd_line[i] <= self.signal_line[i] {
-                sell_signals[i] = true;
-            }
+            self.buy_signals[i] = self.is_buy_cross(i);
+            self.sell_signals[i] = self.is_sell_cross(i);
         }
+    }
 
-        (buy_signals, sell_signals)
+    // 追加第 i 个点的交叉信号
+    fn push_cross(&mut self, i: usize) {
+        let buy = i > 0 && self.is_buy_cross(i);
+        let sell = i > 0 && self.is_sell_cross(i);
+        self.buy_signals.push(buy);
+        self.sell_signals.push(sell);
+    }
+
+    // MACD线从下方穿过信号线 - 买入信号
+    fn is_buy_cross(&self, i: usize) -> bool {
+        self.macd_line[i-1] < self.signal_line[i-1] &&
+            self.macd_line[i] >= self.signal_line[i]
+    }
+
+    // MACD线从上方穿过信号线 - 卖出信号
+    fn is_sell_cross(&self, i: usize) -> bool {
+        self.macd_line[i-1] > self.signal_line[i-1] &&
+            self.macd_line[i] <= self.signal_line[i]
     }
 
     // 获取MACD线
@@ -205,7 +304,6 @@ impl MACD {
         }
 
         let mut ema = Vec::with_capacity(prices.len());
-        let multiplier = 2.0 / (period as f64 + 1.0);
 
         // 初始化EMA为前period个价格的平均值
         let mut sum = 0.0;
@@ -218,14 +316,18 @@ impl MACD {
 
         // 计算剩余的EMA
         for i in 1..prices.len() {
-            let prev_ema = ema[i-1];
-            let current_ema = (prices[i] - prev_ema) * multiplier + prev_ema;
-            ema.push(current_ema);
+            ema.push(Self::next_ema(ema[i-1], prices[i], period));
         }
 
         ema
     }
 
+    // 辅助函数：根据上一个EMA计算下一个EMA
+    fn next_ema(prev_ema: f64, price: f64, period: usize) -> f64 {
+        let multiplier = 2.0 / (period as f64 + 1.0);
+        (price - prev_ema) * multiplier + prev_ema
+    }
+
     // 辅助函数：计算SMA
     fn calculate_sma(values: &[f64], period: usize) -> Vec<f64> {
         if values.is_empty() || period == 0 {
@@ -2,6 +2,7 @@ pub mod macd;
 pub mod candle;
 pub mod kdj;
 pub mod utbot;
+pub mod atr;
 pub mod schaff_trend_cycle;
 pub mod cyc;
 pub mod tradingview_technicals;
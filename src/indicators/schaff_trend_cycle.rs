@@ -1,11 +1,27 @@
+use std::collections::VecDeque;
 use log4rs::encode::Color;
 use crate::indicators::candle::Candle;
+#[derive(Clone)]
 pub struct SchaffTrendCycle {
     length: usize,
     fast_length: usize,
     slow_length: usize,
     factor: f64,
     stc_values: Vec<f64>,
+
+    // 中间结果，增量计算时使用
+    fast_ema: Vec<f64>,
+    slow_ema: Vec<f64>,
+    macd_values: Vec<f64>,
+    stoch1_values: Vec<f64>,
+    stoch1_smooth: Vec<f64>,
+    stoch2_values: Vec<f64>,
+    stoch2_smooth: Vec<f64>,
+    signals: Vec<(f64, bool, bool)>,
+
+    // 增量计算状态
+    recent: VecDeque<f64>, // 最近的收盘价，预热阶段用于整体重算
+    count: usize,          // 已处理的K线数量
 }
 
 impl SchaffTrendCycle {
@@ -16,105 +32,178 @@ impl SchaffTrendCycle {
             slow_length,
             factor: 0.5, // 默认平滑因子
             stc_values: Vec::new(),
+            fast_ema: Vec::new(),
+            slow_ema: Vec::new(),
+            macd_values: Vec::new(),
+            stoch1_values: Vec::new(),
+            stoch1_smooth: Vec::new(),
+            stoch2_values: Vec::new(),
+            stoch2_smooth: Vec::new(),
+            signals: Vec::new(),
+            recent: VecDeque::new(),
+            count: 0,
         }
     }
 
     pub fn calculate(&mut self, candles: &[Candle]) -> Vec<(f64, bool, bool)> {
-        // 确保至少有足够的数据来计算指标
-        let min_required = self.length.max(self.fast_length).max(self.slow_length);
-        if candles.len() < min_required {
-            // 如果数据不足，返回空结果
-            return Vec::new();
-        }
-
         // 提取收盘价
         let prices: Vec<f64> = candles.iter().map(|c| c.close).collect();
+        self.calculate_prices(&prices);
+        self.signals.clone()
+    }
 
-        // 计算MACD值
-        let macd_values = self.calculate_macd(&prices);
+    /// 增量计算：追加一根新K线，结果与 `calculate` 完全一致
+    pub fn update(&mut self, candle: &Candle) {
+        self.count += 1;
+        self.recent.push_back(candle.close);
+        if self.recent.len() > self.warmup() + 1 {
+            self.recent.pop_front();
+        }
+        // EMA 初始值在数据满足最长周期前会变化，预热阶段整体计算
+        if self.count <= self.warmup() {
+            let prices: Vec<f64> = self.recent.iter().copied().collect();
+            self.calculate_prices(&prices);
+            return;
+        }
 
-        // 初始化存储结果的向量
-        self.stc_values = Vec::with_capacity(candles.len());
-        let mut signals = Vec::with_capacity(candles.len());
+        let i = self.macd_values.len();
+        let fast = Self::next_ema(self.fast_ema[i - 1], candle.close, self.fast_length);
+        let slow = Self::next_ema(self.slow_ema[i - 1], candle.close, self.slow_length);
+        self.fast_ema.push(fast);
+        self.slow_ema.push(slow);
+        self.macd_values.push(fast - slow);
+        self.push_stc(i);
+    }
 
-        // 第一阶段随机计算的变量
-        let mut stoch1_values = Vec::with_capacity(candles.len());
-        let mut stoch1_smooth = Vec::with_capacity(candles.len());
+    /// 增量计算：用最新数据修正最后一根（尚未收盘的）K线
+    pub fn update_last(&mut self, candle: &Candle) {
+        if self.count > 0 {
+            self.count -= 1;
+            self.recent.pop_back();
+            if self.count >= self.warmup() {
+                self.fast_ema.pop();
+                self.slow_ema.pop();
+                self.macd_values.pop();
+                self.stoch1_values.pop();
+                self.stoch1_smooth.pop();
+                self.stoch2_values.pop();
+                self.stoch2_smooth.pop();
+                self.stc_values.pop();
+                self.signals.pop();
+            }
+        }
+        self.update(candle);
+    }
 
-        // 第二阶段随机计算的变量
-        let mut stoch2_values = Vec::with_capacity(candles.len());
-        let mut stoch2_smooth = Vec::with_capacity(candles.len());
+    /// 获取全部信号 (STC值, 红色信号, 绿色信号)
+    pub fn signals(&self) -> &[(f64, bool, bool)] {
+        &self.signals
+    }
 
-        // 为每个价格点计算STC值
-        for i in 0..candles.len() {
-            // 确保有足够的数据进行计算
-            if i < self.length {
-                self.stc_values.push(50.0); // 默认值
-                signals.push((50.0, false, false));
-                stoch1_values.push(50.0);
-                stoch1_smooth.push(50.0);
-                stoch2_values.push(50.0);
-                stoch2_smooth.push(50.0);
-                continue;
-            }
+    // 预热长度，与 `calculate` 要求的最少数据量一致
+    fn warmup(&self) -> usize {
+        self.length.max(self.fast_length).max(self.slow_length)
+    }
 
-            // 计算第一阶段随机值 - 修复这里的索引计算
-            let start_idx = if i >= self.length { i - self.length + 1 } else { 0 };
-            let lowest_macd = macd_values[start_idx..=i].iter().fold(f64::INFINITY, |a, &b| a.min(b));
-            let highest_macd = macd_values[start_idx..=i].iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-            let macd_range = highest_macd - lowest_macd;
+    // 对全部收盘价整体计算
+    fn calculate_prices(&mut self, prices: &[f64]) {
+        self.count = prices.len();
+        self.recent = prices[prices.len().saturating_sub(self.warmup() + 1)..].iter().copied().collect();
+        self.signals.clear();
 
-            let stoch1 = if macd_range > 0.0 {
-                (macd_values[i] - lowest_macd) / macd_range * 100.0
-            } else {
-                if i > 0 { stoch1_values[i-1] } else { 50.0 }
-            };
-            stoch1_values.push(stoch1);
+        // 确保至少有足够的数据来计算指标
+        if prices.len() < self.warmup() {
+            // 如果数据不足，返回空结果
+            return;
+        }
 
-            // 应用第一阶段平滑
-            let stoch1_ema = if i > 0 {
-                stoch1_smooth[i-1] + self.factor * (stoch1 - stoch1_smooth[i-1])
-            } else {
-                stoch1
-            };
-            stoch1_smooth.push(stoch1_ema);
+        // 计算MACD值
+        self.fast_ema = self.calculate_ema(prices, self.fast_length);
+        self.slow_ema = self.calculate_ema(prices, self.slow_length);
+        self.macd_values = self.fast_ema.iter().zip(self.slow_ema.iter())
+            .map(|(fast, slow)| fast - slow)
+            .collect();
 
-            // 计算第二阶段随机值 - 同样修复索引计算
-            let lowest_stoch1 = stoch1_smooth[start_idx..=i].iter().fold(f64::INFINITY, |a, &b| a.min(b));
-            let highest_stoch1 = stoch1_smooth[start_idx..=i].iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-            let stoch1_range = highest_stoch1 - lowest_stoch1;
+        // 初始化存储结果的向量
+        self.stc_values = Vec::with_capacity(prices.len());
+        self.stoch1_values = Vec::with_capacity(prices.len());
+        self.stoch1_smooth = Vec::with_capacity(prices.len());
+        self.stoch2_values = Vec::with_capacity(prices.len());
+        self.stoch2_smooth = Vec::with_capacity(prices.len());
 
-            let stoch2 = if stoch1_range > 0.0 {
-                (stoch1_ema - lowest_stoch1) / stoch1_range * 100.0
-            } else {
-                if i > 0 { stoch2_values[i-1] } else { 50.0 }
-            };
-            stoch2_values.push(stoch2);
+        // 为每个价格点计算STC值
+        for i in 0..prices.len() {
+            self.push_stc(i);
+        }
+    }
 
-            // 应用第二阶段平滑 (最终STC值)
-            let stc = if i > 0 {
-                stoch2_smooth[i-1] + self.factor * (stoch2 - stoch2_smooth[i-1])
-            } else {
-                stoch2
-            };
-            stoch2_smooth.push(stc);
-            self.stc_values.push(stc);
-
-            // 生成信号 - 确保有足够的历史数据
-            let red_signal = i >= 3 &&
-                self.stc_values[i-3] <= self.stc_values[i-2] &&
-                self.stc_values[i-2] > self.stc_values[i-1] &&
-                stc > 75.0;
-
-            let green_signal = i >= 3 &&
-                self.stc_values[i-3] >= self.stc_values[i-2] &&
-                self.stc_values[i-2] < self.stc_values[i-1] &&
-                stc < 25.0;
-
-            signals.push((stc, red_signal, green_signal));
+    // 计算第 i 个点的STC值和信号
+    fn push_stc(&mut self, i: usize) {
+        // 确保有足够的数据进行计算
+        if i < self.length {
+            self.stc_values.push(50.0); // 默认值
+            self.signals.push((50.0, false, false));
+            self.stoch1_values.push(50.0);
+            self.stoch1_smooth.push(50.0);
+            self.stoch2_values.push(50.0);
+            self.stoch2_smooth.push(50.0);
+            return;
         }
 
-        signals
+        // 计算第一阶段随机值 - 修复这里的索引计算
+        let start_idx = if i >= self.length { i - self.length + 1 } else { 0 };
+        let lowest_macd = self.macd_values[start_idx..=i].iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let highest_macd = self.macd_values[start_idx..=i].iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        let macd_range = highest_macd - lowest_macd;
+
+        let stoch1 = if macd_range > 0.0 {
+            (self.macd_values[i] - lowest_macd) / macd_range * 100.0
+        } else {
+            if i > 0 { self.stoch1_values[i-1] } else { 50.0 }
+        };
+        self.stoch1_values.push(stoch1);
+
+        // 应用第一阶段平滑
+        let stoch1_ema = if i > 0 {
+            self.stoch1_smooth[i-1] + self.factor * (stoch1 - self.stoch1_smooth[i-1])
+        } else {
+            stoch1
+        };
+        self.stoch1_smooth.push(stoch1_ema);
+
+        // 计算第二阶段随机值 - 同样修复索引计算
+        let lowest_stoch1 = self.stoch1_smooth[start_idx..=i].iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let highest_stoch1 = self.stoch1_smooth[start_idx..=i].iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        let stoch1_range = highest_stoch1 - lowest_stoch1;
+
+        let stoch2 = if stoch1_range > 0.0 {
+            (stoch1_ema - lowest_stoch1) / stoch1_range * 100.0
+        } else {
+            if i > 0 { self.stoch2_values[i-1] } else { 50.0 }
+        };
+        self.stoch2_values.push(stoch2);
+
+        // 应用第二阶段平滑 (最终STC值)
+        let stc = if i > 0 {
+            self.stoch2_smooth[i-1] + self.factor * (stoch2 - self.stoch2_smooth[i-1])
+        } else {
+            stoch2
+        };
+        self.stoch2_smooth.push(stc);
+        self.stc_values.push(stc);
+
+        // 生成信号 - 确保有足够的历史数据
+        let red_signal = i >= 3 &&
+            self.stc_values[i-3] <= self.stc_values[i-2] &&
+            self.stc_values[i-2] > self.stc_values[i-1] &&
+            stc > 75.0;
+
+        let green_signal = i >= 3 &&
+            self.stc_values[i-3] >= self.stc_values[i-2] &&
+            self.stc_values[i-2] < self.stc_values[i-1] &&
+            stc < 25.0;
+
+        self.signals.push((stc, red_signal, green_signal));
     }
 
     // 获取STC值
@@ -141,27 +230,12 @@ impl SchaffTrendCycle {
         colors
     }
 
-    // 辅助函数：计算MACD值
-    fn calculate_macd(&self, prices: &[f64]) -> Vec<f64> {
-        // 计算快速EMA
-        let fast_ema = self.calculate_ema(prices, self.fast_length);
-
-        // 计算慢速EMA
-        let slow_ema = self.calculate_ema(prices, self.slow_length);
-
-        // 计算MACD值 (快速EMA - 慢速EMA)
-        fast_ema.iter().zip(slow_ema.iter())
-            .map(|(fast, slow)| fast - slow)
-            .collect()
-    }
-
     // 辅助函数：计算EMA
     fn calculate_ema(&self, prices: &[f64], period: usize) -> Vec<f64> {
         let mut ema = Vec::with_capacity(prices.len());
 
         // 确保period不为0，避免除以零错误
         let period = if period == 0 { 1 } else { period };
-        let multiplier = 2.0 / (period as f64 + 1.0);
 
         // 初始化EMA为前period个价格的平均值
         let mut sum = 0.0;
@@ -181,11 +255,17 @@ impl SchaffTrendCycle {
 
         // 计算剩余的EMA
         for i in 1..prices.len() {
-            let prev_ema = ema[i-1];
-            let current_ema = (prices[i] - prev_ema) * multiplier + prev_ema;
-            ema.push(current_ema);
+            ema.push(Self::next_ema(ema[i-1], prices[i], period));
         }
 
         ema
     }
+
+    // 辅助函数：根据上一个EMA计算下一个EMA
+    fn next_ema(prev_ema: f64, price: f64, period: usize) -> f64 {
+        // 确保period不为0，避免除以零错误
+        let period = if period == 0 { 1 } else { period };
+        let multiplier = 2.0 / (period as f64 + 1.0);
+        (price - prev_ema) * multiplier + prev_ema
+    }
 }
@@ -1,9 +1,11 @@
+use std::collections::VecDeque;
 use crate::indicators::candle::Candle;
 
 /// UT Bot 指标实现
 ///
 /// 这是一个基于 ATR 的跟踪止损系统，用于生成交易信号
 /// 原始指标来源于 TradingView 的 Pine Script
+#[derive(Clone)]
 pub struct UTBot {
     // 配置参数
     key_value: f64,      // 灵敏度系数
@@ -20,6 +22,10 @@ pub struct UTBot {
     // 信号
     buy_signals: Vec<bool>,            // 买入信号
     sell_signals: Vec<bool>,           // 卖出信号
+
+    // 增量计算状态
+    recent: VecDeque<Candle>,          // 最近 atr_period + 1 根K线
+    count: usize,                      // 已处理的K线数量
 }
 
 impl UTBot {
@@ -41,6 +47,8 @@ impl UTBot {
             ema_values: Vec::new(),
             buy_signals: Vec::new(),
             sell_signals: Vec::new(),
+            recent: VecDeque::new(),
+            count: 0,
         }
     }
 
@@ -53,6 +61,8 @@ impl UTBot {
     pub fn calculate(&mut self, candles: &[Candle]) -> (&[bool], &[bool]) {
         // 重置状态
         self.reset();
+        self.count = candles.len();
+        self.recent = candles[candles.len().saturating_sub(self.warmup() + 1)..].iter().cloned().collect();
 
         if candles.len() < self.atr_period {
             return (&self.buy_signals, &self.sell_signals);
@@ -77,6 +87,66 @@ impl UTBot {
         (&self.buy_signals, &self.sell_signals)
     }
 
+    /// 增量计算：追加一根新K线，结果与 `calculate` 完全一致
+    pub fn update(&mut self, candle: &Candle) {
+        self.count += 1;
+        self.recent.push_back(candle.clone());
+        if self.recent.len() > self.warmup() + 1 {
+            self.recent.pop_front();
+        }
+        // ATR 前 atr_period 根使用简单平均，预热阶段整体计算
+        if self.count <= self.warmup() {
+            let candles: Vec<Candle> = self.recent.iter().cloned().collect();
+            self.calculate(&candles);
+            return;
+        }
+
+        let i = self.source_values.len();
+        let prev_close = self.recent[self.recent.len() - 2].close;
+
+        let src = if self.use_heikin_ashi {
+            (self.source_values[i-1] + (candle.high + candle.low + candle.close) / 3.0) / 2.0
+        } else {
+            candle.close
+        };
+        self.source_values.push(src);
+        self.ema_values.push(src);
+
+        let tr1 = candle.high - candle.low;
+        let tr2 = (candle.high - prev_close).abs();
+        let tr3 = (candle.low - prev_close).abs();
+        let tr = tr1.max(tr2).max(tr3);
+        let atr = (self.atr_values[i-1] * (self.atr_period as f64 - 1.0) + tr) / self.atr_period as f64;
+        self.atr_values.push(atr);
+
+        self.update_trailing_stop(i);
+        self.update_position(i);
+        self.generate_signals(i);
+    }
+
+    /// 增量计算：用最新数据修正最后一根（尚未收盘的）K线
+    pub fn update_last(&mut self, candle: &Candle) {
+        if self.count > 0 {
+            self.count -= 1;
+            self.recent.pop_back();
+            if self.count >= self.warmup() {
+                self.atr_values.pop();
+                self.trailing_stop.pop();
+                self.position.pop();
+                self.source_values.pop();
+                self.ema_values.pop();
+                self.buy_signals.pop();
+                self.sell_signals.pop();
+            }
+        }
+        self.update(candle);
+    }
+
+    /// 预热长度
+    fn warmup(&self) -> usize {
+        self.atr_period.max(1)
+    }
+
     /// 准备源数据 (普通收盘价或平均K线收盘价)
     fn prepare_source_data(&mut self, candles: &[Candle]) {
         if self.use_heikin_ashi {
@@ -228,6 +298,8 @@ impl UTBot {
         self.ema_values.clear();
         self.buy_signals.clear();
         self.sell_signals.clear();
+        self.recent.clear();
+        self.count = 0;
     }
 
     /// 获取最新的跟踪止损值
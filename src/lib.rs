@@ -8,3 +8,5 @@ pub mod tasks;
 pub mod indicators;
 pub mod computes;
 pub mod calculates;
+pub mod backtest;
+pub mod store;
@@ -0,0 +1,51 @@
+use longport::trade::AccountBalance;
+use longport::Decimal;
+
+/// 单一币种的现金信息
+#[derive(Debug, Clone, Default)]
+pub struct CashInfo {
+    /// 币种
+    pub currency: String,
+    /// 可提现金
+    pub withdraw_cash: Decimal,
+    /// 可用现金
+    pub available_cash: Decimal,
+    /// 冻结现金
+    pub frozen_cash: Decimal,
+}
+
+/// 账户余额，与具体券商实现无关
+#[derive(Debug, Clone, Default)]
+pub struct Balance {
+    /// 结算币种
+    pub currency: String,
+    /// 现金总额
+    pub total_cash: Decimal,
+    /// 净资产
+    pub net_assets: Decimal,
+    /// 购买力
+    pub buy_power: Decimal,
+    /// 各币种现金明细
+    pub cash_infos: Vec<CashInfo>,
+}
+
+impl From<AccountBalance> for Balance {
+    fn from(b: AccountBalance) -> Self {
+        Balance {
+            currency: b.currency,
+            total_cash: b.total_cash,
+            net_assets: b.net_assets,
+            buy_power: b.buy_power,
+            cash_infos: b
+                .cash_infos
+                .into_iter()
+                .map(|c| CashInfo {
+                    currency: c.currency,
+                    withdraw_cash: c.withdraw_cash,
+                    available_cash: c.available_cash,
+                    frozen_cash: c.frozen_cash,
+                })
+                .collect(),
+        }
+    }
+}
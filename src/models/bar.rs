@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use crate::indicators::candle::Candle;
+
+/// K线事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarEventKind {
+    /// 当前K线有新的成交，K线仍未收盘
+    Updated,
+    /// K线已收盘，数据不会再变化
+    Closed,
+}
+
+/// 推送K线产生的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarEvent {
+    /// 股票代码
+    pub symbol: String,
+    /// K线周期，与配置中的写法一致，例如 "1h"
+    pub period: String,
+    /// 事件类型
+    pub kind: BarEventKind,
+    /// 对应的K线
+    pub candle: Candle,
+}
+
+impl BarEvent {
+    pub fn new(symbol: &str, period: &str, kind: BarEventKind, candle: Candle) -> Self {
+        BarEvent {
+            symbol: symbol.to_string(),
+            period: period.to_string(),
+            kind,
+            candle,
+        }
+    }
+
+    /// 是否为收盘事件
+    pub fn is_closed(&self) -> bool {
+        self.kind == BarEventKind::Closed
+    }
+}
@@ -1,8 +1,8 @@
 use longport::{decimal, Decimal};
-use serde::{Deserialize};
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MarketData {
     /// 股票代码
     pub symbol: String,
@@ -3,4 +3,5 @@ pub mod order;
 pub mod position;
 pub mod stock;
 pub mod market;
-pub mod symbol_time;
\ No newline at end of file
+pub mod symbol_time;
+pub mod bar;
\ No newline at end of file
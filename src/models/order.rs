@@ -0,0 +1,100 @@
+use longport::trade::{OrderSide, OrderStatus, OrderType};
+use longport::Decimal;
+use time::OffsetDateTime;
+
+/// 订单信息，与具体券商实现无关
+#[derive(Debug, Clone)]
+pub struct Order {
+    /// 订单ID
+    pub order_id: String,
+    /// 股票代码
+    pub symbol: String,
+    /// 订单方向
+    pub side: OrderSide,
+    /// 订单状态
+    pub status: OrderStatus,
+    /// 订单类型
+    pub order_type: OrderType,
+    /// 委托数量
+    pub quantity: Decimal,
+    /// 已成交数量
+    pub executed_quantity: Decimal,
+    /// 委托价格
+    pub price: Option<Decimal>,
+    /// 成交均价
+    pub executed_price: Option<Decimal>,
+    /// 提交时间
+    pub submitted_at: OffsetDateTime,
+    /// 订单备注，策略提交的订单为下单意图的幂等键
+    pub remark: String,
+}
+
+impl From<longport::trade::Order> for Order {
+    fn from(o: longport::trade::Order) -> Self {
+        Order {
+            order_id: o.order_id,
+            symbol: o.symbol,
+            side: o.side,
+            status: o.status,
+            order_type: o.order_type,
+            quantity: o.quantity,
+            executed_quantity: o.executed_quantity,
+            price: o.price,
+            executed_price: o.executed_price,
+            submitted_at: o.submitted_at,
+            remark: o.remark,
+        }
+    }
+}
+
+impl Order {
+    /// 订单是否仍可能成交（未成交、部分成交或改单、撤单处理中）
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self.status,
+            OrderStatus::NotReported
+                | OrderStatus::ReplacedNotReported
+                | OrderStatus::ProtectedNotReported
+                | OrderStatus::VarietiesNotReported
+                | OrderStatus::WaitToNew
+                | OrderStatus::New
+                | OrderStatus::WaitToReplace
+                | OrderStatus::PendingReplace
+                | OrderStatus::Replaced
+                | OrderStatus::PartialFilled
+                | OrderStatus::WaitToCancel
+                | OrderStatus::PendingCancel
+        )
+    }
+}
+
+/// 下单意图：策略的一次下单决定。
+///
+/// `key` 是客户端幂等键，同一个策略在同一根K线的同一个操作总是得到相同的键，
+/// 重试或重复判断时据此识别已经提交过的订单，不会重复下单；不同策略交易同一个股票时键不会冲突。
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderIntent {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub key: String,
+}
+
+impl OrderIntent {
+    /// 为策略 `strategy` 在K线 `ts`（秒级时间戳）上的操作 `action`（如 `buy`、`take_profit`）创建下单意图
+    pub fn new(strategy: &str, symbol: &str, ts: u64, action: &str, side: OrderSide, price: Decimal, quantity: Decimal) -> Self {
+        OrderIntent {
+            symbol: symbol.to_string(),
+            side,
+            price,
+            quantity,
+            key: format!("{}{}:{}", Self::key_prefix(strategy, symbol), ts, action),
+        }
+    }
+
+    /// 策略 `strategy` 在股票 `symbol` 上的幂等键前缀
+    pub fn key_prefix(strategy: &str, symbol: &str) -> String {
+        format!("{}:{}:", strategy, symbol)
+    }
+}
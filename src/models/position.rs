@@ -0,0 +1,44 @@
+use longport::trade::StockPosition;
+use longport::Decimal;
+
+/// 股票持仓，与具体券商实现无关
+#[derive(Debug, Clone, Default)]
+pub struct Position {
+    /// 股票代码
+    pub symbol: String,
+    /// 股票名称
+    pub symbol_name: String,
+    /// 持仓数量，空头持仓为负数
+    pub quantity: Decimal,
+    /// 可用数量
+    pub available_quantity: Decimal,
+    /// 币种
+    pub currency: String,
+    /// 成本价
+    pub cost_price: Decimal,
+}
+
+impl Position {
+    /// 是否为空头持仓（数量为负）
+    pub fn is_short(&self) -> bool {
+        self.quantity < Decimal::ZERO
+    }
+
+    /// 是否为多头持仓
+    pub fn is_long(&self) -> bool {
+        self.quantity > Decimal::ZERO
+    }
+}
+
+impl From<StockPosition> for Position {
+    fn from(p: StockPosition) -> Self {
+        Position {
+            symbol: p.symbol,
+            symbol_name: p.symbol_name,
+            quantity: p.quantity,
+            available_quantity: p.available_quantity,
+            currency: p.currency,
+            cost_price: p.cost_price,
+        }
+    }
+}
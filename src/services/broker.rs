@@ -0,0 +1,90 @@
+use std::future::Future;
+use longport::quote::MarketTemperature;
+use longport::trade::{OrderSide, SubmitOrderResponse};
+use longport::Decimal;
+use time::OffsetDateTime;
+use crate::indicators::candle::Candle;
+use crate::models::balance::Balance;
+use crate::models::order::{Order, OrderIntent};
+use crate::models::position::Position;
+use crate::utils::error::AqtError;
+
+/// `Broker` 抽象了交易相关的接口（下单、撤单、持仓、资金、订单查询），
+/// 策略只依赖该 trait，从而可以接入长桥以外的实现（如模拟盘）。
+///
+/// 所有请求失败时返回 [`AqtError`]，与“没有数据”区分开。
+pub trait Broker: Send + Sync {
+    /// 提交限价订单。
+    fn submit_order(
+        &self,
+        symbol: String,
+        side: OrderSide,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> impl Future<Output = Result<SubmitOrderResponse, AqtError>> + Send;
+
+    /// 提交下单意图，幂等键作为订单备注提交，重启后可以据此找回已提交的订单。
+    ///
+    /// 默认忽略幂等键，直接提交限价订单。
+    fn submit_intent(&self, intent: &OrderIntent) -> impl Future<Output = Result<SubmitOrderResponse, AqtError>> + Send {
+        self.submit_order(intent.symbol.clone(), intent.side, intent.price, intent.quantity)
+    }
+
+    /// 取消订单。
+    fn cancel_order(&self, order_id: String) -> impl Future<Output = Result<(), AqtError>> + Send;
+
+    /// 获取股票持仓。
+    fn stock_positions(&self) -> impl Future<Output = Result<Vec<Position>, AqtError>> + Send;
+
+    /// 获取账户余额。
+    fn account_balance(&self) -> impl Future<Output = Result<Vec<Balance>, AqtError>> + Send;
+
+    /// 获取指定股票的今日订单，包含已撤销、被拒绝等所有状态。
+    ///
+    /// 对账据此判断订单是否存在，不能只返回部分状态。
+    fn get_today_orders(&self, symbol: &str) -> impl Future<Output = Result<Vec<Order>, AqtError>> + Send;
+
+    /// 估算按 `price` 下单时指定方向的最大可下单数量（含融资、融券额度）。
+    ///
+    /// 卖出方向的结果包含可卖空数量，做空前用于检查保证金和可卖空额度。
+    fn estimate_max_quantity(
+        &self,
+        symbol: &str,
+        side: OrderSide,
+        price: Decimal,
+    ) -> impl Future<Output = Result<Decimal, AqtError>> + Send;
+
+    /// 获取指定股票的历史订单，包含买卖两个方向、所有状态的订单。
+    fn get_history_orders(
+        &self,
+        symbol: &str,
+        start_at: Option<OffsetDateTime>,
+        end_at: Option<OffsetDateTime>,
+    ) -> impl Future<Output = Result<Vec<Order>, AqtError>> + Send;
+
+    /// 券商视角的当前时间，模拟盘和回测可以覆盖为模拟时钟。
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// `MarketDataSource` 抽象了策略所需的行情数据接口。
+pub trait MarketDataSource: Send + Sync {
+    /// 获取指定股票、指定周期的K线数据。
+    fn get_candlesticks(
+        &self,
+        symbol: String,
+        period: String,
+    ) -> impl Future<Output = Result<Vec<Candle>, AqtError>> + Send;
+
+    /// 获取指定时间戳（秒）及之后的K线，用于增量同步；`since` 为 `None` 时返回最近的一批K线。
+    fn get_candlesticks_since(
+        &self,
+        symbol: String,
+        period: String,
+        since: Option<u64>,
+    ) -> impl Future<Output = Result<Vec<Candle>, AqtError>> + Send;
+
+    /// 获取市场温度。
+    fn get_market_temperature(&self) -> impl Future<Output = Result<MarketTemperature, AqtError>> + Send;
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::{error, info, warn};
+use longport::trade::{OrderSide, SubmitOrderResponse};
+use longport::{decimal, Decimal};
+use time::{Date, OffsetDateTime};
+use crate::config::config::BreakerConfig;
+use crate::models::balance::Balance;
+use crate::models::market::MarketData;
+use crate::models::order::{Order, OrderIntent};
+use crate::models::position::Position;
+use crate::services::broker::Broker;
+use crate::services::risk::{is_reducing, RiskReason};
+use crate::utils::error::AqtError;
+
+/// 熔断器的运行状态
+#[derive(Debug, Default)]
+struct BreakerState {
+    day: Option<Date>,                  // 当前交易日
+    peak: Decimal,                      // 当日净资产高点
+    failures: u32,                      // 连续下单失败次数
+    prices: HashMap<String, Decimal>,   // 最新价格，用于估算持仓市值和平仓
+    tripped: Option<String>,            // 触发原因
+    latched: bool,                      // 熔断标记文件是否存在（已写入或启动时读到）
+}
+
+/// 熔断器：跟踪当日净资产回撤和连续下单失败次数，超过阈值后熔断。
+///
+/// 熔断后停止开仓，只允许减仓，并把原因写入 `latch_file`；
+/// 熔断一直保持（包括进程重启），直到操作员删除该文件或调用 [`CircuitBreaker::reset`]。
+/// 标记文件写入失败时熔断只保持在内存中，不会因为找不到文件而自行解除；
+/// 标记文件无法读取时按已熔断处理。
+/// 多个策略共用同一个熔断器，行情通过 [`CircuitBreaker::observe`] 传入。
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// 创建熔断器，`latch_file` 存在时保持上次的熔断状态，无法读取时直接熔断
+    pub fn new(config: BreakerConfig) -> Self {
+        let tripped = match fs::read_to_string(&config.latch_file) {
+            Ok(reason) => Some(reason.trim().to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => Some(format!("无法读取熔断标记: {}", e)),
+        };
+        if let Some(reason) = &tripped {
+            error!("熔断仍未解除（{}）: {}", config.latch_file, reason);
+        }
+        CircuitBreaker {
+            config,
+            state: Mutex::new(BreakerState { latched: tripped.is_some(), tripped, ..Default::default() }),
+        }
+    }
+
+    pub fn config(&self) -> &BreakerConfig {
+        &self.config
+    }
+
+    /// 熔断标记文件
+    pub fn latch_file(&self) -> &Path {
+        Path::new(&self.config.latch_file)
+    }
+
+    /// 记录最新价格
+    pub fn observe(&self, data: &MarketData) {
+        if !data.price.is_zero() {
+            self.state.lock().unwrap().prices.insert(data.symbol.clone(), data.price);
+        }
+    }
+
+    /// 熔断原因，未熔断时返回 `None`；
+    /// 标记文件存在过、现在确认已被删除时视为操作员已解除熔断，无法确认时保持熔断
+    pub fn tripped(&self) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        if state.tripped.is_some() && state.latched && matches!(self.latch_file().try_exists(), Ok(false)) {
+            Self::clear(&mut state);
+        }
+        state.tripped.clone()
+    }
+
+    /// 解除熔断；标记文件存在但删除失败时保持熔断，否则重启后又会熔断
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Err(e) = fs::remove_file(self.latch_file()) {
+            if e.kind() != std::io::ErrorKind::NotFound && state.latched {
+                error!("删除熔断标记 {} 失败，保持熔断: {}", self.config.latch_file, e);
+                return;
+            }
+        }
+        if state.tripped.is_some() {
+            Self::clear(&mut state);
+        }
+    }
+
+    fn clear(state: &mut BreakerState) {
+        info!("熔断已解除，恢复交易");
+        state.tripped = None;
+        state.latched = false;
+        state.failures = 0;
+        state.day = None;
+    }
+
+    /// 按账户现金和持仓（最新价格，没有行情时按成本价）计算净资产
+    pub fn equity(&self, balances: &[Balance], positions: &[Position]) -> Decimal {
+        let cash = balances
+            .iter()
+            .find(|b| b.currency == self.config.currency)
+            .or(balances.first())
+            .map_or(Decimal::ZERO, |b| b.total_cash);
+        let state = self.state.lock().unwrap();
+        let holdings: Decimal = positions
+            .iter()
+            .map(|p| p.quantity * state.prices.get(&p.symbol).copied().unwrap_or(p.cost_price))
+            .sum();
+        cash + holdings
+    }
+
+    /// 更新当日净资产，回撤超过阈值时熔断，返回新触发的原因
+    pub fn update_equity(&self, equity: Decimal, today: Date) -> Option<String> {
+        let reason = {
+            let mut state = self.state.lock().unwrap();
+            if state.tripped.is_some() {
+                return None;
+            }
+            if state.day != Some(today) {
+                state.day = Some(today);
+                state.peak = equity;
+            }
+            state.peak = state.peak.max(equity);
+            let limit = self.config.max_drawdown?;
+            if state.peak <= Decimal::ZERO {
+                return None;
+            }
+            let drawdown = (state.peak - equity) / state.peak * decimal!(100);
+            if drawdown < decimal!(limit) {
+                return None;
+            }
+            format!("当日净资产从 {} 回撤到 {}（{:.2}%）", state.peak, equity, drawdown)
+        };
+        self.trip(reason)
+    }
+
+    /// 记录下单结果，连续失败次数达到阈值时熔断，返回新触发的原因；风控拒单不计入失败
+    pub fn record_result<T>(&self, result: &Result<T, AqtError>) -> Option<String> {
+        let reason = {
+            let mut state = self.state.lock().unwrap();
+            match result {
+                Ok(_) => {
+                    state.failures = 0;
+                    return None;
+                }
+                Err(AqtError::RiskRejected(_)) => return None,
+                Err(_) => state.failures += 1,
+            }
+            let limit = self.config.max_consecutive_failures?;
+            if state.tripped.is_some() || state.failures < limit {
+                return None;
+            }
+            format!("连续下单失败 {} 次", state.failures)
+        };
+        self.trip(reason)
+    }
+
+    fn trip(&self, reason: String) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        if state.tripped.is_some() {
+            return None;
+        }
+        error!("触发熔断，停止开仓: {}", reason);
+        let path = self.latch_file();
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, format!("{} {}\n", OffsetDateTime::now_utc(), reason))
+        };
+        state.latched = match write() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("写入熔断标记 {} 失败，熔断保持到进程内解除: {}", path.display(), e);
+                false
+            }
+        };
+        state.tripped = Some(reason.clone());
+        Some(reason)
+    }
+
+    fn price_of(&self, position: &Position) -> Decimal {
+        self.state.lock().unwrap().prices.get(&position.symbol).copied().unwrap_or(position.cost_price)
+    }
+
+    fn symbols(&self) -> Vec<String> {
+        self.state.lock().unwrap().prices.keys().cloned().collect()
+    }
+}
+
+/// 经过熔断器下单的券商：熔断后只允许减仓，触发时按配置撤单、平仓
+pub struct CircuitBreakerBroker<B: Broker> {
+    inner: Arc<B>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl<B: Broker> CircuitBreakerBroker<B> {
+    pub fn new(inner: Arc<B>, breaker: Arc<CircuitBreaker>) -> Self {
+        CircuitBreakerBroker { inner, breaker }
+    }
+
+    pub fn breaker(&self) -> &Arc<CircuitBreaker> {
+        &self.breaker
+    }
+
+    /// 检查当日净资产回撤，返回当前的熔断原因
+    pub async fn check(&self) -> Result<Option<String>, AqtError> {
+        if let Some(reason) = self.breaker.tripped() {
+            return Ok(Some(reason));
+        }
+        let balances = self.inner.account_balance().await?;
+        let positions = self.inner.stock_positions().await?;
+        let equity = self.breaker.equity(&balances, &positions);
+        if self.breaker.update_equity(equity, self.inner.now().date()).is_some() {
+            self.on_trip().await;
+        }
+        Ok(self.breaker.tripped())
+    }
+
+    /// 按 `check_interval_secs` 定期检查，直到进程退出
+    pub async fn monitor(&self) {
+        let interval = Duration::from_secs(self.breaker.config().check_interval_secs.max(1));
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = self.check().await {
+                warn!("熔断检查失败: {}", e);
+            }
+        }
+    }
+
+    /// 已熔断时拒绝不是减仓的订单
+    async fn guard(&self, symbol: &str, side: OrderSide, quantity: Decimal) -> Result<(), AqtError> {
+        if let Some(reason) = self.check().await? {
+            let positions = self.inner.stock_positions().await?;
+            let held = positions.iter().find(|p| p.symbol == symbol).map_or(Decimal::ZERO, |p| p.quantity);
+            if !is_reducing(held, side, quantity) {
+                warn!("已熔断，拒绝 {} {:?} {}", symbol, side, quantity);
+                return Err(AqtError::RiskRejected(RiskReason::CircuitOpen { reason }));
+            }
+        }
+        Ok(())
+    }
+
+    /// 记录下单结果，连续失败触发熔断时撤单、平仓
+    async fn after_submit<T>(&self, result: &Result<T, AqtError>) {
+        if self.breaker.record_result(result).is_some() {
+            self.on_trip().await;
+        }
+    }
+
+    /// 熔断触发时撤销未完成订单、平掉全部持仓（按配置）
+    async fn on_trip(&self) {
+        let config = self.breaker.config();
+        if !config.cancel_orders && !config.flatten {
+            return;
+        }
+        let positions = match self.inner.stock_positions().await {
+            Ok(positions) => positions,
+            Err(e) => {
+                error!("熔断后查询持仓失败: {}", e);
+                return;
+            }
+        };
+        if config.cancel_orders {
+            let mut symbols = self.breaker.symbols();
+            symbols.extend(positions.iter().map(|p| p.symbol.clone()));
+            symbols.sort();
+            symbols.dedup();
+            for symbol in symbols {
+                let orders = match self.inner.get_today_orders(&symbol).await {
+                    Ok(orders) => orders,
+                    Err(e) => {
+                        error!("熔断后查询 {} 订单失败: {}", symbol, e);
+                        continue;
+                    }
+                };
+                for order in orders.into_iter().filter(|o| o.is_open()) {
+                    match self.inner.cancel_order(order.order_id.clone()).await {
+                        Ok(()) => info!("熔断撤单 {} {}", symbol, order.order_id),
+                        Err(e) => error!("熔断撤单 {} {} 失败: {}", symbol, order.order_id, e),
+                    }
+                }
+            }
+        }
+        if config.flatten {
+            for position in positions.iter().filter(|p| !p.quantity.is_zero()) {
+                let (side, quantity) = if position.is_short() {
+                    (OrderSide::Buy, position.quantity.abs())
+                } else {
+                    (OrderSide::Sell, position.available_quantity)
+                };
+                if quantity.is_zero() {
+                    continue;
+                }
+                let price = self.breaker.price_of(position);
+                match self.inner.submit_order(position.symbol.clone(), side, price, quantity).await {
+                    Ok(resp) => info!("熔断平仓 {} {:?} {} {:?}", position.symbol, side, quantity, resp),
+                    Err(e) => error!("熔断平仓 {} 失败: {}", position.symbol, e),
+                }
+            }
+        }
+    }
+}
+
+impl<B: Broker> Broker for CircuitBreakerBroker<B> {
+    async fn submit_order(
+        &self,
+        symbol: String,
+        side: OrderSide,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<SubmitOrderResponse, AqtError> {
+        self.guard(&symbol, side, quantity).await?;
+        let result = self.inner.submit_order(symbol, side, price, quantity).await;
+        self.after_submit(&result).await;
+        result
+    }
+
+    async fn submit_intent(&self, intent: &OrderIntent) -> Result<SubmitOrderResponse, AqtError> {
+        self.guard(&intent.symbol, intent.side, intent.quantity).await?;
+        let result = self.inner.submit_intent(intent).await;
+        self.after_submit(&result).await;
+        result
+    }
+
+    async fn cancel_order(&self, order_id: String) -> Result<(), AqtError> {
+        self.inner.cancel_order(order_id).await
+    }
+
+    async fn stock_positions(&self) -> Result<Vec<Position>, AqtError> {
+        self.inner.stock_positions().await
+    }
+
+    async fn account_balance(&self) -> Result<Vec<Balance>, AqtError> {
+        self.inner.account_balance().await
+    }
+
+    async fn get_today_orders(&self, symbol: &str) -> Result<Vec<Order>, AqtError> {
+        self.inner.get_today_orders(symbol).await
+    }
+
+    async fn estimate_max_quantity(&self, symbol: &str, side: OrderSide, price: Decimal) -> Result<Decimal, AqtError> {
+        self.inner.estimate_max_quantity(symbol, side, price).await
+    }
+
+    async fn get_history_orders(
+        &self,
+        symbol: &str,
+        start_at: Option<OffsetDateTime>,
+        end_at: Option<OffsetDateTime>,
+    ) -> Result<Vec<Order>, AqtError> {
+        self.inner.get_history_orders(symbol, start_at, end_at).await
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        self.inner.now()
+    }
+}
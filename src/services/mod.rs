@@ -1 +1,6 @@
 pub mod service;
+pub mod broker;
+pub mod paper_broker;
+pub mod risk;
+pub mod circuit_breaker;
+pub mod pricing;
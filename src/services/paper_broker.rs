@@ -0,0 +1,517 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use log::{error, info};
+use longport::trade::{OrderSide, OrderStatus, OrderType, SubmitOrderResponse};
+use longport::{decimal, Decimal};
+use time::OffsetDateTime;
+use crate::config::config::PaperConfig;
+use crate::models::balance::{Balance, CashInfo};
+use crate::models::market::MarketData;
+use crate::models::order::{Order, OrderIntent};
+use crate::models::position::Position;
+use crate::services::broker::Broker;
+use crate::services::pricing::{OrderPrice, Pricer};
+use crate::utils::error::AqtError;
+
+/// 模拟成交记录
+#[derive(Debug, Clone)]
+pub struct Fill {
+    /// 订单ID
+    pub order_id: String,
+    /// 股票代码
+    pub symbol: String,
+    /// 成交方向
+    pub side: OrderSide,
+    /// 成交价格
+    pub price: Decimal,
+    /// 成交数量
+    pub quantity: Decimal,
+    /// 成交时间
+    pub ts: OffsetDateTime,
+}
+
+/// 模拟账户的内部状态
+struct PaperState {
+    cash: Decimal,                        // 现金余额（包含冻结部分）
+    collateral: HashMap<String, Decimal>, // 卖空所得，作为保证金冻结，平空时释放
+    positions: HashMap<String, Position>, // 持仓
+    orders: Vec<Order>,                   // 全部订单
+    fills: Vec<Fill>,                     // 成交记录
+    last_prices: HashMap<String, Decimal>, // 最新价格
+    last_volumes: HashMap<String, i64>,   // 最新累计成交量
+    next_id: u64,                         // 下一个订单编号
+    clock: Option<OffsetDateTime>,        // 最近一次行情时间
+}
+
+/// `PaperBroker` 是进程内的模拟券商。
+///
+/// 它按与 `Service::submit_order` 相同的委托定价（[`Pricer`]，默认现价 ±5% 限价单）挂单，
+/// 市价单在下一笔行情按最新价成交。根据收到的 `MarketData` 行情撮合成交，维护现金、持仓和订单状态
+/// （New → PartialFilled → Filled/Canceled）。
+///
+/// 配置了 `short_margin` 时允许卖出超过持仓的数量（做空），空头持仓数量为负数，
+/// 可卖空数量受净资产和保证金比例限制。卖空所得计入现金但作为保证金冻结，只能用于平空，不增加购买力。
+pub struct PaperBroker {
+    currency: String,
+    participation: Option<Decimal>,
+    short_margin: Option<Decimal>,
+    pricer: Pricer,
+    state: Mutex<PaperState>,
+}
+
+impl PaperBroker {
+    /// 根据模拟盘配置创建一个新的 `PaperBroker`
+    pub fn new(config: &PaperConfig) -> Self {
+        PaperBroker {
+            currency: config.currency.clone(),
+            participation: config.participation.map(|p| decimal!(p)),
+            short_margin: config.short_margin.map(|m| decimal!(m)),
+            pricer: Pricer::default(),
+            state: Mutex::new(PaperState {
+                cash: decimal!(config.initial_cash),
+                collateral: HashMap::new(),
+                positions: HashMap::new(),
+                orders: Vec::new(),
+                fills: Vec::new(),
+                last_prices: HashMap::new(),
+                last_volumes: HashMap::new(),
+                next_id: 1,
+                clock: None,
+            }),
+        }
+    }
+
+    /// 使用指定的委托定价
+    pub fn with_pricer(mut self, pricer: Pricer) -> Self {
+        self.pricer = pricer;
+        self
+    }
+
+    /// 处理一笔行情，撮合该股票所有未完成的订单
+    pub fn on_market_data(&self, data: &MarketData) {
+        let mut state = self.state.lock().unwrap();
+        state.clock = Some(data.ts);
+        state.last_prices.insert(data.symbol.clone(), data.price);
+
+        // 根据累计成交量的增量计算本次最多可成交数量
+        let prev_volume = state.last_volumes.insert(data.symbol.clone(), data.volume);
+        let max_fill = match (self.participation, prev_volume) {
+            (Some(ratio), Some(prev)) => {
+                Some((Decimal::from((data.volume - prev).max(0)) * ratio).floor())
+            }
+            _ => None,
+        };
+
+        let PaperState { cash, collateral, positions, orders, fills, .. } = &mut *state;
+        for order in orders.iter_mut() {
+            if order.symbol != data.symbol
+                || !(order.status == OrderStatus::New || order.status == OrderStatus::PartialFilled)
+            {
+                continue;
+            }
+            let limit = order.price.unwrap_or(data.price);
+            let crossed = match order.side {
+                OrderSide::Buy => data.price <= limit,
+                OrderSide::Sell => data.price >= limit,
+                _ => false,
+            };
+            if !crossed {
+                continue;
+            }
+
+            let remaining = order.quantity - order.executed_quantity;
+            let quantity = match max_fill {
+                Some(max) => remaining.min(max),
+                None => remaining,
+            };
+            if quantity.is_zero() {
+                continue;
+            }
+
+            let px = data.price;
+            let position = positions
+                .entry(order.symbol.clone())
+                .or_insert_with(|| Position {
+                    symbol: order.symbol.clone(),
+                    currency: self.currency.clone(),
+                    ..Default::default()
+                });
+            if order.side == OrderSide::Buy {
+                // 平空的部分按比例释放卖空时冻结的保证金
+                let short = (-position.quantity).max(Decimal::ZERO);
+                if !short.is_zero() {
+                    let held = collateral.get(&order.symbol).copied().unwrap_or_default();
+                    if quantity >= short {
+                        collateral.remove(&order.symbol);
+                    } else {
+                        collateral.insert(order.symbol.clone(), held - held * quantity / short);
+                    }
+                }
+                *cash -= px * quantity;
+                Self::apply_fill(position, px, quantity);
+                position.available_quantity += quantity;
+            } else {
+                // 卖出数量在下单时已从可用数量中冻结，超过多头持仓的部分为卖空，所得冻结为保证金
+                let opened = (quantity - position.quantity.max(Decimal::ZERO)).max(Decimal::ZERO);
+                if !opened.is_zero() {
+                    *collateral.entry(order.symbol.clone()).or_default() += px * opened;
+                }
+                *cash += px * quantity;
+                Self::apply_fill(position, px, -quantity);
+            }
+            if position.quantity.is_zero() && position.available_quantity.is_zero() {
+                positions.remove(&order.symbol);
+            }
+
+            let executed_value = order.executed_price.unwrap_or_default() * order.executed_quantity + px * quantity;
+            order.executed_quantity += quantity;
+            order.executed_price = Some(executed_value / order.executed_quantity);
+            order.status = if order.executed_quantity == order.quantity {
+                OrderStatus::Filled
+            } else {
+                OrderStatus::PartialFilled
+            };
+            info!("模拟成交 {} {:?} {} @ {}", order.symbol, order.side, quantity, px);
+            fills.push(Fill {
+                order_id: order.order_id.clone(),
+                symbol: order.symbol.clone(),
+                side: order.side,
+                price: px,
+                quantity,
+                ts: data.ts,
+            });
+        }
+    }
+
+    /// 按成交更新持仓数量和成本价，`quantity` 买入为正、卖出为负。
+    ///
+    /// 加仓（含加空）时按数量加权计算成本价，减仓时成本价不变，反手时以成交价为成本价。
+    fn apply_fill(position: &mut Position, px: Decimal, quantity: Decimal) {
+        let total = position.quantity + quantity;
+        if position.quantity.is_zero() || position.quantity.is_sign_positive() == quantity.is_sign_positive() {
+            position.cost_price = (position.cost_price * position.quantity.abs() + px * quantity.abs()) / total.abs();
+        } else if !total.is_zero() && total.is_sign_positive() != position.quantity.is_sign_positive() {
+            position.cost_price = px;
+        }
+        position.quantity = total;
+    }
+
+    /// 获取全部成交记录
+    pub fn fills(&self) -> Vec<Fill> {
+        self.state.lock().unwrap().fills.clone()
+    }
+
+    /// 获取当前现金余额
+    pub fn cash(&self) -> Decimal {
+        self.state.lock().unwrap().cash
+    }
+
+    /// 按最新价格计算的账户净值
+    pub fn equity(&self) -> Decimal {
+        let state = self.state.lock().unwrap();
+        Self::net_assets(&state)
+    }
+
+    fn net_assets(state: &PaperState) -> Decimal {
+        let market_value: Decimal = state
+            .positions
+            .values()
+            .map(|p| {
+                let px = state.last_prices.get(&p.symbol).copied().unwrap_or(p.cost_price);
+                px * p.quantity
+            })
+            .sum();
+        state.cash + market_value
+    }
+
+    /// 未成交买单冻结的资金
+    fn frozen_cash(state: &PaperState) -> Decimal {
+        state
+            .orders
+            .iter()
+            .filter(|o| {
+                o.side == OrderSide::Buy
+                    && (o.status == OrderStatus::New || o.status == OrderStatus::PartialFilled)
+            })
+            // 市价单按最新价格冻结
+            .map(|o| {
+                let price = o.price.or_else(|| state.last_prices.get(&o.symbol).copied()).unwrap_or_default();
+                price * (o.quantity - o.executed_quantity)
+            })
+            .sum()
+    }
+
+    /// 空头持仓数量（正数），没有空头时为 0
+    fn short_quantity(state: &PaperState, symbol: &str) -> Decimal {
+        state
+            .positions
+            .get(symbol)
+            .map(|p| (-p.quantity).max(Decimal::ZERO))
+            .unwrap_or_default()
+    }
+
+    /// 未成交买单的剩余数量
+    fn pending_buy_quantity(state: &PaperState, symbol: &str) -> Decimal {
+        state
+            .orders
+            .iter()
+            .filter(|o| {
+                o.symbol == symbol
+                    && o.side == OrderSide::Buy
+                    && (o.status == OrderStatus::New || o.status == OrderStatus::PartialFilled)
+            })
+            .map(|o| o.quantity - o.executed_quantity)
+            .sum()
+    }
+
+    /// 尚未被未成交买单用于平空的空头数量，及其对应的保证金
+    fn uncovered_short(state: &PaperState, symbol: &str) -> (Decimal, Decimal) {
+        let short = Self::short_quantity(state, symbol);
+        let held = state.collateral.get(symbol).copied().unwrap_or_default();
+        if short.is_zero() {
+            return (Decimal::ZERO, Decimal::ZERO);
+        }
+        let uncovered = (short - Self::pending_buy_quantity(state, symbol)).max(Decimal::ZERO);
+        (uncovered, held * uncovered / short)
+    }
+
+    /// 冻结的卖空保证金，已有买单平空的部分计入买单冻结的资金，不重复冻结
+    fn frozen_collateral(state: &PaperState) -> Decimal {
+        state.collateral.keys().map(|symbol| Self::uncovered_short(state, symbol).1).sum()
+    }
+
+    /// 可用于买入的资金：现金减去买单冻结的资金和卖空保证金
+    fn available_cash(state: &PaperState) -> Decimal {
+        state.cash - Self::frozen_cash(state) - Self::frozen_collateral(state)
+    }
+
+    /// 买入 `quantity` 所需的可用资金，平空的部分可以使用释放的保证金
+    fn buy_cost(state: &PaperState, symbol: &str, price: Decimal, quantity: Decimal) -> Decimal {
+        let (uncovered, held) = Self::uncovered_short(state, symbol);
+        if uncovered.is_zero() {
+            return price * quantity;
+        }
+        price * quantity - held * quantity.min(uncovered) / uncovered
+    }
+
+    /// 最多可买入的数量
+    fn max_buy_quantity(state: &PaperState, symbol: &str, price: Decimal) -> Decimal {
+        let available = Self::available_cash(state).max(Decimal::ZERO);
+        let (uncovered, held) = Self::uncovered_short(state, symbol);
+        // 全部平空后剩余资金按价格买入
+        if available + held >= price * uncovered {
+            return ((available + held) / price).floor();
+        }
+        // 资金不足以全部平空：平空数量 q 需要 q × (价格 - 卖空均价) ≤ 可用资金
+        (available / (price - held / uncovered)).floor().max(Decimal::ZERO)
+    }
+
+    /// 最多可卖出的数量：可用持仓加上按保证金比例计算的可卖空数量
+    fn max_sell_quantity(&self, state: &PaperState, symbol: &str, price: Decimal) -> Decimal {
+        let available = state
+            .positions
+            .get(symbol)
+            .map(|p| p.available_quantity.max(Decimal::ZERO))
+            .unwrap_or_default();
+        let Some(margin) = self.short_margin else {
+            return available;
+        };
+        if price <= Decimal::ZERO || margin <= Decimal::ZERO {
+            return available;
+        }
+        // 已有空头（含未成交的卖空委托）占用的保证金
+        let used: Decimal = state
+            .positions
+            .values()
+            .filter(|p| p.available_quantity < Decimal::ZERO)
+            .map(|p| {
+                let px = state.last_prices.get(&p.symbol).copied().unwrap_or(p.cost_price);
+                -p.available_quantity * px * margin
+            })
+            .sum();
+        let free = Self::net_assets(state) - Self::frozen_cash(state) - used;
+        available + (free / (price * margin)).floor().max(Decimal::ZERO)
+    }
+
+    /// 模拟盘拒绝订单，模拟盘没有业务错误码，统一使用 0
+    fn reject(message: String) -> AqtError {
+        error!("模拟盘拒绝请求: {}", message);
+        AqtError::ApiRejected { code: 0, message }
+    }
+
+    fn clock_of(state: &PaperState) -> OffsetDateTime {
+        state.clock.unwrap_or_else(OffsetDateTime::now_utc)
+    }
+
+    /// 按限价冻结资金或持仓后挂单，`remark` 为订单备注
+    fn submit(
+        &self,
+        symbol: String,
+        side: OrderSide,
+        price: Decimal,
+        quantity: Decimal,
+        remark: String,
+    ) -> Result<SubmitOrderResponse, AqtError> {
+        let order_price = self.pricer.price(&symbol, side, price)?;
+        // 市价单按现价检查资金和可卖数量
+        let submitted_price = order_price.limit_or(price);
+        let mut state = self.state.lock().unwrap();
+        if quantity <= Decimal::ZERO {
+            return Err(Self::reject("数量必须大于0".to_string()));
+        }
+        match side {
+            OrderSide::Buy => {
+                if Self::available_cash(&state) < Self::buy_cost(&state, &symbol, submitted_price, quantity) {
+                    return Err(Self::reject(format!("{} 资金不足", symbol)));
+                }
+            }
+            OrderSide::Sell => {
+                if self.max_sell_quantity(&state, &symbol, submitted_price) < quantity {
+                    let reason = if self.short_margin.is_some() { "可卖空数量不足" } else { "可用持仓不足" };
+                    return Err(Self::reject(format!("{} {}", symbol, reason)));
+                }
+                // 冻结卖出数量，超过持仓的部分为卖空
+                let position = state.positions.entry(symbol.clone()).or_insert_with(|| Position {
+                    symbol: symbol.clone(),
+                    currency: self.currency.clone(),
+                    ..Default::default()
+                });
+                position.available_quantity -= quantity;
+            }
+            _ => {
+                return Err(Self::reject("未知的订单方向".to_string()));
+            }
+        }
+
+        let order_id = format!("PAPER-{}", state.next_id);
+        state.next_id += 1;
+        let submitted_at = Self::clock_of(&state);
+        state.orders.push(Order {
+            order_id: order_id.clone(),
+            symbol,
+            side,
+            status: OrderStatus::New,
+            order_type: if order_price == OrderPrice::Market { OrderType::MO } else { OrderType::LO },
+            quantity,
+            executed_quantity: Decimal::ZERO,
+            price: match order_price {
+                OrderPrice::Market => None,
+                OrderPrice::Limit(price) => Some(price),
+            },
+            executed_price: None,
+            submitted_at,
+            remark,
+        });
+        Ok(SubmitOrderResponse { order_id })
+    }
+}
+
+impl Broker for PaperBroker {
+    /// 模拟盘使用最近一次行情的时间作为当前时间，回测时即为模拟时钟
+    fn now(&self) -> OffsetDateTime {
+        Self::clock_of(&self.state.lock().unwrap())
+    }
+
+    async fn submit_order(
+        &self,
+        symbol: String,
+        side: OrderSide,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<SubmitOrderResponse, AqtError> {
+        self.submit(symbol, side, price, quantity, String::new())
+    }
+
+    async fn submit_intent(&self, intent: &OrderIntent) -> Result<SubmitOrderResponse, AqtError> {
+        self.submit(intent.symbol.clone(), intent.side, intent.price, intent.quantity, intent.key.clone())
+    }
+
+    async fn cancel_order(&self, order_id: String) -> Result<(), AqtError> {
+        let mut state = self.state.lock().unwrap();
+        let PaperState { positions, orders, .. } = &mut *state;
+        let order = orders.iter_mut().find(|o| {
+            o.order_id == order_id
+                && (o.status == OrderStatus::New || o.status == OrderStatus::PartialFilled)
+        });
+        match order {
+            Some(o) => {
+                o.status = OrderStatus::Canceled;
+                // 释放冻结的卖出数量
+                if o.side == OrderSide::Sell {
+                    if let Some(p) = positions.get_mut(&o.symbol) {
+                        p.available_quantity += o.quantity - o.executed_quantity;
+                        if p.quantity.is_zero() && p.available_quantity.is_zero() {
+                            positions.remove(&o.symbol);
+                        }
+                    }
+                }
+                Ok(())
+            }
+            None => Err(Self::reject(format!("模拟订单 {} 不存在或已完成", order_id))),
+        }
+    }
+
+    async fn stock_positions(&self) -> Result<Vec<Position>, AqtError> {
+        Ok(self.state.lock().unwrap().positions.values().cloned().collect())
+    }
+
+    async fn account_balance(&self) -> Result<Vec<Balance>, AqtError> {
+        let state = self.state.lock().unwrap();
+        let frozen = Self::frozen_cash(&state) + Self::frozen_collateral(&state);
+        let available = state.cash - frozen;
+        Ok(vec![Balance {
+            currency: self.currency.clone(),
+            total_cash: state.cash,
+            net_assets: Self::net_assets(&state),
+            buy_power: available,
+            cash_infos: vec![CashInfo {
+                currency: self.currency.clone(),
+                withdraw_cash: available,
+                available_cash: available,
+                frozen_cash: frozen,
+            }],
+        }])
+    }
+
+    async fn estimate_max_quantity(&self, symbol: &str, side: OrderSide, price: Decimal) -> Result<Decimal, AqtError> {
+        let submitted_price = self.pricer.price(symbol, side, price)?.limit_or(price);
+        let state = self.state.lock().unwrap();
+        match side {
+            OrderSide::Buy if submitted_price > Decimal::ZERO => {
+                Ok(Self::max_buy_quantity(&state, symbol, submitted_price))
+            }
+            OrderSide::Sell => Ok(self.max_sell_quantity(&state, symbol, submitted_price)),
+            _ => Ok(Decimal::ZERO),
+        }
+    }
+
+    async fn get_today_orders(&self, symbol: &str) -> Result<Vec<Order>, AqtError> {
+        let state = self.state.lock().unwrap();
+        let today = Self::clock_of(&state).date();
+        Ok(state
+            .orders
+            .iter()
+            .filter(|o| o.symbol == symbol && o.submitted_at.date() == today)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_history_orders(
+        &self,
+        symbol: &str,
+        start_at: Option<OffsetDateTime>,
+        end_at: Option<OffsetDateTime>,
+    ) -> Result<Vec<Order>, AqtError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .orders
+            .iter()
+            .filter(|o| {
+                o.symbol == symbol
+                    && start_at.is_none_or(|t| o.submitted_at >= t)
+                    && end_at.is_none_or(|t| o.submitted_at <= t)
+            })
+            .cloned()
+            .collect())
+    }
+}
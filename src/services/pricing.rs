@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use log::warn;
+use longport::quote::PushDepth;
+use longport::trade::OrderSide;
+use longport::{decimal, Decimal};
+use serde::Deserialize;
+use time::{Duration, OffsetDateTime};
+use crate::config::config::Configs;
+use crate::utils::error::AqtError;
+
+/// 委托价格
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderPrice {
+    /// 市价单
+    Market,
+    /// 限价单
+    Limit(Decimal),
+}
+
+impl OrderPrice {
+    /// 限价单的委托价格，市价单返回 `reference`
+    pub fn limit_or(self, reference: Decimal) -> Decimal {
+        match self {
+            OrderPrice::Market => reference,
+            OrderPrice::Limit(price) => price,
+        }
+    }
+}
+
+/// 委托定价方式，用 `kind` 区分，未配置时按现价上下浮动 5%
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PricingPolicy {
+    /// 买入按现价上浮 `pct`%，卖出下浮 `pct`%
+    Percent {
+        #[serde(default = "default_percent")]
+        pct: f64,
+    },
+    /// 市价单
+    Market,
+    /// 买入按卖一价，卖出按买一价
+    BestQuote,
+    /// 买卖一价的中间价，买入加 `bps` 个基点，卖出减 `bps` 个基点
+    Mid {
+        #[serde(default)]
+        bps: f64,
+    },
+    /// 买入按现价加 `offset`，卖出按现价减 `offset`
+    LastOffset { offset: f64 },
+}
+
+fn default_percent() -> f64 {
+    5.0
+}
+
+impl Default for PricingPolicy {
+    fn default() -> Self {
+        PricingPolicy::Percent { pct: default_percent() }
+    }
+}
+
+impl PricingPolicy {
+    /// 是否需要实时盘口
+    pub fn needs_depth(&self) -> bool {
+        matches!(self, PricingPolicy::BestQuote | PricingPolicy::Mid { .. })
+    }
+}
+
+/// 买一价和卖一价，没有挂单的一侧为 `None`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BestQuote {
+    pub bid: Option<Decimal>,
+    pub ask: Option<Decimal>,
+}
+
+/// 每个股票最新的买一、卖一价及更新时间，由 [`DepthCollectors`](crate::collect::depth::DepthCollectors) 的推送更新
+#[derive(Debug, Default)]
+pub struct DepthBook {
+    quotes: Mutex<HashMap<String, (BestQuote, OffsetDateTime)>>,
+}
+
+impl DepthBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 更新买一、卖一价，更新时间为当前时间
+    pub fn update(&self, symbol: &str, quote: BestQuote) {
+        self.update_at(symbol, quote, OffsetDateTime::now_utc());
+    }
+
+    /// 更新买一、卖一价及其更新时间
+    pub fn update_at(&self, symbol: &str, quote: BestQuote, at: OffsetDateTime) {
+        self.quotes.lock().unwrap().insert(symbol.to_string(), (quote, at));
+    }
+
+    /// 处理一次盘口推送，取档位最小的有效价格
+    pub fn on_depth(&self, symbol: &str, depth: &PushDepth) {
+        let best = |levels: &[longport::quote::Depth]| {
+            levels
+                .iter()
+                .filter(|d| d.price.is_some_and(|p| !p.is_zero()))
+                .min_by_key(|d| d.position)
+                .and_then(|d| d.price)
+        };
+        self.update(symbol, BestQuote { bid: best(&depth.bids), ask: best(&depth.asks) });
+    }
+
+    pub fn best(&self, symbol: &str) -> Option<BestQuote> {
+        self.quote(symbol).map(|(quote, _)| quote)
+    }
+
+    /// 买一、卖一价及其更新时间
+    pub fn quote(&self, symbol: &str) -> Option<(BestQuote, OffsetDateTime)> {
+        self.quotes.lock().unwrap().get(symbol).copied()
+    }
+}
+
+/// 证券的最小价格变动单位，按市场和价格区间确定
+pub fn tick_size(symbol: &str, price: Decimal) -> Decimal {
+    if symbol.ends_with(".HK") {
+        // 港交所价位表
+        let table = [
+            (decimal!(0.25), decimal!(0.001)),
+            (decimal!(0.5), decimal!(0.005)),
+            (decimal!(10), decimal!(0.01)),
+            (decimal!(20), decimal!(0.02)),
+            (decimal!(100), decimal!(0.05)),
+            (decimal!(200), decimal!(0.1)),
+            (decimal!(500), decimal!(0.2)),
+            (decimal!(1000), decimal!(0.5)),
+            (decimal!(2000), decimal!(1)),
+            (decimal!(5000), decimal!(2)),
+        ];
+        return table.iter().find(|(upper, _)| price < *upper).map_or(decimal!(5), |(_, tick)| *tick);
+    }
+    if symbol.ends_with(".US") && price < decimal!(1) {
+        return decimal!(0.0001);
+    }
+    decimal!(0.01)
+}
+
+/// 按最小价格变动单位取整：买入向下、卖出向上，不会比计算出的价格更差
+pub fn round_to_tick(price: Decimal, tick: Decimal, side: OrderSide) -> Decimal {
+    if tick <= Decimal::ZERO {
+        return price;
+    }
+    let ticks = price / tick;
+    let ticks = if side == OrderSide::Sell { ticks.ceil() } else { ticks.floor() };
+    (ticks * tick).normalize()
+}
+
+/// 委托定价：按每个股票配置的 [`PricingPolicy`] 和最小价格变动单位计算委托价格。
+///
+/// 需要盘口的定价方式在没有盘口数据或盘口超过 `max_quote_age` 未更新时，按默认的上下浮动 5% 委托。
+#[derive(Debug, Clone)]
+pub struct Pricer {
+    policies: HashMap<String, PricingPolicy>,
+    tick_sizes: HashMap<String, Decimal>,
+    book: Option<Arc<DepthBook>>,
+    max_quote_age: Duration,
+}
+
+/// 盘口默认的有效时间
+const DEFAULT_MAX_QUOTE_AGE: Duration = Duration::seconds(10);
+
+impl Default for Pricer {
+    fn default() -> Self {
+        Pricer {
+            policies: HashMap::new(),
+            tick_sizes: HashMap::new(),
+            book: None,
+            max_quote_age: DEFAULT_MAX_QUOTE_AGE,
+        }
+    }
+}
+
+impl Pricer {
+    /// 按所有启用策略的股票配置创建
+    pub fn from_config(config: &Configs) -> Self {
+        let mut pricer = Pricer::default();
+        for strategy in config.strategy_configs() {
+            for sym in strategy.symbols {
+                if let Some(tick) = sym.tick_size {
+                    pricer.tick_sizes.insert(sym.symbol.clone(), decimal!(tick));
+                }
+                pricer.policies.insert(sym.symbol, sym.pricing);
+            }
+        }
+        pricer
+    }
+
+    /// 设置股票的定价方式
+    pub fn with_policy(mut self, symbol: &str, policy: PricingPolicy) -> Self {
+        self.policies.insert(symbol.to_string(), policy);
+        self
+    }
+
+    /// 使用实时盘口
+    pub fn with_book(mut self, book: Arc<DepthBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// 盘口超过 `age` 未更新时视为过期，默认 10 秒
+    pub fn with_max_quote_age(mut self, age: Duration) -> Self {
+        self.max_quote_age = age;
+        self
+    }
+
+    /// 是否有股票的定价方式需要实时盘口
+    pub fn needs_depth(&self) -> bool {
+        self.policies.values().any(PricingPolicy::needs_depth)
+    }
+
+    /// 需要实时盘口的股票
+    pub fn depth_symbols(&self) -> Vec<String> {
+        self.policies.iter().filter(|(_, p)| p.needs_depth()).map(|(s, _)| s.clone()).collect()
+    }
+
+    /// 以现价 `last` 买卖 `symbol` 时的委托价格，计算出的限价不是正数时返回 [`AqtError::Data`]
+    pub fn price(&self, symbol: &str, side: OrderSide, last: Decimal) -> Result<OrderPrice, AqtError> {
+        let policy = self.policies.get(symbol).cloned().unwrap_or_default();
+        let buy = side == OrderSide::Buy;
+        let quote = self.book.as_ref().and_then(|b| b.quote(symbol));
+        let stale = quote.is_some_and(|(_, at)| OffsetDateTime::now_utc() - at > self.max_quote_age);
+        let best = quote.filter(|_| !stale).map(|(best, _)| best).unwrap_or_default();
+        let raw = match policy {
+            PricingPolicy::Market => return Ok(OrderPrice::Market),
+            PricingPolicy::Percent { pct } => Some(Self::percent(side, last, pct)),
+            PricingPolicy::BestQuote => if buy { best.ask } else { best.bid },
+            PricingPolicy::Mid { bps } => best.bid.zip(best.ask).map(|(bid, ask)| {
+                let mid = (bid + ask) / decimal!(2);
+                let offset = mid * decimal!(bps) / decimal!(10000);
+                if buy { mid + offset } else { mid - offset }
+            }),
+            PricingPolicy::LastOffset { offset } => {
+                Some(if buy { last + decimal!(offset) } else { last - decimal!(offset) })
+            }
+        };
+        let price = raw.unwrap_or_else(|| {
+            if stale {
+                warn!("{} 盘口超过 {} 秒未更新，按现价上下浮动 {}% 委托", symbol, self.max_quote_age.whole_seconds(), default_percent());
+            } else {
+                warn!("{} 没有盘口数据，按现价上下浮动 {}% 委托", symbol, default_percent());
+            }
+            Self::percent(side, last, default_percent())
+        });
+        let tick = self.tick_sizes.get(symbol).copied().unwrap_or_else(|| tick_size(symbol, price));
+        let price = round_to_tick(price, tick, side);
+        if price <= Decimal::ZERO {
+            return Err(AqtError::Data(format!("{} 现价 {} 计算出的委托价格 {} 不是正数", symbol, last, price)));
+        }
+        Ok(OrderPrice::Limit(price))
+    }
+
+    fn percent(side: OrderSide, last: Decimal, pct: f64) -> Decimal {
+        let ratio = decimal!(pct) / decimal!(100);
+        match side {
+            OrderSide::Buy => last * (Decimal::ONE + ratio),
+            OrderSide::Sell => last * (Decimal::ONE - ratio),
+            _ => last,
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::{info, warn};
+use longport::trade::{OrderSide, SubmitOrderResponse};
+use longport::{decimal, Decimal};
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
+use crate::config::config::RiskConfig;
+use crate::models::balance::Balance;
+use crate::models::order::{Order, OrderIntent};
+use crate::models::position::Position;
+use crate::services::broker::{Broker, MarketDataSource};
+use crate::utils::error::AqtError;
+
+/// 风控拒单的审计日志目标，可以在 log4rs 中单独输出到文件
+pub const AUDIT_TARGET: &str = "risk_audit";
+
+/// 风控拒绝下单的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskReason {
+    /// 单笔订单金额超过上限
+    OrderNotional { notional: Decimal, limit: Decimal },
+    /// 成交后单个股票的持仓金额超过上限
+    SymbolNotional { exposure: Decimal, limit: Decimal },
+    /// 成交后所有持仓的总金额超过上限
+    GrossExposure { exposure: Decimal, limit: Decimal },
+    /// 持仓股票数量已达上限
+    MaxPositions { count: usize, limit: usize },
+    /// 该股票当日下单次数已达上限
+    DailyOrders { count: usize, limit: usize },
+    /// 当日亏损（已实现 + 未实现）已达上限
+    DailyLoss { loss: Decimal, limit: Decimal },
+    /// 成交后分组的持仓金额超过上限
+    GroupExposure { group: String, exposure: Decimal, limit: Decimal },
+    /// 与现有持仓按持仓金额加权的平均相关系数超过上限
+    Correlation { correlation: f64, limit: f64 },
+    /// 熔断已触发，停止开仓
+    CircuitOpen { reason: String },
+}
+
+impl fmt::Display for RiskReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskReason::OrderNotional { notional, limit } => write!(f, "订单金额 {} 超过上限 {}", notional, limit),
+            RiskReason::SymbolNotional { exposure, limit } => write!(f, "股票持仓金额 {} 超过上限 {}", exposure, limit),
+            RiskReason::GrossExposure { exposure, limit } => write!(f, "总持仓金额 {} 超过上限 {}", exposure, limit),
+            RiskReason::MaxPositions { count, limit } => write!(f, "持仓股票数量 {} 已达上限 {}", count, limit),
+            RiskReason::DailyOrders { count, limit } => write!(f, "当日下单 {} 次已达上限 {}", count, limit),
+            RiskReason::DailyLoss { loss, limit } => write!(f, "当日亏损 {} 已达上限 {}", loss, limit),
+            RiskReason::GroupExposure { group, exposure, limit } => {
+                write!(f, "分组 {} 持仓金额 {} 超过上限 {}", group, exposure, limit)
+            }
+            RiskReason::Correlation { correlation, limit } => {
+                write!(f, "与现有持仓的加权平均相关系数 {:.2} 超过上限 {:.2}", correlation, limit)
+            }
+            RiskReason::CircuitOpen { reason } => write!(f, "已熔断: {}", reason),
+        }
+    }
+}
+
+/// 持仓为 `held` 时，订单是否只减少持仓而不反手
+pub(crate) fn is_reducing(held: Decimal, side: OrderSide, quantity: Decimal) -> bool {
+    let after = held + if side == OrderSide::Sell { -quantity } else { quantity };
+    after.abs() < held.abs() && (after.is_zero() || after.is_sign_positive() == held.is_sign_positive())
+}
+
+/// 两组收益率的皮尔逊相关系数，按末尾对齐取共同长度；少于 2 个或方差为 0 时返回 `None`
+pub fn correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return None;
+    }
+    let (a, b) = (&a[a.len() - n..], &b[b.len() - n..]);
+    let mean_a = a.iter().sum::<f64>() / n as f64;
+    let mean_b = b.iter().sum::<f64>() / n as f64;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        return None;
+    }
+    Some(cov / (var_a * var_b).sqrt())
+}
+
+/// 当日亏损的基准：交易日及当天开始时的净资产
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DayStart {
+    /// 交易日（儒略日）
+    pub day: i32,
+    /// 当天开始时的净资产
+    pub equity: Decimal,
+}
+
+impl DayStart {
+    fn date(&self) -> Option<Date> {
+        Date::from_julian_day(self.day).ok()
+    }
+}
+
+/// 一次被风控拒绝的下单
+#[derive(Debug, Clone)]
+pub struct RiskRejection {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub reason: RiskReason,
+    pub ts: OffsetDateTime,
+}
+
+/// 下单前风控：包装任意 `Broker`，所有订单先经过 [`RiskConfig`] 中的限制再提交。
+///
+/// 只减少持仓的订单（平多、平空）不受限制，保证任何时候都可以止损离场。
+/// 当日亏损以交易日开始时的净资产为基准：[`RiskCheckedBroker::monitor_daily_loss`] 在日期变化后立即记录，
+/// 没有运行时以当天第一次检查时的净资产为基准；配置了 [`RiskCheckedBroker::with_state_file`] 时基准写入文件，
+/// 当天重启后继续使用。
+/// 分组限制使用 [`RiskCheckedBroker::with_groups`] 设置的股票分组；相关系数使用
+/// [`RiskCheckedBroker::update_closes`] 记录的收盘价，收益率不足 `correlation_window` 个时不检查。
+/// 被拒绝的订单返回 [`AqtError::RiskRejected`]，并写入 [`AUDIT_TARGET`] 审计日志。
+pub struct RiskCheckedBroker<B: Broker> {
+    inner: Arc<B>,
+    limits: RiskConfig,
+    groups: HashMap<String, Vec<String>>,
+    returns: Mutex<HashMap<String, Vec<f64>>>,
+    day_start: Mutex<Option<DayStart>>,
+    state_file: Option<PathBuf>,
+    rejections: Mutex<Vec<RiskRejection>>,
+}
+
+impl<B: Broker> RiskCheckedBroker<B> {
+    pub fn new(inner: Arc<B>, limits: RiskConfig) -> Self {
+        RiskCheckedBroker {
+            inner,
+            limits,
+            groups: HashMap::new(),
+            returns: Mutex::new(HashMap::new()),
+            day_start: Mutex::new(None),
+            state_file: None,
+            rejections: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 当日亏损基准保存到 `path`，创建时读取文件中已有的基准；文件不存在或损坏时重新记录
+    pub fn with_state_file(mut self, path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            match fs::read(&path).map_err(|e| e.to_string()).and_then(|b| serde_json::from_slice(&b).map_err(|e| e.to_string())) {
+                Ok(start) => *self.day_start.get_mut().unwrap() = Some(start),
+                Err(e) => warn!("读取当日亏损基准 {} 失败: {}", path.display(), e),
+            }
+        }
+        self.state_file = Some(path);
+        self
+    }
+
+    /// 当前的当日亏损基准
+    pub fn day_start(&self) -> Option<DayStart> {
+        *self.day_start.lock().unwrap()
+    }
+
+    /// 设置每个股票所属的分组
+    pub fn with_groups(mut self, groups: HashMap<String, Vec<String>>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// 记录股票最近的收盘价（按时间顺序），保留最近 `correlation_window` 个收益率
+    pub fn update_closes(&self, symbol: &str, closes: &[f64]) {
+        let mut returns: Vec<f64> = closes
+            .windows(2)
+            .filter(|w| w[0] != 0.0)
+            .map(|w| w[1] / w[0] - 1.0)
+            .collect();
+        let window = self.limits.correlation_window;
+        if returns.len() > window {
+            returns.drain(..returns.len() - window);
+        }
+        self.returns.lock().unwrap().insert(symbol.to_string(), returns);
+    }
+
+    /// 从行情数据来源获取 `correlation_period` 周期的K线，更新这些股票的收益率
+    pub async fn refresh_closes<M: MarketDataSource>(&self, market: &M, symbols: &[String]) {
+        for symbol in symbols {
+            match market.get_candlesticks(symbol.clone(), self.limits.correlation_period.clone()).await {
+                Ok(candles) => {
+                    let closes: Vec<f64> = candles.iter().map(|c| c.close).collect();
+                    self.update_closes(symbol, &closes);
+                }
+                Err(e) => warn!("获取 {} K线计算相关系数失败: {}", symbol, e),
+            }
+        }
+    }
+
+    /// 每小时更新一次收益率，直到进程退出
+    pub async fn monitor_correlation<M: MarketDataSource>(&self, market: Arc<M>, symbols: Vec<String>) {
+        loop {
+            self.refresh_closes(market.as_ref(), &symbols).await;
+            info!("已更新 {} 个股票的收益率", symbols.len());
+            tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+        }
+    }
+
+    /// 每分钟检查一次日期，新交易日开始后立即以当时的净资产作为当日亏损基准，直到进程退出
+    pub async fn monitor_daily_loss(&self) {
+        loop {
+            if let Err(e) = self.daily_loss().await {
+                warn!("记录当日亏损基准失败: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }
+    }
+
+    /// 被包装的券商
+    pub fn inner(&self) -> &Arc<B> {
+        &self.inner
+    }
+
+    /// 全部被拒绝的下单
+    pub fn rejections(&self) -> Vec<RiskRejection> {
+        self.rejections.lock().unwrap().clone()
+    }
+
+    /// 检查订单是否符合全部限制，返回第一个不满足的原因
+    pub async fn check(&self, symbol: &str, side: OrderSide, price: Decimal, quantity: Decimal) -> Result<Option<RiskReason>, AqtError> {
+        let positions = self.inner.stock_positions().await?;
+        let held = positions.iter().find(|p| p.symbol == symbol).map_or(Decimal::ZERO, |p| p.quantity);
+        // 只减少持仓、不反手的订单直接放行
+        if is_reducing(held, side, quantity) {
+            return Ok(None);
+        }
+        let after = held + if side == OrderSide::Sell { -quantity } else { quantity };
+
+        let limit = |value: Option<f64>| value.map(|v| decimal!(v));
+        let notional = price * quantity;
+        if let Some(limit) = limit(self.limits.max_order_notional) {
+            if notional > limit {
+                return Ok(Some(RiskReason::OrderNotional { notional, limit }));
+            }
+        }
+        let exposure = after.abs() * price;
+        if let Some(limit) = limit(self.limits.max_symbol_notional) {
+            if exposure > limit {
+                return Ok(Some(RiskReason::SymbolNotional { exposure, limit }));
+            }
+        }
+        if let Some(limit) = limit(self.limits.max_gross_exposure) {
+            // 其他股票按成本价估算持仓金额
+            let others: Decimal = positions
+                .iter()
+                .filter(|p| p.symbol != symbol)
+                .map(|p| p.quantity.abs() * p.cost_price)
+                .sum();
+            if others + exposure > limit {
+                return Ok(Some(RiskReason::GrossExposure { exposure: others + exposure, limit }));
+            }
+        }
+        if let Some(groups) = self.groups.get(symbol) {
+            for group in groups {
+                let Some(limit) = limit(self.limits.group_limits.get(group).copied()) else {
+                    continue;
+                };
+                let others: Decimal = positions
+                    .iter()
+                    .filter(|p| p.symbol != symbol)
+                    .filter(|p| self.groups.get(&p.symbol).is_some_and(|g| g.contains(group)))
+                    .map(|p| p.quantity.abs() * p.cost_price)
+                    .sum();
+                if others + exposure > limit {
+                    let group = group.clone();
+                    return Ok(Some(RiskReason::GroupExposure { group, exposure: others + exposure, limit }));
+                }
+            }
+        }
+        if let Some(limit) = self.limits.max_correlation {
+            if let Some(correlation) = self.portfolio_correlation(symbol, after, &positions) {
+                if correlation > limit {
+                    return Ok(Some(RiskReason::Correlation { correlation, limit }));
+                }
+            }
+        }
+        if let Some(limit) = self.limits.max_positions {
+            let count = positions.iter().filter(|p| !p.quantity.is_zero()).count();
+            if held.is_zero() && count >= limit {
+                return Ok(Some(RiskReason::MaxPositions { count, limit }));
+            }
+        }
+        // 已撤销、被拒绝的订单也计入，撤单后重新下单不能绕过次数上限
+        if let Some(limit) = self.limits.max_daily_orders {
+            let count = self
+                .inner
+                .get_today_orders(symbol)
+                .await?
+                .iter()
+                .filter(|o| o.symbol == symbol)
+                .count();
+            if count >= limit {
+                return Ok(Some(RiskReason::DailyOrders { count, limit }));
+            }
+        }
+        if let Some(limit) = limit(self.limits.max_daily_loss) {
+            let loss = self.daily_loss().await?;
+            if loss >= limit {
+                return Ok(Some(RiskReason::DailyLoss { loss, limit }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 成交后持仓与其他持仓收益率的相关系数按持仓金额（成本价估算）加权的平均值，空头方向取反；
+    /// 没有其他持仓或收益率不足时返回 `None`
+    fn portfolio_correlation(&self, symbol: &str, after: Decimal, positions: &[Position]) -> Option<f64> {
+        let returns = self.returns.lock().unwrap();
+        let window = self.limits.correlation_window;
+        let own = returns.get(symbol).filter(|r| r.len() >= window)?;
+        let sign = |q: Decimal| if q.is_sign_negative() { -1.0 } else { 1.0 };
+        let values: Vec<(f64, f64)> = positions
+            .iter()
+            .filter(|p| p.symbol != symbol && !p.quantity.is_zero())
+            .filter_map(|p| {
+                let other = returns.get(&p.symbol).filter(|r| r.len() >= window)?;
+                let weight = f64::try_from(p.quantity.abs() * p.cost_price).unwrap_or(0.0);
+                correlation(own, other).map(|c| (c * sign(after) * sign(p.quantity), weight))
+            })
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        let total: f64 = values.iter().map(|(_, w)| w).sum();
+        // 成本价缺失时按等权平均
+        if total <= 0.0 {
+            return Some(values.iter().map(|(c, _)| c).sum::<f64>() / values.len() as f64);
+        }
+        Some(values.iter().map(|(c, w)| c * w).sum::<f64>() / total)
+    }
+
+    /// 当日亏损金额，盈利时为负数
+    async fn daily_loss(&self) -> Result<Decimal, AqtError> {
+        let balances = self.inner.account_balance().await?;
+        let equity = balances
+            .iter()
+            .find(|b| b.currency == self.limits.currency)
+            .or(balances.first())
+            .map_or(Decimal::ZERO, |b| b.net_assets);
+        let today = self.inner.now().date();
+        let start = {
+            let mut day_start = self.day_start.lock().unwrap();
+            match *day_start {
+                Some(start) if start.date() == Some(today) => return Ok(start.equity - equity),
+                _ => {
+                    let start = DayStart { day: today.to_julian_day(), equity };
+                    *day_start = Some(start);
+                    start
+                }
+            }
+        };
+        info!("记录 {} 的当日亏损基准，净资产 {}", today, equity);
+        self.save_day_start(&start);
+        Ok(Decimal::ZERO)
+    }
+
+    /// 先写临时文件再重命名，失败只记录日志
+    fn save_day_start(&self, start: &DayStart) {
+        let Some(path) = &self.state_file else {
+            return;
+        };
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let tmp = path.with_extension("json.tmp");
+            fs::write(&tmp, serde_json::to_vec_pretty(start)?)?;
+            fs::rename(&tmp, path)
+        };
+        if let Err(e) = write() {
+            warn!("保存当日亏损基准 {} 失败: {}", path.display(), e);
+        }
+    }
+
+    fn reject(&self, symbol: String, side: OrderSide, price: Decimal, quantity: Decimal, reason: RiskReason) -> AqtError {
+        warn!(
+            target: AUDIT_TARGET,
+            "风控拒绝 {} {:?} 数量 {} 价格 {}: {}", symbol, side, quantity, price, reason
+        );
+        self.rejections.lock().unwrap().push(RiskRejection {
+            symbol,
+            side,
+            price,
+            quantity,
+            reason: reason.clone(),
+            ts: self.inner.now(),
+        });
+        AqtError::RiskRejected(reason)
+    }
+}
+
+impl<B: Broker> Broker for RiskCheckedBroker<B> {
+    async fn submit_order(
+        &self,
+        symbol: String,
+        side: OrderSide,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<SubmitOrderResponse, AqtError> {
+        if let Some(reason) = self.check(&symbol, side, price, quantity).await? {
+            return Err(self.reject(symbol, side, price, quantity, reason));
+        }
+        self.inner.submit_order(symbol, side, price, quantity).await
+    }
+
+    async fn submit_intent(&self, intent: &OrderIntent) -> Result<SubmitOrderResponse, AqtError> {
+        if let Some(reason) = self.check(&intent.symbol, intent.side, intent.price, intent.quantity).await? {
+            return Err(self.reject(intent.symbol.clone(), intent.side, intent.price, intent.quantity, reason));
+        }
+        self.inner.submit_intent(intent).await
+    }
+
+    async fn cancel_order(&self, order_id: String) -> Result<(), AqtError> {
+        self.inner.cancel_order(order_id).await
+    }
+
+    async fn stock_positions(&self) -> Result<Vec<Position>, AqtError> {
+        self.inner.stock_positions().await
+    }
+
+    async fn account_balance(&self) -> Result<Vec<Balance>, AqtError> {
+        self.inner.account_balance().await
+    }
+
+    async fn get_today_orders(&self, symbol: &str) -> Result<Vec<Order>, AqtError> {
+        self.inner.get_today_orders(symbol).await
+    }
+
+    async fn estimate_max_quantity(&self, symbol: &str, side: OrderSide, price: Decimal) -> Result<Decimal, AqtError> {
+        self.inner.estimate_max_quantity(symbol, side, price).await
+    }
+
+    async fn get_history_orders(
+        &self,
+        symbol: &str,
+        start_at: Option<OffsetDateTime>,
+        end_at: Option<OffsetDateTime>,
+    ) -> Result<Vec<Order>, AqtError> {
+        self.inner.get_history_orders(symbol, start_at, end_at).await
+    }
+
+    fn now(&self) -> OffsetDateTime {
+        self.inner.now()
+    }
+}
@@ -1,15 +1,24 @@
 use std::sync::Arc;
-use log::error;
-use longport::{decimal, Decimal, Market, QuoteContext, TradeContext};
+use log::{error, info};
+use longport::{Decimal, Market, QuoteContext, TradeContext};
 use longport::quote::{AdjustType, Candlestick, MarketTemperature, Period, TradeSessions, WatchlistGroup};
-use longport::trade::{AccountBalance, FundPositionChannel, FundPositionsResponse, GetHistoryOrdersOptions, GetTodayOrdersOptions, Order, OrderSide, OrderStatus, OrderType, StockPositionChannel, StockPositionsResponse, SubmitOrderOptions, SubmitOrderResponse, TimeInForceType};
-use time::macros::datetime;
-use time::{Duration, OffsetDateTime};
+use longport::trade::{EstimateMaxPurchaseQuantityOptions, FundPositionChannel, GetHistoryOrdersOptions, GetTodayOrdersOptions, OrderSide, OrderType, SubmitOrderOptions, SubmitOrderResponse, TimeInForceType};
+use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+use crate::indicators::candle::Candle;
+use crate::models::balance::Balance;
+use crate::models::order::{Order, OrderIntent};
+use crate::models::position::Position;
+use crate::services::broker::{Broker, MarketDataSource};
+use crate::services::pricing::{OrderPrice, Pricer};
+use crate::utils::error::AqtError;
 
 /// `Service` 结构体用于封装 `QuoteContext` 和 `TradeContext`，提供统一的服务接口。
+///
+/// 它是 [`Broker`] 与 [`MarketDataSource`] 的长桥实现。
 pub struct Service {
     quote_ctx: Arc<QuoteContext>, // 引用计数的报价上下文
     trade_ctx: Arc<TradeContext>, // 引用计数的交易上下文
+    pricer: Pricer,               // 委托定价
 }
 
 impl Service {
@@ -21,10 +30,93 @@ impl Service {
     /// # 返回值
     /// 返回一个初始化完成的 `Service` 实例。
     pub fn new(quote_ctx: Arc<QuoteContext>, trade_ctx: Arc<TradeContext>) -> Self {
-        Service { quote_ctx, trade_ctx }
+        Service { quote_ctx, trade_ctx, pricer: Pricer::default() }
     }
 
-    /// 获取历史订单列表。
+    /// 使用指定的委托定价，默认按现价上下浮动 5% 限价委托
+    pub fn with_pricer(mut self, pricer: Pricer) -> Self {
+        self.pricer = pricer;
+        self
+    }
+
+    /// 下单参数：市价单当日有效，限价单有效至次日；委托价格不是正数时返回错误
+    fn order_options(&self, symbol: String, side: OrderSide, price: Decimal, quantity: Decimal) -> Result<SubmitOrderOptions, AqtError> {
+        let order_price = self.pricer.price(&symbol, side, price)?;
+        info!("{} 下单价格：{:?}", symbol, order_price);
+        Ok(match order_price {
+            OrderPrice::Market => SubmitOrderOptions::new(symbol, OrderType::MO, side, quantity, TimeInForceType::Day),
+            OrderPrice::Limit(submitted_price) => {
+                // 修改点：将 `expire_time` 设置为 24 小时后（即直接下一天）
+                let expire_time = OffsetDateTime::now_utc().saturating_add(Duration::days(1));
+                SubmitOrderOptions::new(symbol, OrderType::LO, side, quantity, TimeInForceType::GoodTilDate)
+                    .submitted_price(submitted_price)
+                    .expire_date(expire_time.date())
+            }
+        })
+    }
+
+    /// 获取基金持仓。
+    ///
+    /// # 返回值
+    /// 返回基金持仓列表。如果发生错误，则打印错误信息并返回 [`AqtError`]。
+    pub async fn fund_positions(
+        &self,
+    ) -> Result<Vec<FundPositionChannel>, AqtError> {
+        let resp = self.trade_ctx.fund_positions(None).await
+            .inspect_err(|e| error!("获取账户持仓出错: {}", e))?;
+        Ok(resp.channels)
+    }
+
+    pub async fn watchlist(&self) -> Result<Vec<WatchlistGroup>, AqtError> {
+        let resp = self.quote_ctx.watchlist().await
+            .inspect_err(|e| error!("获取自选列表出错: {}", e))?;
+        Ok(resp)
+    }
+}
+
+/// 单次请求K线的最大数量
+const MAX_CANDLE_COUNT: usize = 1000;
+
+/// 将配置中的K线级别转换为长桥的 `Period`
+pub fn to_period(period: &str) -> Period {
+    match period {
+        "1m" => Period::OneMinute,
+        "2m" => Period::TwoMinute,
+        "3m" => Period::ThreeMinute,
+        "5m" => Period::FiveMinute,
+        "10m" => Period::TenMinute,
+        "15m" => Period::FifteenMinute,
+        "20m" => Period::TwentyMinute,
+        "30m" => Period::ThirtyMinute,
+        "45m" => Period::FortyFiveMinute,
+        "1h" => Period::SixtyMinute,
+        "2h" => Period::TwoHour,
+        "3h" => Period::ThreeHour,
+        "4h" => Period::FourHour,
+        "1d" => Period::Day,
+        "1w" => Period::Week,
+        _ => Period::UnknownPeriod,
+    }
+}
+
+/// 将长桥的K线数据转换为指标计算使用的 `Candle`
+pub fn to_candles(symbol: &str, candles: &[Candlestick]) -> Vec<Candle> {
+    candles
+        .iter()
+        .map(|c| Candle {
+            symbol: Option::from(symbol.to_string()),
+            timestamp: c.timestamp.to_utc().unix_timestamp() as u64,
+            open: f64::try_from(c.open).unwrap(),
+            high: f64::try_from(c.high).unwrap(),
+            low: f64::try_from(c.low).unwrap(),
+            close: f64::try_from(c.close).unwrap(),
+            volume: c.volume as f64,
+        })
+        .collect::<Vec<_>>()
+}
+
+impl Broker for Service {
+    /// 获取历史订单列表，包含买卖两个方向、所有状态的订单。
     ///
     /// # 参数
     /// - `symbol`: 股票代码。
@@ -32,17 +124,15 @@ impl Service {
     /// - `end_at`: 查询结束时间（可选）。
     ///
     /// # 返回值
-    /// 返回一个包含历史订单的向量。如果发生错误，则打印错误信息并返回空向量。
-    pub async fn get_history_orders(
+    /// 返回一个包含历史订单的向量。如果发生错误，则打印错误信息并返回 [`AqtError`]。
+    async fn get_history_orders(
         &self,
         symbol: &str,
         start_at: Option<OffsetDateTime>,
         end_at: Option<OffsetDateTime>,
-    ) -> Vec<Order> {
+    ) -> Result<Vec<Order>, AqtError> {
         let mut opts = GetHistoryOrdersOptions::new()
             .symbol(symbol)
-            .status([OrderStatus::Filled, OrderStatus::New])
-            .side(OrderSide::Buy)
             .market(Market::US);
         if let Some(start) = start_at {
             opts = opts.start_at(start); // 设置查询开始时间
@@ -51,33 +141,29 @@ impl Service {
             opts = opts.end_at(end); // 设置查询结束时间
         }
 
-        // 调用 `history_orders` 方法获取历史订单，若发生错误则打印错误信息并返回空向量。
-        self.trade_ctx.history_orders(opts).await.unwrap_or_else(|e| {
-            error!("获取历史订单出错: {}", e); // 直接打印错误信息
-            Vec::new() // 返回空的订单列表
-        })
+        // 调用 `history_orders` 方法获取历史订单，若发生错误则打印错误信息并返回错误。
+        let resp = self.trade_ctx.history_orders(opts).await
+            .inspect_err(|e| error!("获取历史订单出错: {}", e))?;
+        Ok(resp.into_iter().map(Order::from).collect())
     }
 
-    /// 获取今日订单列表。
+    /// 获取今日订单列表，包含部分成交、已撤销和被拒绝等所有状态的订单。
     ///
     /// # 参数
     /// - `symbol`: 股票代码。
     ///
     /// # 返回值
-    /// 返回一个包含今日订单的向量。如果发生错误，则打印错误信息并返回空向量。
-    pub async fn get_today_orders(
+    /// 返回一个包含今日订单的向量。如果发生错误，则打印错误信息并返回 [`AqtError`]。
+    async fn get_today_orders(
         &self,
         symbol: &str,
-    ) -> Vec<Order> {
+    ) -> Result<Vec<Order>, AqtError> {
         let opts = GetTodayOrdersOptions::new()
             .symbol(symbol)
-            .status([OrderStatus::Filled, OrderStatus::New, OrderStatus::WaitToNew, OrderStatus::NotReported])
             .market(Market::US);
-        let resp = self.trade_ctx.today_orders(opts).await.unwrap_or_else(|e| {
-            error!("获取今日订单出错: {}", e); // 直接打印错误信息
-            Vec::new() // 返回空的订单列表
-        });
-        resp
+        let resp = self.trade_ctx.today_orders(opts).await
+            .inspect_err(|e| error!("获取今日订单出错: {}", e))?;
+        Ok(resp.into_iter().map(Order::from).collect())
     }
 
     /// 提交订单。
@@ -88,46 +174,54 @@ impl Service {
     /// - `quantity`: 订单数量。
     ///
     /// # 返回值
-    /// 返回一个包含订单ID的响应。如果发生错误，则打印错误信息并返回一个空的订单ID。
-    pub async fn submit_order(
+    /// 返回一个包含订单ID的响应。如果发生错误，则打印错误信息并返回 [`AqtError`]。
+    async fn submit_order(
         &self,
         symbol: String,
         side: OrderSide,
         price: Decimal,
         quantity: Decimal,
-    ) -> SubmitOrderResponse {
-        let mut submitted_price = price;
-        if side.clone() == OrderSide::Buy{
-            submitted_price = (price * decimal!(1.05)).round_dp(2);
-        }
-        if side.clone() == OrderSide::Sell{
-            submitted_price = (price * decimal!(0.95)).round_dp(2);
-        }
-        println!("下单价格：{:?}", submitted_price.clone());
-        // 修改点：将 `expire_time` 设置为 24 小时后（即直接下一天）
-        let expire_time = OffsetDateTime::now_utc().saturating_add(Duration::days(1));
-        let opts = SubmitOrderOptions::new(symbol, OrderType::LO, side, quantity, TimeInForceType::GoodTilDate)
-            .submitted_price(submitted_price)
-            .expire_date(expire_time.date());
-        let resp = self.trade_ctx.submit_order(opts).await.unwrap_or_else(|e| {
-            error!("下单出错: {}", e); // 直接打印错误信息
-            SubmitOrderResponse { order_id: "".to_string() }
-        });
-        resp
+    ) -> Result<SubmitOrderResponse, AqtError> {
+        let opts = self.order_options(symbol, side, price, quantity)?;
+        let resp = self.trade_ctx.submit_order(opts).await
+            .inspect_err(|e| error!("下单出错: {}", e))?;
+        Ok(resp)
+    }
+
+    async fn submit_intent(&self, intent: &OrderIntent) -> Result<SubmitOrderResponse, AqtError> {
+        let opts = self.order_options(intent.symbol.clone(), intent.side, intent.price, intent.quantity)?
+            .remark(intent.key.clone());
+        let resp = self.trade_ctx.submit_order(opts).await
+            .inspect_err(|e| error!("下单出错 {}: {}", intent.key, e))?;
+        Ok(resp)
+    }
+
+    /// 估算最大可下单数量。
+    ///
+    /// 使用与下单相同的限价估算，返回包含融资、融券额度的数量。
+    async fn estimate_max_quantity(
+        &self,
+        symbol: &str,
+        side: OrderSide,
+        price: Decimal,
+    ) -> Result<Decimal, AqtError> {
+        let opts = EstimateMaxPurchaseQuantityOptions::new(symbol, OrderType::LO, side)
+            .price(self.pricer.price(symbol, side, price)?.limit_or(price));
+        let resp = self.trade_ctx.estimate_max_purchase_quantity(opts).await
+            .inspect_err(|e| error!("估算最大可下单数量出错: {}", e))?;
+        Ok(resp.margin_max_qty)
     }
 
     /// 获取账户余额。
     ///
     /// # 返回值
-    /// 返回一个包含账户余额的向量。如果发生错误，则打印错误信息并返回空向量。
-    pub async fn account_balance(
+    /// 返回一个包含账户余额的向量。如果发生错误，则打印错误信息并返回 [`AqtError`]。
+    async fn account_balance(
         &self,
-    ) -> Vec<AccountBalance> {
-        let resp = self.trade_ctx.account_balance(None).await.unwrap_or_else(|e| {
-            error!("获取账户余额出错: {}", e); // 直接打印错误信息
-            Vec::new() // 返回空的列表
-        });
-        resp
+    ) -> Result<Vec<Balance>, AqtError> {
+        let resp = self.trade_ctx.account_balance(None).await
+            .inspect_err(|e| error!("获取账户余额出错: {}", e))?;
+        Ok(resp.into_iter().map(Balance::from).collect())
     }
 
     /// 取消订单。
@@ -136,110 +230,103 @@ impl Service {
     /// - `order_id`: 订单ID。
     ///
     /// # 返回值
-    /// 如果发生错误，则打印错误信息。
-    pub async fn cancel_order(
+    /// 如果发生错误，则打印错误信息并返回 [`AqtError`]。
+    async fn cancel_order(
         &self,
         order_id: String,
-    ) {
-        let resp = self.trade_ctx.cancel_order(order_id).await.unwrap_or_else(|e| {
-            error!("取消订单出错: {}", e); // 直接打印错误信息
-        });
-        resp
+    ) -> Result<(), AqtError> {
+        self.trade_ctx.cancel_order(order_id).await
+            .inspect_err(|e| error!("取消订单出错: {}", e))?;
+        Ok(())
     }
 
     /// 获取账户持仓。
     ///
     /// # 返回值
-    /// 返回一个包含账户持仓的响应。如果发生错误，则打印错误信息并返回一个空的持仓列表。
-    pub async fn fund_positions(
+    /// 返回账户持仓列表。如果发生错误，则打印错误信息并返回 [`AqtError`]。
+    async fn stock_positions(
         &self,
-    ) -> Vec<FundPositionChannel> {
-        let resp = self.trade_ctx.fund_positions(None).await.unwrap_or_else(|e| {
-            error!("获取账户持仓出错: {}", e); // 直接打印错误信息
-            FundPositionsResponse { channels: Vec::new() }
-        });
-        if resp.channels.is_empty() {
-            return Vec::new();
-        }
-        resp.channels
-    }
-
-    /// 获取账户持仓。
-    ///
-    /// # 返回值
-    /// 返回一个包含账户持仓的响应。如果发生错误，则打印错误信息并返回一个空的持仓列表。
-    pub async fn stock_positions(
-        &self,
-    ) -> Vec<StockPositionChannel> {
-        let resp = self.trade_ctx.stock_positions(None).await.unwrap_or_else(|e| {
-            error!("获取账户持仓出错: {}", e); // 直接打印错误信息
-            StockPositionsResponse { channels: Vec::new() }
-        });
-        if resp.channels.is_empty() {
-            return Vec::new();
-        }
-        resp.channels
+    ) -> Result<Vec<Position>, AqtError> {
+        let resp = self.trade_ctx.stock_positions(None).await
+            .inspect_err(|e| error!("获取账户持仓出错: {}", e))?;
+        Ok(resp.channels
+            .into_iter()
+            .flat_map(|c| c.positions)
+            .map(Position::from)
+            .collect())
     }
+}
 
+impl MarketDataSource for Service {
     /// 获取行情数据
     ///
-    /// 返回值：Vec<Candlestick>
+    /// 返回值：Result<Vec<Candle>, AqtError>
     /// 返回股票的K线数据集合
-    pub async fn get_candlesticks(
+    async fn get_candlesticks(
         &self,
         symbol: String,
         period: String,
-    ) -> Vec<Candlestick> {
+    ) -> Result<Vec<Candle>, AqtError> {
         let adjust_type = AdjustType::NoAdjust;
         let trade_sessions = TradeSessions::All;
         let count = 365;
-        let pd;
-        match period.as_str() {
-            "1m" => pd = Period::OneMinute,
-            "2m" => pd = Period::TwoMinute,
-            "3m" => pd = Period::ThreeMinute,
-            "5m" => pd = Period::FiveMinute,
-            "10m" => pd = Period::TenMinute,
-            "15m" => pd = Period::FifteenMinute,
-            "20m" => pd = Period::TwentyMinute,
-            "30m" => pd = Period::ThirtyMinute,
-            "45m" => pd = Period::FortyFiveMinute,
-            "1h" => pd = Period::SixtyMinute,
-            "2h" => pd = Period::TwoHour,
-            "3h" => pd = Period::ThreeHour,
-            "4h" => pd = Period::FourHour,
-            "1d" => pd = Period::Day,
-            "1w" => pd = Period::Week,
-            _ => pd = Period::UnknownPeriod
-        }
-        let resp = self.quote_ctx.candlesticks(symbol, pd, count, adjust_type, trade_sessions).await.unwrap_or_else(|e| {
-            error!("获取行情数据出错: {}", e); // 直接打印错误信息
-            Vec::new() // 返回空的订单列表
-        });
-        resp
+        let pd = to_period(&period);
+        let resp = self.quote_ctx.candlesticks(symbol.clone(), pd, count, adjust_type, trade_sessions).await
+            .inspect_err(|e| error!("获取行情数据出错: {}", e))?;
+        Ok(to_candles(&symbol, &resp))
     }
 
-    pub async fn get_market_temperature(
+    /// 增量获取K线：从 `since` 开始向后分页拉取，直到没有更新的K线
+    async fn get_candlesticks_since(
         &self,
-    ) -> MarketTemperature {
-        let resp = self.quote_ctx.market_temperature(Market::US).await.unwrap_or_else(|e| {
-            error!("获取行情数据出错: {}", e); // 直接打印错误信息
-            MarketTemperature {
-                temperature: 0,
-                description: "".to_string(),
-                valuation: 0,
-                sentiment: 0,
-                timestamp: datetime!(2024-01-01 12:59:59.5 -5),
+        symbol: String,
+        period: String,
+        since: Option<u64>,
+    ) -> Result<Vec<Candle>, AqtError> {
+        let pd = to_period(&period);
+        let Some(since) = since else {
+            // 本地没有数据时拉取最近一批K线作为起点
+            let resp = self.quote_ctx
+                .candlesticks(symbol.clone(), pd, MAX_CANDLE_COUNT, AdjustType::NoAdjust, TradeSessions::All)
+                .await
+                .inspect_err(|e| error!("获取行情数据出错: {}", e))?;
+            return Ok(to_candles(&symbol, &resp));
+        };
+
+        // 接口按交易所当地时间解释查询时间，向前多取一天避免时区差异漏掉K线，重叠部分由存储去重
+        let mut cursor = since.saturating_sub(24 * 60 * 60);
+        let mut candles: Vec<Candle> = Vec::new();
+        while let Ok(start) = OffsetDateTime::from_unix_timestamp(cursor as i64) {
+            let resp = self.quote_ctx
+                .history_candlesticks_by_offset(
+                    symbol.clone(),
+                    pd,
+                    AdjustType::NoAdjust,
+                    true,
+                    Some(PrimitiveDateTime::new(start.date(), start.time())),
+                    MAX_CANDLE_COUNT,
+                    TradeSessions::All,
+                )
+                .await
+                .inspect_err(|e| error!("增量获取行情数据出错: {}", e))?;
+            let page = to_candles(&symbol, &resp);
+            let last = page.last().map(|c| c.timestamp).unwrap_or(cursor);
+            let full = page.len() >= MAX_CANDLE_COUNT;
+            candles.extend(page.into_iter().filter(|c| c.timestamp >= since));
+            // 不满一页或没有前进说明已经拉到最新
+            if !full || last <= cursor {
+                break;
             }
-        });
-        resp
-    }
- 
-    pub async fn watchlist(&self) -> Vec<WatchlistGroup> {
-        let resp = self.quote_ctx.watchlist().await.unwrap_or_else(|e| {
-            error!("获取自选列表出错: {}", e); // 直接打印错误信息
-            Vec::new() // 返回空的订单列表
-        });
-        resp
-    }
-}
\ No newline at end of file
+            cursor = last;
+        }
+        Ok(candles)
+    }
+
+    async fn get_market_temperature(
+        &self,
+    ) -> Result<MarketTemperature, AqtError> {
+        let resp = self.quote_ctx.market_temperature(Market::US).await
+            .inspect_err(|e| error!("获取市场温度出错: {}", e))?;
+        Ok(resp)
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use log::error;
+use longport::quote::MarketTemperature;
+use crate::indicators::candle::Candle;
+use crate::services::broker::MarketDataSource;
+use crate::store::candle_store::CandleStore;
+use crate::utils::error::AqtError;
+
+/// 每次返回给策略的K线数量，与 `Service::get_candlesticks` 保持一致
+const CANDLE_COUNT: usize = 365;
+
+/// 带本地K线存储的行情数据来源：请求K线时先增量同步到 [`CandleStore`]，再从本地返回。
+///
+/// 按起始时间请求时，本地缺少起始时间之后的更早K线会先补齐历史数据。
+/// 同步失败时仍返回本地已有的K线，本地也没有数据时返回同步的错误。
+pub struct CachedMarket<M: MarketDataSource> {
+    inner: Arc<M>,
+    store: CandleStore,
+    // 每个股票、周期已经补齐过的最早起始时间，行情源没有更早的数据时避免重复拉取
+    backfilled: Mutex<HashMap<(String, String), u64>>,
+}
+
+impl<M: MarketDataSource> CachedMarket<M> {
+    pub fn new(inner: Arc<M>, store: CandleStore) -> Self {
+        CachedMarket {
+            inner,
+            store,
+            backfilled: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn store(&self) -> &CandleStore {
+        &self.store
+    }
+}
+
+impl<M: MarketDataSource> CachedMarket<M> {
+    // 同步后读取本地K线，同步失败且本地没有数据时返回同步的错误
+    async fn sync_then<F>(&self, symbol: &str, period: &str, since: Option<u64>, read: F) -> Result<Vec<Candle>, AqtError>
+    where
+        F: FnOnce(&CandleStore) -> Result<Vec<Candle>, Box<dyn std::error::Error>>,
+    {
+        let mut synced = self.store.sync(self.inner.as_ref(), symbol, period).await;
+        if let (Ok(_), Some(since)) = (&synced, since) {
+            synced = self.backfill(symbol, period, since).await;
+        }
+        if let Err(e) = &synced {
+            error!("同步K线出错: {}", e);
+        }
+        let candles = read(&self.store).map_err(|e| {
+            error!("读取本地K线出错: {}", e);
+            AqtError::Data(e.to_string())
+        })?;
+        match synced {
+            Err(e) if candles.is_empty() => Err(e),
+            _ => Ok(candles),
+        }
+    }
+
+    // 补齐 `since` 之前缺失的K线，同一起始时间或更晚的请求只补一次
+    async fn backfill(&self, symbol: &str, period: &str, since: u64) -> Result<usize, AqtError> {
+        let key = (symbol.to_string(), period.to_string());
+        if self.backfilled.lock().unwrap().get(&key).is_some_and(|ts| *ts <= since) {
+            return Ok(0);
+        }
+        let added = self.store.backfill(self.inner.as_ref(), symbol, period, since).await?;
+        self.backfilled.lock().unwrap().insert(key, since);
+        Ok(added)
+    }
+}
+
+impl<M: MarketDataSource> MarketDataSource for CachedMarket<M> {
+    async fn get_candlesticks(&self, symbol: String, period: String) -> Result<Vec<Candle>, AqtError> {
+        self.sync_then(&symbol, &period, None, |store| store.latest(&symbol, &period, CANDLE_COUNT)).await
+    }
+
+    async fn get_candlesticks_since(&self, symbol: String, period: String, since: Option<u64>) -> Result<Vec<Candle>, AqtError> {
+        self.sync_then(&symbol, &period, since, |store| store.range(&symbol, &period, since, None)).await
+    }
+
+    async fn get_market_temperature(&self) -> Result<MarketTemperature, AqtError> {
+        self.inner.get_market_temperature().await
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use log::{info, warn};
+use crate::backtest::loader::{candle_file, format_candle, load_candles};
+use crate::indicators::candle::Candle;
+use crate::services::broker::MarketDataSource;
+use crate::utils::error::AqtError;
+
+/// 本地K线存储：每个股票、周期一个 CSV 文件 `{dir}/{symbol}_{period}.csv`，
+/// 与回测的数据目录格式相同，可以直接用作回测数据。
+///
+/// 文件按时间升序、时间戳唯一保存；读过的文件缓存在内存中，避免每次行情都读盘。
+pub struct CandleStore {
+    dir: PathBuf,
+    cache: Mutex<HashMap<(String, String), Vec<Candle>>>,
+}
+
+impl CandleStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        CandleStore {
+            dir: dir.into(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 读取全部K线，文件不存在时返回空
+    pub fn load(&self, symbol: &str, period: &str) -> Result<Vec<Candle>, Box<dyn Error>> {
+        let key = (symbol.to_string(), period.to_string());
+        if let Some(candles) = self.cache.lock().unwrap().get(&key) {
+            return Ok(candles.clone());
+        }
+        let path = self.path(symbol, period);
+        let candles = if path.exists() {
+            let loaded = load_candles(&path, symbol)?;
+            let total = loaded.len();
+            let candles = dedupe(loaded);
+            if candles.len() != total {
+                warn!("{} 存在 {} 根重复K线，已修复", path.display(), total - candles.len());
+                self.write(symbol, period, &candles)?;
+            }
+            candles
+        } else {
+            Vec::new()
+        };
+        self.cache.lock().unwrap().insert(key, candles.clone());
+        Ok(candles)
+    }
+
+    /// 最后一根K线的时间戳
+    pub fn last_timestamp(&self, symbol: &str, period: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        Ok(self.load(symbol, period)?.last().map(|c| c.timestamp))
+    }
+
+    /// 最近的 `count` 根K线
+    pub fn latest(&self, symbol: &str, period: &str, count: usize) -> Result<Vec<Candle>, Box<dyn Error>> {
+        let candles = self.load(symbol, period)?;
+        Ok(candles[candles.len().saturating_sub(count)..].to_vec())
+    }
+
+    /// 按时间范围查询K线，`start`、`end` 为秒级时间戳，均为闭区间，`None` 表示不限制
+    pub fn range(
+        &self,
+        symbol: &str,
+        period: &str,
+        start: Option<u64>,
+        end: Option<u64>,
+    ) -> Result<Vec<Candle>, Box<dyn Error>> {
+        let candles = self.load(symbol, period)?;
+        let from = start.map_or(0, |ts| candles.partition_point(|c| c.timestamp < ts));
+        let to = end.map_or(candles.len(), |ts| candles.partition_point(|c| c.timestamp <= ts));
+        Ok(candles.get(from..to).map(|c| c.to_vec()).unwrap_or_default())
+    }
+
+    /// 合并新的K线：相同时间戳的K线以新数据为准（未收盘的K线会被更新），返回新增的数量
+    ///
+    /// 数据没有变化时不写文件；只有末尾的K线变化时（通常是重新拉取的最后一根K线）只替换文件末尾。
+    pub fn merge(&self, symbol: &str, period: &str, candles: Vec<Candle>) -> Result<usize, Box<dyn Error>> {
+        if candles.is_empty() {
+            return Ok(0);
+        }
+        let existing = self.load(symbol, period)?;
+        let before = existing.len();
+        let mut by_ts: BTreeMap<u64, Candle> = existing.iter().map(|c| (c.timestamp, c.clone())).collect();
+        for candle in candles {
+            by_ts.insert(candle.timestamp, candle);
+        }
+        let merged: Vec<Candle> = by_ts.into_values().collect();
+        // 第一根与已存数据不同的K线
+        let from = existing
+            .iter()
+            .zip(&merged)
+            .position(|(a, b)| format_candle(a) != format_candle(b))
+            .unwrap_or(before.min(merged.len()));
+        if from == merged.len() && from == before {
+            return Ok(0);
+        }
+        let added = merged.len() - before;
+        if from == 0 || self.replace_tail(symbol, period, before - from, &merged[from..]).is_err() {
+            self.write(symbol, period, &merged)?;
+        }
+        self.cache
+            .lock()
+            .unwrap()
+            .insert((symbol.to_string(), period.to_string()), merged);
+        Ok(added)
+    }
+
+    /// 增量同步：只拉取最后一根已存K线及之后的数据，返回新增的数量
+    ///
+    /// 行情请求失败时返回原始错误，读写本地文件失败时返回 [`AqtError::Data`]。
+    pub async fn sync<M: MarketDataSource>(
+        &self,
+        market: &M,
+        symbol: &str,
+        period: &str,
+    ) -> Result<usize, AqtError> {
+        let since = self
+            .last_timestamp(symbol, period)
+            .map_err(|e| AqtError::Data(e.to_string()))?;
+        let candles = market
+            .get_candlesticks_since(symbol.to_string(), period.to_string(), since)
+            .await?;
+        let added = self
+            .merge(symbol, period, candles)
+            .map_err(|e| AqtError::Data(e.to_string()))?;
+        if added > 0 {
+            info!("同步 {} {} K线，新增 {} 根", symbol, period, added);
+        }
+        Ok(added)
+    }
+
+    /// 补齐 `since` 之前缺失的历史K线：本地第一根K线晚于 `since`（或本地没有数据）时，
+    /// 从 `since` 开始拉取并合并，返回新增的数量
+    pub async fn backfill<M: MarketDataSource>(
+        &self,
+        market: &M,
+        symbol: &str,
+        period: &str,
+        since: u64,
+    ) -> Result<usize, AqtError> {
+        let first = self
+            .load(symbol, period)
+            .map_err(|e| AqtError::Data(e.to_string()))?
+            .first()
+            .map(|c| c.timestamp);
+        if first.is_some_and(|ts| ts <= since) {
+            return Ok(0);
+        }
+        let candles = market
+            .get_candlesticks_since(symbol.to_string(), period.to_string(), Some(since))
+            .await?;
+        let added = self
+            .merge(symbol, period, candles)
+            .map_err(|e| AqtError::Data(e.to_string()))?;
+        if added > 0 {
+            info!("补齐 {} {} 历史K线，新增 {} 根", symbol, period, added);
+        }
+        Ok(added)
+    }
+
+    fn path(&self, symbol: &str, period: &str) -> PathBuf {
+        candle_file(&self.dir.to_string_lossy(), symbol, period)
+    }
+
+    /// 先写临时文件再重命名，避免进程中断留下半个文件
+    fn write(&self, symbol: &str, period: &str, candles: &[Candle]) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(symbol, period);
+        let tmp = path.with_extension("csv.tmp");
+        let mut out = String::from("timestamp,open,high,low,close,volume\n");
+        for candle in candles {
+            out.push_str(&format_candle(candle));
+            out.push('\n');
+        }
+        fs::write(&tmp, out)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// 删除文件末尾的 `count` 行后追加 `candles`
+    fn replace_tail(&self, symbol: &str, period: &str, count: usize, candles: &[Candle]) -> std::io::Result<()> {
+        let path = self.path(symbol, period);
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let len = file.metadata()?.len();
+        let mut window = 8192u64;
+        let cut = loop {
+            let start = len.saturating_sub(window);
+            file.seek(SeekFrom::Start(start))?;
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            if !buf.ends_with(b"\n") {
+                return Err(std::io::Error::other("K线文件末尾没有换行符"));
+            }
+            // 倒数第 `count + 1` 个换行符之后的内容为要替换的行
+            let found = buf.iter().enumerate().rev().filter(|(_, b)| **b == b'\n').nth(count);
+            match found {
+                Some((i, _)) => break start + i as u64 + 1,
+                None if start == 0 => return Err(std::io::Error::other("K线文件行数与缓存不一致")),
+                None => window *= 4,
+            }
+        };
+        file.set_len(cut)?;
+        file.seek(SeekFrom::End(0))?;
+        let mut out = String::new();
+        for candle in candles {
+            out.push_str(&format_candle(candle));
+            out.push('\n');
+        }
+        file.write_all(out.as_bytes())
+    }
+}
+
+/// 按时间戳去重（保留文件中靠后的一根），结果按时间升序
+fn dedupe(candles: Vec<Candle>) -> Vec<Candle> {
+    let by_ts: BTreeMap<u64, Candle> = candles.into_iter().map(|c| (c.timestamp, c)).collect();
+    by_ts.into_values().collect()
+}
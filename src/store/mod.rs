@@ -0,0 +1,2 @@
+pub mod candle_store;
+pub mod cached_market;
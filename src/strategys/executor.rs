@@ -1,37 +1,75 @@
 use std::error::Error;
 use std::sync::Arc;
 use log::error;
-use longport::{QuoteContext, TradeContext};
 use tokio::sync::mpsc;
+use crate::models::bar::BarEvent;
 use crate::models::market::MarketData;
-use crate::strategys::strategy::Strategy;
+use crate::strategys::strategy::{DynStrategy, Strategy};
 
-pub struct Executor<T: Strategy> {
+/// 驱动单个策略，策略可以是具体类型，也可以是装箱的 `Box<dyn DynStrategy>`
+pub struct Executor<T: DynStrategy> {
     executor: T,
     quote_receiver: mpsc::Receiver<MarketData>,
+    bar_receiver: Option<mpsc::Receiver<BarEvent>>,
 }
 
 impl<T: Strategy + Send> Executor<T> {
     pub fn new(
-        quote_ctx: Arc<QuoteContext>,
-        trade_ctx: Arc<TradeContext>,
+        broker: Arc<T::Broker>,
+        market: Arc<T::Market>,
         quote_receiver: mpsc::Receiver<MarketData>,
     ) -> Self {
+        Self::with_strategy(T::new(broker, market), quote_receiver)
+    }
+}
+
+impl<T: DynStrategy> Executor<T> {
+    /// 使用已创建的策略实例，例如按配置创建的策略
+    pub fn with_strategy(strategy: T, quote_receiver: mpsc::Receiver<MarketData>) -> Self {
         Executor {
-            executor: T::new(quote_ctx, trade_ctx),
+            executor: strategy,
             quote_receiver,
+            bar_receiver: None,
         }
     }
 
+    /// 同时接收K线推送事件
+    pub fn with_bars(mut self, bar_receiver: mpsc::Receiver<BarEvent>) -> Self {
+        self.bar_receiver = Some(bar_receiver);
+        self
+    }
+
     // 运行执行器，接收市场数据并传递给内部策略
     pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
         // 首先初始化内部策略
         self.executor.run().await?;
 
-        // 然后处理接收到的市场数据
-        while let Some(event) = self.quote_receiver.recv().await {
-            if let Err(e) = self.executor.execute(&event).await {
-                error!("Error executing strategy: {:?}", e);
+        // 然后处理接收到的市场数据和K线事件，行情通道关闭时结束
+        loop {
+            let bar_receiver = &mut self.bar_receiver;
+            let bars = async {
+                match bar_receiver.as_mut() {
+                    Some(rx) => rx.recv().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                event = self.quote_receiver.recv() => {
+                    let Some(event) = event else { break };
+                    if let Err(e) = self.executor.execute(&event).await {
+                        error!("Error executing strategy: {:?}", e);
+                    }
+                }
+                bar = bars => {
+                    let Some(bar) = bar else {
+                        // K线通道关闭后只继续处理行情
+                        self.bar_receiver = None;
+                        continue;
+                    };
+                    if let Err(e) = self.executor.on_bar(&bar).await {
+                        error!("Error handling bar event: {:?}", e);
+                    }
+                }
             }
         }
         // 最后停止内部策略
@@ -39,4 +77,4 @@ impl<T: Strategy + Send> Executor<T> {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
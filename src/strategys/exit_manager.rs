@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::fmt;
+use log::info;
+use longport::trade::OrderSide;
+use longport::{decimal, Decimal};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use crate::config::config::SymbolConfig;
+use crate::indicators::candle::Candle;
+use crate::indicators::utbot::UTBot;
+use crate::models::order::Order;
+use crate::models::position::Position;
+
+/// 平仓原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// 固定止损，按 `sl_ratio` 计算
+    StopLoss,
+    /// UTBot（ATR）跟踪止损
+    TrailingStop,
+    /// 盈利达到保本阈值后回落到成本价
+    Breakeven,
+    /// 超过最长持仓时间
+    TimeStop,
+    /// 止盈
+    TakeProfit,
+}
+
+impl ExitReason {
+    /// 英文标识，用于下单意图的幂等键
+    pub fn code(&self) -> &'static str {
+        match self {
+            ExitReason::StopLoss => "stop_loss",
+            ExitReason::TrailingStop => "trailing_stop",
+            ExitReason::Breakeven => "breakeven",
+            ExitReason::TimeStop => "time_stop",
+            ExitReason::TakeProfit => "take_profit",
+        }
+    }
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExitReason::StopLoss => "固定止损",
+            ExitReason::TrailingStop => "跟踪止损",
+            ExitReason::Breakeven => "保本止损",
+            ExitReason::TimeStop => "持仓超时",
+            ExitReason::TakeProfit => "止盈",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 平仓记录
+#[derive(Debug, Clone)]
+pub struct ExitRecord {
+    pub symbol: String,
+    pub reason: ExitReason,
+    /// 触发平仓时的价格
+    pub price: Decimal,
+    /// 平仓前的持仓数量，空头为负数
+    pub quantity: Decimal,
+    pub ts: OffsetDateTime,
+}
+
+/// 需要提交的平仓订单
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExitSignal {
+    pub side: OrderSide,
+    pub quantity: Decimal,
+    pub reason: ExitReason,
+    /// 持仓成本价
+    pub cost: Decimal,
+    /// 平仓前的持仓数量，空头为负数
+    pub held: Decimal,
+}
+
+/// 单个股票的持仓跟踪状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExitState {
+    quantity: Decimal,           // 持仓数量，空头为负数
+    available: Decimal,          // 可平仓数量，已扣除未完成的平仓订单
+    cost: Decimal,               // 成本价
+    #[serde(with = "timestamp")]
+    opened_at: OffsetDateTime,   // 开仓（首次看到该方向持仓）时间
+    trailing: Option<Decimal>,   // 跟踪止损价，只朝有利方向移动
+    breakeven: bool,             // 是否已经移到保本止损
+    pending: bool,               // 已发出平仓订单，等待持仓数量变化
+}
+
+/// 平仓管理：在每笔行情上检查固定止损、跟踪止损、保本止损和最长持仓时间。
+///
+/// 持仓和跟踪止损在K线收盘时同步，行情只用于判断是否触发；
+/// 触发后直到持仓数量变化（或平仓订单未成交就结束）才会再次发出平仓订单。
+///
+/// 开仓时间、跟踪止损和保本状态随策略状态保存，重启后继续生效；平仓记录只保存在内存中。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExitManager {
+    states: HashMap<String, ExitState>,
+    #[serde(skip)]
+    records: Vec<ExitRecord>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl ExitManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 同步持仓：没有持仓时清除状态，新开仓或反手时重新计时
+    ///
+    /// `orders` 为该股票的当日订单，未完成的平仓方向订单从可平仓数量中扣除。
+    /// 已发出的平仓订单在持仓数量变化，或者没有未完成的平仓订单（撤单、被拒绝）时才结束等待。
+    pub fn update_position(&mut self, symbol: &str, position: &Position, orders: &[Order], now: OffsetDateTime) {
+        if position.quantity.is_zero() {
+            self.states.remove(symbol);
+            return;
+        }
+        let long = position.quantity.is_sign_positive();
+        let closing = if long { OrderSide::Sell } else { OrderSide::Buy };
+        let working: Decimal = orders
+            .iter()
+            .filter(|o| o.symbol == symbol && o.side == closing && o.is_open())
+            .map(|o| o.quantity - o.executed_quantity)
+            .sum();
+        let remaining = position.quantity.abs() - working;
+        // 多头的可用数量已由券商扣除冻结的卖单，取两者中较小的一个
+        let available = if long { position.available_quantity.min(remaining) } else { remaining }.max(Decimal::ZERO);
+        match self.states.get_mut(symbol) {
+            Some(state) if state.quantity.is_sign_positive() == long => {
+                if state.quantity != position.quantity || working.is_zero() {
+                    state.pending = false;
+                }
+                state.quantity = position.quantity;
+                state.available = available;
+                state.cost = position.cost_price;
+            }
+            _ => {
+                self.states.insert(symbol.to_string(), ExitState {
+                    quantity: position.quantity,
+                    available,
+                    cost: position.cost_price,
+                    opened_at: now,
+                    trailing: None,
+                    breakeven: false,
+                    pending: false,
+                });
+            }
+        }
+    }
+
+    /// 根据收盘K线更新跟踪止损，多头止损只上移，空头止损只下移
+    pub fn update_levels(&mut self, sym: &SymbolConfig, candles: &[Candle]) {
+        let Some(params) = &sym.exit.trailing else {
+            return;
+        };
+        let Some(state) = self.states.get_mut(&sym.symbol) else {
+            return;
+        };
+        let mut utbot = UTBot::new(params.key, params.atr, params.heikin);
+        utbot.calculate(candles);
+        let Some(stop) = utbot.latest_stop().filter(|s| s.is_finite()).map(|s| decimal!(s)) else {
+            return;
+        };
+        state.trailing = Some(match state.trailing {
+            Some(prev) if state.quantity.is_sign_positive() => prev.max(stop),
+            Some(prev) => prev.min(stop),
+            None => stop,
+        });
+    }
+
+    /// 检查最新价格是否触发平仓，触发时返回平仓订单；订单提交成功后由调用方 [`record`](Self::record)
+    pub fn check(&mut self, sym: &SymbolConfig, price: Decimal, now: OffsetDateTime) -> Option<ExitSignal> {
+        let state = self.states.get_mut(&sym.symbol)?;
+        if state.pending || state.available.is_zero() || state.cost.is_zero() {
+            return None;
+        }
+        let long = state.quantity.is_sign_positive();
+        // 价格是否不利于持仓方向地越过 `level`
+        let crossed = |level: Decimal| if long { price <= level } else { price >= level };
+        let gain = if long { price / state.cost - decimal!(1) } else { decimal!(1) - price / state.cost } * decimal!(100);
+
+        if let Some(ratio) = sym.exit.breakeven_ratio {
+            if !state.breakeven && gain >= decimal!(ratio) {
+                state.breakeven = true;
+                self.changed = true;
+            }
+        }
+        let sl = decimal!(sym.sl_ratio) * decimal!(0.01);
+        let stop = if long { state.cost * (decimal!(1) - sl) } else { state.cost * (decimal!(1) + sl) };
+
+        let reason = if sym.exit.max_holding_secs.is_some_and(|secs| (now - state.opened_at).whole_seconds() >= secs as i64) {
+            Some(ExitReason::TimeStop)
+        } else if sym.sl_ratio > 0 && crossed(stop) {
+            Some(ExitReason::StopLoss)
+        } else if state.breakeven && crossed(state.cost) {
+            Some(ExitReason::Breakeven)
+        } else if state.trailing.is_some_and(crossed) {
+            Some(ExitReason::TrailingStop)
+        } else {
+            None
+        }?;
+
+        state.pending = true;
+        let signal = ExitSignal {
+            side: if long { OrderSide::Sell } else { OrderSide::Buy },
+            quantity: state.available,
+            reason,
+            cost: state.cost,
+            held: state.quantity,
+        };
+        Some(signal)
+    }
+
+    /// 平仓订单提交失败时允许下一笔行情重新触发
+    pub fn reset_pending(&mut self, symbol: &str) {
+        if let Some(state) = self.states.get_mut(symbol) {
+            state.pending = false;
+        }
+    }
+
+    /// 记录一次平仓
+    pub fn record(&mut self, symbol: &str, reason: ExitReason, price: Decimal, quantity: Decimal, ts: OffsetDateTime) {
+        info!("{} 触发{}，价格 {}，持仓 {}", symbol, reason, price, quantity);
+        self.records.push(ExitRecord { symbol: symbol.to_string(), reason, price, quantity, ts });
+    }
+
+    /// 行情检查后是否有需要保存的变化（移到保本止损），读取后清除
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// 全部平仓记录
+    pub fn records(&self) -> &[ExitRecord] {
+        &self.records
+    }
+}
+
+/// 开仓时间以秒级时间戳保存
+mod timestamp {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(ts: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(ts.unix_timestamp())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OffsetDateTime, D::Error> {
+        OffsetDateTime::from_unix_timestamp(i64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
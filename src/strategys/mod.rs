@@ -1,4 +1,9 @@
 pub mod strategy;
 pub mod vecor_v1;
 pub mod executor;
+pub mod exit_manager;
+pub mod multi_executor;
+pub mod registry;
+pub mod sizer;
+pub mod state;
 mod gconsts;
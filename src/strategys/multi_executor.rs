@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+use log::{error, info, warn};
+use tokio::sync::{mpsc, oneshot};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinHandle;
+use crate::models::bar::BarEvent;
+use crate::models::market::MarketData;
+use crate::strategys::executor::Executor;
+use crate::strategys::strategy::DynStrategy;
+
+/// 只保留发送失败的原因，丢弃未发出的数据
+fn discard<T>(e: TrySendError<T>) -> TrySendError<()> {
+    match e {
+        TrySendError::Full(_) => TrySendError::Full(()),
+        TrySendError::Closed(_) => TrySendError::Closed(()),
+    }
+}
+
+/// 单个策略的路由：策略负责的股票及发往策略执行器的通道
+struct StrategyRoute {
+    name: String,
+    symbols: HashSet<String>,
+    quote_sender: mpsc::Sender<MarketData>,
+    bar_sender: mpsc::Sender<BarEvent>,
+    // 收盘K线先进入不限容量的队列，由该策略自己的转发任务等待执行器处理，不阻塞路由
+    closed_sender: mpsc::UnboundedSender<BarEvent>,
+    handle: JoinHandle<()>,
+}
+
+/// 发给运行中的执行器的控制命令
+enum Command {
+    Start { name: String, symbols: Vec<String>, strategy: Box<dyn DynStrategy> },
+    Stop(String),
+    Names(oneshot::Sender<Vec<String>>),
+}
+
+/// 执行器的控制句柄：执行器移入任务中运行 [`MultiExecutor::run`] 后，通过句柄单独启动和停止策略
+#[derive(Clone)]
+pub struct ExecutorHandle {
+    sender: mpsc::UnboundedSender<Command>,
+}
+
+impl ExecutorHandle {
+    /// 启动策略，同名策略已在运行时先停止旧的；执行器已退出时返回 `false`
+    pub fn start<T>(&self, name: &str, symbols: Vec<String>, strategy: T) -> bool
+    where
+        T: DynStrategy + 'static,
+    {
+        let command = Command::Start { name: name.to_string(), symbols, strategy: Box::new(strategy) };
+        self.sender.send(command).is_ok()
+    }
+
+    /// 停止策略，执行器已退出时返回 `false`
+    pub fn stop(&self, name: &str) -> bool {
+        self.sender.send(Command::Stop(name.to_string())).is_ok()
+    }
+
+    /// 正在运行的策略名称，执行器已退出时返回空
+    pub async fn names(&self) -> Vec<String> {
+        let (sender, receiver) = oneshot::channel();
+        if self.sender.send(Command::Names(sender)).is_err() {
+            return Vec::new();
+        }
+        receiver.await.unwrap_or_default()
+    }
+
+    /// 执行器是否已经退出
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+/// 在一个进程中运行多个策略，共用同一份行情订阅。
+///
+/// 每个策略在独立的任务中由自己的 [`Executor`] 驱动，行情和K线事件按股票路由给
+/// 负责该股票的策略。某个策略出错或退出不会影响其他策略；策略可以单独启动和停止。
+pub struct MultiExecutor {
+    routes: Vec<StrategyRoute>,
+    capacity: usize,
+    commands: mpsc::UnboundedReceiver<Command>,
+    control: mpsc::UnboundedSender<Command>,
+}
+
+impl Default for MultiExecutor {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl MultiExecutor {
+    /// `capacity` 为每个策略的行情、K线通道容量，策略处理不过来时丢弃新行情和未收盘的K线更新，
+    /// 避免阻塞其他策略；收盘K线不能丢失，由每个策略各自的转发任务排队等待策略处理
+    pub fn new(capacity: usize) -> Self {
+        let (control, commands) = mpsc::unbounded_channel();
+        MultiExecutor { routes: Vec::new(), capacity, commands, control }
+    }
+
+    /// 控制句柄，在 [`MultiExecutor::run`] 运行期间处理句柄发来的命令
+    pub fn handle(&self) -> ExecutorHandle {
+        ExecutorHandle { sender: self.control.clone() }
+    }
+
+    /// 启动策略，同名策略已在运行时先停止旧的；策略可以是具体类型或 `Box<dyn DynStrategy>`
+    pub fn start<T>(&mut self, name: &str, symbols: Vec<String>, strategy: T)
+    where
+        T: DynStrategy + 'static,
+    {
+        self.stop(name);
+        let (quote_sender, quote_receiver) = mpsc::channel(self.capacity);
+        let (bar_sender, bar_receiver) = mpsc::channel(self.capacity);
+        let mut executor = Executor::with_strategy(strategy, quote_receiver).with_bars(bar_receiver);
+        let (closed_sender, mut closed_receiver) = mpsc::unbounded_channel::<BarEvent>();
+        let forward = bar_sender.clone();
+        // 策略退出后发送失败，转发任务结束，路由据此发现策略已退出
+        tokio::spawn(async move {
+            while let Some(bar) = closed_receiver.recv().await {
+                if forward.send(bar).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let task_name = name.to_string();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = executor.run().await {
+                error!("策略 {} 执行出错: {}", task_name, e);
+            }
+        });
+        info!("启动策略 {}，股票: {:?}", name, symbols);
+        self.routes.push(StrategyRoute {
+            name: name.to_string(),
+            symbols: symbols.into_iter().collect(),
+            quote_sender,
+            bar_sender,
+            closed_sender,
+            handle,
+        });
+    }
+
+    /// 停止策略：关闭通道，策略处理完已收到的行情后退出，返回策略任务
+    pub fn stop(&mut self, name: &str) -> Option<JoinHandle<()>> {
+        let index = self.routes.iter().position(|r| r.name == name)?;
+        let route = self.routes.remove(index);
+        info!("停止策略 {}", name);
+        Some(route.handle)
+    }
+
+    /// 正在运行的策略名称
+    pub fn names(&self) -> Vec<String> {
+        self.routes.iter().map(|r| r.name.clone()).collect()
+    }
+
+    /// 所有策略负责的股票
+    pub fn symbols(&self) -> HashSet<String> {
+        self.routes.iter().flat_map(|r| r.symbols.iter().cloned()).collect()
+    }
+
+    /// 将行情发给负责该股票的策略，返回收到行情的策略数量
+    pub fn route_quote(&mut self, event: &MarketData) -> usize {
+        self.route(&event.symbol, |r| r.quote_sender.try_send(event.clone()).map_err(discard))
+    }
+
+    /// 将K线事件发给负责该股票的策略，返回收到事件的策略数量，不会等待策略处理。
+    ///
+    /// 收盘K线进入策略自己的队列，不会丢失；未收盘的K线更新与行情一样，通道满时丢弃。
+    pub fn route_bar(&mut self, bar: &BarEvent) -> usize {
+        if bar.is_closed() {
+            let closed = |r: &StrategyRoute| r.closed_sender.send(bar.clone()).map_err(|_| TrySendError::Closed(()));
+            return self.route(&bar.symbol, closed);
+        }
+        self.route(&bar.symbol, |r| r.bar_sender.try_send(bar.clone()).map_err(discard))
+    }
+
+    fn route<F>(&mut self, symbol: &str, send: F) -> usize
+    where
+        F: Fn(&StrategyRoute) -> Result<(), TrySendError<()>>,
+    {
+        let mut delivered = 0;
+        let mut exited = Vec::new();
+        for route in self.routes.iter().filter(|r| r.symbols.contains(symbol)) {
+            match send(route) {
+                Ok(()) => delivered += 1,
+                Err(TrySendError::Full(_)) => warn!("策略 {} 处理不过来，丢弃 {} 的数据", route.name, symbol),
+                Err(TrySendError::Closed(_)) => exited.push(route.name.clone()),
+            }
+        }
+        self.remove_exited(exited);
+        delivered
+    }
+
+    // 策略任务已经退出（出错或 panic），不再向其路由
+    fn remove_exited(&mut self, exited: Vec<String>) {
+        for name in exited {
+            warn!("策略 {} 已退出，移除其路由", name);
+            self.routes.retain(|r| r.name != name);
+        }
+    }
+
+    fn apply(&mut self, command: Command) {
+        match command {
+            Command::Start { name, symbols, strategy } => self.start(&name, symbols, strategy),
+            // 不等待策略退出，避免阻塞行情路由
+            Command::Stop(name) => {
+                self.stop(&name);
+            }
+            Command::Names(reply) => {
+                let _ = reply.send(self.names());
+            }
+        }
+    }
+
+    /// 持续路由行情和K线事件并处理控制句柄的命令，行情通道关闭后停止所有策略并等待其退出
+    pub async fn run(
+        &mut self,
+        mut quote_receiver: mpsc::Receiver<MarketData>,
+        mut bar_receiver: Option<mpsc::Receiver<BarEvent>>,
+    ) {
+        loop {
+            let bars = async {
+                match bar_receiver.as_mut() {
+                    Some(rx) => rx.recv().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                event = quote_receiver.recv() => {
+                    let Some(event) = event else { break };
+                    self.route_quote(&event);
+                }
+                bar = bars => {
+                    match bar {
+                        Some(bar) => {
+                            self.route_bar(&bar);
+                        }
+                        None => bar_receiver = None,
+                    }
+                }
+                // 执行器自己持有发送端，通道不会关闭
+                Some(command) = self.commands.recv() => self.apply(command),
+            }
+        }
+        self.shutdown().await;
+    }
+
+    /// 停止所有策略并等待其退出
+    pub async fn shutdown(&mut self) {
+        for route in std::mem::take(&mut self.routes) {
+            let StrategyRoute { name, quote_sender, bar_sender, closed_sender, handle, .. } = route;
+            info!("停止策略 {}", name);
+            // 关闭通道后执行器会调用策略的 stop 并退出
+            drop(quote_sender);
+            drop(bar_sender);
+            drop(closed_sender);
+            if let Err(e) = handle.await {
+                error!("策略 {} 任务异常退出: {}", name, e);
+            }
+        }
+    }
+}
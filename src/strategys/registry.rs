@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use crate::computes::profile::IndicatorProfile;
+use crate::config::config::{SymbolConfig, StrategyConfig};
+use crate::services::broker::{Broker, MarketDataSource};
+use crate::strategys::state::StateStore;
+use crate::strategys::strategy::DynStrategy;
+use crate::strategys::vecor_v1::{VecorParams, VecorStrategy};
+use crate::utils::error::AqtError;
+
+/// 创建策略时可用的上下文
+pub struct StrategyContext<B: Broker, M: MarketDataSource> {
+    /// 策略实例名称
+    pub name: String,
+    pub broker: Arc<B>,
+    pub market: Arc<M>,
+    /// 策略负责的股票配置
+    pub symbols: Vec<SymbolConfig>,
+    /// 命名的指标组合
+    pub profiles: HashMap<String, IndicatorProfile>,
+    /// 配置中的策略参数，由具体策略解析
+    pub params: serde_yaml::Value,
+    /// 策略状态文件，未配置状态持久化时为 `None`
+    pub state: Option<StateStore>,
+}
+
+impl<B: Broker, M: MarketDataSource> StrategyContext<B, M> {
+    /// 将参数解析为策略自己的参数类型，未配置参数时使用默认值
+    pub fn params<P>(&self) -> Result<P, AqtError>
+    where
+        P: serde::de::DeserializeOwned + Default,
+    {
+        if self.params.is_null() {
+            return Ok(P::default());
+        }
+        serde_yaml::from_value(self.params.clone())
+            .map_err(|e| AqtError::Config(format!("策略 {} 参数错误: {}", self.name, e)))
+    }
+}
+
+/// 根据上下文创建策略的工厂函数
+pub type StrategyFactory<B, M> =
+    Box<dyn Fn(StrategyContext<B, M>) -> Result<Box<dyn DynStrategy>, AqtError> + Send + Sync>;
+
+/// 策略注册表：策略以名称注册，启动时按配置中的 `strategy` 创建
+pub struct StrategyRegistry<B: Broker, M: MarketDataSource> {
+    factories: HashMap<String, StrategyFactory<B, M>>,
+    state_dir: Option<PathBuf>,
+}
+
+impl<B: Broker + 'static, M: MarketDataSource + 'static> Default for StrategyRegistry<B, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Broker + 'static, M: MarketDataSource + 'static> StrategyRegistry<B, M> {
+    /// 注册了内置策略的注册表
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register("vecor_v1", |ctx| {
+            let params: VecorParams = ctx.params()?;
+            let strategy = VecorStrategy::with_config(ctx.broker, ctx.market, ctx.symbols, ctx.profiles)
+                .with_name(&ctx.name)
+                .with_params(params);
+            Ok(Box::new(match ctx.state {
+                Some(store) => strategy.with_state(store),
+                None => strategy,
+            }))
+        });
+        registry
+    }
+
+    /// 不包含任何策略的注册表
+    pub fn empty() -> Self {
+        StrategyRegistry { factories: HashMap::new(), state_dir: None }
+    }
+
+    /// 创建的策略把状态保存到 `{dir}/{策略名称}.json`
+    pub fn with_state_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.state_dir = Some(dir.into());
+        self
+    }
+
+    /// 注册策略，同名策略会被覆盖
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(StrategyContext<B, M>) -> Result<Box<dyn DynStrategy>, AqtError> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// 已注册的策略名称（排序后）
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.factories.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 按配置创建策略，策略未注册时返回 [`AqtError::Config`]
+    pub fn build(
+        &self,
+        config: &StrategyConfig,
+        broker: Arc<B>,
+        market: Arc<M>,
+        profiles: &HashMap<String, IndicatorProfile>,
+    ) -> Result<Box<dyn DynStrategy>, AqtError> {
+        let factory = self.factories.get(&config.strategy).ok_or_else(|| {
+            AqtError::Config(format!(
+                "策略 {} 使用了未注册的策略类型 {}，可选: {}",
+                config.name,
+                config.strategy,
+                self.names().join(", ")
+            ))
+        })?;
+        factory(StrategyContext {
+            name: config.name.clone(),
+            broker,
+            market,
+            symbols: config.symbols.clone(),
+            profiles: profiles.clone(),
+            params: config.params.clone(),
+            state: self.state_dir.as_ref().map(|dir| StateStore::for_strategy(dir, &config.name)),
+        })
+    }
+}
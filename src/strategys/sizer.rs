@@ -0,0 +1,224 @@
+use longport::{decimal, Decimal};
+use serde::{Deserialize, Serialize};
+use crate::indicators::atr::atr;
+use crate::indicators::candle::Candle;
+
+/// 计算开仓数量时可用的信息
+pub struct SizingContext<'a> {
+    /// 可用于开仓的资金
+    pub capital: Decimal,
+    /// 下单价格
+    pub price: Decimal,
+    /// 入场周期的K线
+    pub candles: &'a [Candle],
+    /// 该股票的历史平仓盈亏统计
+    pub stats: &'a TradeStats,
+}
+
+/// 买入时要求可用资金覆盖 `数量 * 价格 * BUY_BUFFER`，为委托价上浮和费用留出余量
+pub const BUY_BUFFER: Decimal = Decimal::from_parts(105, 0, 0, false, 2);
+
+/// 仓位模型：根据资金、价格、波动和历史表现计算开仓数量（股数），返回 0 表示不开仓
+pub trait PositionSizer: Send + Sync {
+    fn quantity(&self, ctx: &SizingContext) -> Decimal;
+}
+
+/// 按资金比例开仓：`ceil(资金 * fraction / 价格)`
+pub struct FixedFraction {
+    pub fraction: f64,
+}
+
+impl PositionSizer for FixedFraction {
+    fn quantity(&self, ctx: &SizingContext) -> Decimal {
+        if ctx.price.is_zero() {
+            return Decimal::ZERO;
+        }
+        (ctx.capital * decimal!(self.fraction) / ctx.price).ceil()
+    }
+}
+
+/// 每次开仓固定金额：`floor(amount / 价格)`
+pub struct FixedAmount {
+    pub amount: f64,
+}
+
+impl PositionSizer for FixedAmount {
+    fn quantity(&self, ctx: &SizingContext) -> Decimal {
+        if ctx.price.is_zero() {
+            return Decimal::ZERO;
+        }
+        (decimal!(self.amount) / ctx.price).floor()
+    }
+}
+
+/// 波动率目标：止损设在 `multiple` 倍 ATR 处，触发止损时亏损资金的 `risk_pct`%，
+/// 不超过资金按价格（含 [`BUY_BUFFER`] 余量）可买的数量 `floor(资金 / (价格 * 1.05))`；
+/// `multiple` 不是正数或 ATR 为 0 时不开仓
+pub struct AtrVolatility {
+    pub risk_pct: f64,
+    pub period: usize,
+    pub multiple: f64,
+}
+
+impl PositionSizer for AtrVolatility {
+    fn quantity(&self, ctx: &SizingContext) -> Decimal {
+        if ctx.price <= Decimal::ZERO || self.multiple <= 0.0 {
+            return Decimal::ZERO;
+        }
+        let Some(stop) = atr(ctx.candles, self.period)
+            .map(|a| a * self.multiple)
+            .filter(|s| *s > 0.0 && s.is_finite())
+        else {
+            return Decimal::ZERO;
+        };
+        let stop = decimal!(stop);
+        if stop.is_zero() {
+            return Decimal::ZERO;
+        }
+        let risk = ctx.capital * decimal!(self.risk_pct) * decimal!(0.01);
+        // 波动很小时按风险计算的数量可能远超资金，按下单时的资金检查封顶，否则订单不会提交
+        let affordable = (ctx.capital / (ctx.price * BUY_BUFFER)).floor();
+        (risk / stop).floor().min(affordable)
+    }
+}
+
+/// 凯利公式：`f = 胜率 - 败率 / 盈亏比`，乘以 `multiplier` 后不超过 `cap`。
+///
+/// 平仓次数少于 `min_trades` 时按 `fallback` 比例开仓；凯利比例不大于 0 时不开仓。
+pub struct Kelly {
+    pub multiplier: f64,
+    pub cap: f64,
+    pub min_trades: u32,
+    pub fallback: f64,
+}
+
+impl PositionSizer for Kelly {
+    fn quantity(&self, ctx: &SizingContext) -> Decimal {
+        if ctx.price.is_zero() {
+            return Decimal::ZERO;
+        }
+        let fraction = if ctx.stats.trades() < self.min_trades {
+            self.fallback
+        } else {
+            match ctx.stats.kelly() {
+                Some(kelly) if kelly > 0.0 => (kelly * self.multiplier).min(self.cap),
+                _ => return Decimal::ZERO,
+            }
+        };
+        (ctx.capital * decimal!(fraction) / ctx.price).floor()
+    }
+}
+
+/// 平仓盈亏统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradeStats {
+    pub wins: u32,
+    pub losses: u32,
+    pub total_win: f64,  // 盈利交易的盈利合计
+    pub total_loss: f64, // 亏损交易的亏损合计（正数）
+}
+
+impl TradeStats {
+    /// 记录一次平仓盈亏
+    pub fn record(&mut self, pnl: f64) {
+        if pnl > 0.0 {
+            self.wins += 1;
+            self.total_win += pnl;
+        } else {
+            self.losses += 1;
+            self.total_loss += -pnl;
+        }
+    }
+
+    pub fn trades(&self) -> u32 {
+        self.wins + self.losses
+    }
+
+    /// 凯利比例，没有交易时返回 `None`；没有亏损交易时等于胜率
+    pub fn kelly(&self) -> Option<f64> {
+        if self.trades() == 0 {
+            return None;
+        }
+        let win_rate = self.wins as f64 / self.trades() as f64;
+        if self.wins == 0 {
+            return Some(-1.0);
+        }
+        if self.losses == 0 || self.total_loss == 0.0 {
+            return Some(win_rate);
+        }
+        let payoff = (self.total_win / self.wins as f64) / (self.total_loss / self.losses as f64);
+        Some(win_rate - (1.0 - win_rate) / payoff)
+    }
+}
+
+/// 仓位模型配置，用 `kind` 区分，未配置时按 `volume` 比例开仓
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SizerConfig {
+    /// 按股票的 `volume` 比例开仓
+    #[default]
+    FixedFraction,
+    /// 固定金额
+    FixedAmount { amount: f64 },
+    /// ATR 波动率目标
+    AtrVolatility {
+        risk_pct: f64,
+        #[serde(default = "default_atr_period")]
+        period: usize,
+        #[serde(default = "default_atr_multiple")]
+        multiple: f64,
+    },
+    /// 凯利公式
+    Kelly {
+        #[serde(default = "default_kelly_multiplier")]
+        multiplier: f64,
+        #[serde(default = "default_kelly_cap")]
+        cap: f64,
+        #[serde(default = "default_min_trades")]
+        min_trades: u32,
+        /// 交易次数不足时的资金比例，未配置时使用股票的 `volume`
+        #[serde(default)]
+        fallback: Option<f64>,
+    },
+}
+
+fn default_atr_period() -> usize {
+    14
+}
+
+fn default_atr_multiple() -> f64 {
+    2.0
+}
+
+fn default_kelly_multiplier() -> f64 {
+    0.5
+}
+
+fn default_kelly_cap() -> f64 {
+    0.25
+}
+
+fn default_min_trades() -> u32 {
+    20
+}
+
+impl SizerConfig {
+    /// 创建仓位模型，`volume` 为股票配置的开仓比例
+    pub fn build(&self, volume: f64) -> Box<dyn PositionSizer> {
+        match self {
+            SizerConfig::FixedFraction => Box::new(FixedFraction { fraction: volume }),
+            SizerConfig::FixedAmount { amount } => Box::new(FixedAmount { amount: *amount }),
+            SizerConfig::AtrVolatility { risk_pct, period, multiple } => Box::new(AtrVolatility {
+                risk_pct: *risk_pct,
+                period: *period,
+                multiple: *multiple,
+            }),
+            SizerConfig::Kelly { multiplier, cap, min_trades, fallback } => Box::new(Kelly {
+                multiplier: *multiplier,
+                cap: *cap,
+                min_trades: *min_trades,
+                fallback: fallback.unwrap_or(volume),
+            }),
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use longport::trade::OrderSide;
+use longport::Decimal;
+use serde::{Deserialize, Serialize};
+use crate::computes::calculate::Decision;
+use crate::models::order::{Order, OrderIntent};
+use crate::models::symbol_time::SymbolTimeData;
+use crate::strategys::exit_manager::ExitManager;
+use crate::strategys::sizer::TradeStats;
+use crate::utils::error::AqtError;
+
+/// 已结束的订单保留的时间（秒）
+const ORDER_RETENTION_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// 策略下过的订单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlacedOrder {
+    pub order_id: String,
+    pub symbol: String,
+    #[serde(with = "side")]
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    /// 已成交数量，启动对账时按券商订单更新
+    #[serde(default)]
+    pub executed_quantity: Decimal,
+    /// 提交时间（秒级时间戳）
+    pub submitted_at: i64,
+    /// 是否仍可能成交
+    pub active: bool,
+    /// 下单意图的幂等键，直接下单时为空
+    #[serde(default)]
+    pub key: String,
+    /// 平仓订单的持仓成本价，订单结束后按成交均价计入盈亏统计，计入后清空
+    #[serde(default)]
+    pub exit_cost: Option<Decimal>,
+}
+
+impl PlacedOrder {
+    pub fn new(order_id: String, symbol: &str, side: OrderSide, price: Decimal, quantity: Decimal, submitted_at: i64) -> Self {
+        PlacedOrder {
+            order_id,
+            symbol: symbol.to_string(),
+            side,
+            price,
+            quantity,
+            executed_quantity: Decimal::ZERO,
+            submitted_at,
+            active: true,
+            key: String::new(),
+            exit_cost: None,
+        }
+    }
+
+    /// 按下单意图提交成功的订单
+    pub fn from_intent(order_id: String, intent: &OrderIntent, submitted_at: i64) -> Self {
+        PlacedOrder {
+            key: intent.key.clone(),
+            ..Self::new(order_id, &intent.symbol, intent.side, intent.price, intent.quantity, submitted_at)
+        }
+    }
+}
+
+/// 最近一次技术指标判断
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    pub signal: i64,
+    /// 各指标的判断依据
+    pub explain: String,
+    /// 判断时间（秒级时间戳）
+    pub ts: i64,
+}
+
+/// 策略运行状态的快照，每次变化后写入磁盘，重启后恢复
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StrategyState {
+    /// 按行情推测K线收盘时，每个股票下一次判断的时间
+    #[serde(default)]
+    pub next_run_time: Vec<SymbolTimeData>,
+    /// 已由K线收盘事件驱动的股票，其余股票仍按行情推测K线收盘
+    #[serde(default, deserialize_with = "bar_driven::deserialize")]
+    pub bar_driven: HashSet<String>,
+    /// 每个股票最近一次判断过的收盘K线时间戳，重启后不会重复处理同一根K线
+    #[serde(default)]
+    pub last_bars: HashMap<String, u64>,
+    /// 每个股票最近一次技术指标判断
+    #[serde(default)]
+    pub decisions: HashMap<String, DecisionRecord>,
+    /// 策略下过的订单
+    #[serde(default)]
+    pub orders: Vec<PlacedOrder>,
+    /// 每个股票的平仓盈亏统计（按平仓订单的成交计算），供凯利仓位模型使用
+    #[serde(default)]
+    pub trades: HashMap<String, TradeStats>,
+    /// 平仓管理的持仓跟踪状态：开仓时间、跟踪止损和保本状态
+    #[serde(default)]
+    pub exits: ExitManager,
+}
+
+impl StrategyState {
+    /// 记录技术指标判断
+    pub fn record_decision(&mut self, symbol: &str, decision: &Decision, ts: i64) {
+        self.decisions.insert(symbol.to_string(), DecisionRecord {
+            signal: decision.signal,
+            explain: decision.explain(),
+            ts,
+        });
+    }
+
+    /// 记录提交成功的订单
+    pub fn record_order(&mut self, order: PlacedOrder) {
+        self.orders.push(order);
+    }
+
+    /// 标记平仓订单及其持仓成本价，订单结束后由 [`record_fills`](Self::record_fills) 计入盈亏统计
+    pub fn track_exit(&mut self, order_id: &str, cost: Decimal) {
+        if let Some(placed) = self.orders.iter_mut().find(|o| o.order_id == order_id) {
+            placed.exit_cost = Some(cost);
+        }
+    }
+
+    /// 按券商订单的成交结果记录已结束的平仓订单盈亏，未成交就结束的订单不计入，返回计入的数量
+    pub fn record_fills(&mut self, orders: &[Order]) -> usize {
+        let mut recorded = 0;
+        for placed in self.orders.iter_mut().filter(|p| p.exit_cost.is_some()) {
+            let Some(order) = orders.iter().find(|o| o.order_id == placed.order_id) else {
+                continue;
+            };
+            placed.executed_quantity = order.executed_quantity;
+            placed.active = order.is_open();
+            if placed.active {
+                continue;
+            }
+            let cost = placed.exit_cost.take().unwrap_or_default();
+            if order.executed_quantity.is_zero() {
+                continue;
+            }
+            let price = order.executed_price.unwrap_or(placed.price);
+            // 卖出平多按成交价减成本计算，买入平空相反
+            let diff = if order.side == OrderSide::Sell { price - cost } else { cost - price };
+            let pnl = f64::try_from(diff * order.executed_quantity).unwrap_or(0.0);
+            self.trades.entry(placed.symbol.clone()).or_default().record(pnl);
+            recorded += 1;
+        }
+        recorded
+    }
+
+    /// `before`（秒级时间戳）之前提交、尚未计入盈亏统计的平仓订单中最早的提交时间，
+    /// 这些订单不在今日订单中，需要按历史订单计入
+    pub fn earliest_unrecorded_exit(&self, symbol: &str, before: i64) -> Option<i64> {
+        self.orders
+            .iter()
+            .filter(|p| p.symbol == symbol && p.exit_cost.is_some() && p.submitted_at < before)
+            .map(|p| p.submitted_at)
+            .min()
+    }
+
+    /// 该股票是否还有可能成交的开仓订单（不是平仓订单）
+    pub fn has_open_entry(&self, symbol: &str) -> bool {
+        self.orders.iter().any(|p| p.symbol == symbol && p.active && p.exit_cost.is_none())
+    }
+
+    /// 该幂等键的下单意图是否已经提交过
+    pub fn has_intent(&self, key: &str) -> bool {
+        !key.is_empty() && self.orders.iter().any(|o| o.key == key)
+    }
+
+    /// 该股票是否已由K线收盘事件驱动
+    pub fn is_bar_driven(&self, symbol: &str) -> bool {
+        self.bar_driven.contains(symbol)
+    }
+
+    /// 该K线是否已经判断过
+    pub fn is_processed(&self, symbol: &str, timestamp: u64) -> bool {
+        self.last_bars.get(symbol).is_some_and(|ts| *ts >= timestamp)
+    }
+
+    /// 该股票最近一根已收盘K线的时间戳：K线驱动时为最近处理的收盘K线，否则为按行情推测的上一根K线
+    pub fn last_closed_bar(&self, symbol: &str) -> Option<u64> {
+        self.last_bars.get(symbol).copied().or_else(|| {
+            self.next_run_time
+                .iter()
+                .find(|t| t.symbol == symbol && t.last_time > 0)
+                .map(|t| t.last_time)
+        })
+    }
+
+    /// 与券商的今日订单对账，`now` 为秒级时间戳。
+    ///
+    /// `orders` 为券商今日的全部订单（包含部分成交、已撤销和被拒绝的订单）。
+    /// 券商有记录的订单更新成交数量和状态，幂等键随订单保留；今日提交但券商没有记录的订单被移除并返回；
+    /// 之前交易日的订单不会再成交，标记为结束，超过保留时间后清理。
+    /// 券商订单备注是策略 `strategy` 的、状态中没有的幂等键时（提交后未来得及保存状态），补记该订单。
+    pub fn reconcile(&mut self, strategy: &str, orders: &[Order], now: i64) -> Vec<PlacedOrder> {
+        let day_start = now - now.rem_euclid(24 * 60 * 60);
+        let mut dropped = Vec::new();
+        self.orders.retain_mut(|placed| {
+            if let Some(order) = orders.iter().find(|o| o.order_id == placed.order_id) {
+                placed.executed_quantity = order.executed_quantity;
+                placed.active = order.is_open();
+                return true;
+            }
+            if placed.submitted_at >= day_start {
+                dropped.push(placed.clone());
+                return false;
+            }
+            placed.active = false;
+            placed.submitted_at >= now - ORDER_RETENTION_SECS
+        });
+        for order in orders {
+            if order.remark.is_empty() || self.orders.iter().any(|p| p.order_id == order.order_id) {
+                continue;
+            }
+            if self.has_intent(&order.remark) || !order.remark.starts_with(&OrderIntent::key_prefix(strategy, &order.symbol)) {
+                continue;
+            }
+            let price = order.price.unwrap_or_default();
+            let submitted_at = order.submitted_at.unix_timestamp();
+            let mut placed = PlacedOrder::new(order.order_id.clone(), &order.symbol, order.side, price, order.quantity, submitted_at);
+            placed.executed_quantity = order.executed_quantity;
+            placed.active = order.is_open();
+            placed.key = order.remark.clone();
+            self.orders.push(placed);
+        }
+        dropped
+    }
+}
+
+/// 策略状态文件，JSON 格式。
+///
+/// 先写入同目录下的临时文件再重命名，进程在写入过程中退出也不会留下不完整的状态。
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    path: PathBuf,
+}
+
+impl StateStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        StateStore { path: path.into() }
+    }
+
+    /// 在 `dir` 下为策略 `name` 创建状态文件 `{dir}/{name}.json`
+    pub fn for_strategy(dir: impl AsRef<Path>, name: &str) -> Self {
+        Self::new(dir.as_ref().join(format!("{}.json", name)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 读取状态，文件不存在时返回 `None`，内容无法解析时返回 [`AqtError::Data`]
+    pub fn load(&self) -> Result<Option<StrategyState>, AqtError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| AqtError::Data(format!("读取 {} 失败: {}", self.path.display(), e)))?;
+        let state = serde_json::from_str(&content)
+            .map_err(|e| AqtError::Data(format!("解析 {} 失败: {}", self.path.display(), e)))?;
+        Ok(Some(state))
+    }
+
+    /// 保存状态
+    pub fn save(&self, state: &StrategyState) -> Result<(), AqtError> {
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let tmp = self.path.with_extension("json.tmp");
+            fs::write(&tmp, serde_json::to_vec_pretty(state)?)?;
+            fs::rename(&tmp, &self.path)
+        };
+        write().map_err(|e| AqtError::Data(format!("写入 {} 失败: {}", self.path.display(), e)))
+    }
+}
+
+/// 订单方向以 `Buy` / `Sell` 保存
+mod side {
+    use longport::trade::OrderSide;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(side: &OrderSide, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match side {
+            OrderSide::Buy => "Buy",
+            OrderSide::Sell => "Sell",
+            _ => "Unknown",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OrderSide, D::Error> {
+        Ok(match String::deserialize(deserializer)?.as_str() {
+            "Buy" => OrderSide::Buy,
+            "Sell" => OrderSide::Sell,
+            _ => OrderSide::Unknown,
+        })
+    }
+}
+
+/// 旧版本状态文件中 `bar_driven` 是整个策略共用的布尔值，读取时忽略，收到K线后重新记录
+mod bar_driven {
+    use std::collections::HashSet;
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Symbols(HashSet<String>),
+        #[allow(dead_code)]
+        Legacy(bool),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashSet<String>, D::Error> {
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Legacy(_) => HashSet::new(),
+            Repr::Symbols(symbols) => symbols,
+        })
+    }
+}
@@ -1,10 +1,67 @@
 use std::sync::Arc;
-use longport::{QuoteContext, TradeContext};
+use futures::future::BoxFuture;
+use crate::models::bar::BarEvent;
 use crate::models::market::MarketData;
+use crate::services::broker::{Broker, MarketDataSource};
 
 pub trait Strategy {
-    fn new(quote_ctx: Arc<QuoteContext>, trade_ctx: Arc<TradeContext>) -> Self;
+    /// 下单使用的券商实现
+    type Broker: Broker;
+    /// 行情数据来源
+    type Market: MarketDataSource;
+
+    fn new(broker: Arc<Self::Broker>, market: Arc<Self::Market>) -> Self;
     fn run(&mut self) -> impl std::future::Future<Output = Result<(), Box<dyn std::error::Error>>> + Send;
     fn execute(&mut self, event: &MarketData) -> impl std::future::Future<Output = Result<(), Box<dyn std::error::Error>>> + Send;
+    /// 处理K线事件，默认忽略
+    fn on_bar(&mut self, _bar: &BarEvent) -> impl std::future::Future<Output = Result<(), Box<dyn std::error::Error>>> + Send {
+        async { Ok(()) }
+    }
+    fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// 对象安全的策略接口，可以装箱后由执行器驱动，用于按名称创建策略。
+///
+/// 所有实现了 [`Strategy`] 的策略自动实现该接口。
+pub trait DynStrategy: Send {
+    fn run(&mut self) -> BoxFuture<'_, Result<(), Box<dyn std::error::Error>>>;
+    fn execute<'a>(&'a mut self, event: &'a MarketData) -> BoxFuture<'a, Result<(), Box<dyn std::error::Error>>>;
+    fn on_bar<'a>(&'a mut self, bar: &'a BarEvent) -> BoxFuture<'a, Result<(), Box<dyn std::error::Error>>>;
     fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>>;
 }
+
+impl<T: Strategy + Send> DynStrategy for T {
+    fn run(&mut self) -> BoxFuture<'_, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(Strategy::run(self))
+    }
+
+    fn execute<'a>(&'a mut self, event: &'a MarketData) -> BoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(Strategy::execute(self, event))
+    }
+
+    fn on_bar<'a>(&'a mut self, bar: &'a BarEvent) -> BoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(Strategy::on_bar(self, bar))
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Strategy::stop(self)
+    }
+}
+
+impl DynStrategy for Box<dyn DynStrategy> {
+    fn run(&mut self) -> BoxFuture<'_, Result<(), Box<dyn std::error::Error>>> {
+        (**self).run()
+    }
+
+    fn execute<'a>(&'a mut self, event: &'a MarketData) -> BoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        (**self).execute(event)
+    }
+
+    fn on_bar<'a>(&'a mut self, bar: &'a BarEvent) -> BoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        (**self).on_bar(bar)
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        (**self).stop()
+    }
+}
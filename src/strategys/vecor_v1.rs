@@ -1,209 +1,541 @@
-use crate::calculates::cyc_calculate::CycCalculate;
-use crate::calculates::kdj_calculate::KdjCalculate;
-use crate::calculates::macd_calculate::MacdCalculate;
-use crate::calculates::stc_calculate::STCCalculate;
-use crate::calculates::technicals_calculate::TechnicalsCalculate;
-use crate::calculates::utbot_calculate::UTBotCalculate;
-use crate::computes::calculate::Calculate;
-use crate::computes::defult_rules::{CulRules, DefultRules};
+use crate::computes::calculate::Decision;
+use crate::computes::indicator_state::IndicatorState;
+use crate::computes::profile::IndicatorProfile;
 use crate::config::config;
 use crate::config::config::SymbolConfig;
-use crate::indicators::candle::Candle;
+use crate::indicators::candle::{aggregate_candles, period_seconds, Candle};
 use crate::indicators::tradingview_technicals::TradingTechnicals;
+use crate::models::bar::BarEvent;
 use crate::models::market::MarketData;
 use crate::models::symbol_time::SymbolTimeData;
-use crate::services::service::Service;
+use crate::models::order::{Order, OrderIntent};
+use crate::models::position::Position;
+use crate::services::broker::{Broker, MarketDataSource};
+use crate::strategys::exit_manager::ExitReason;
+use crate::strategys::sizer::{SizingContext, BUY_BUFFER};
+use crate::strategys::state::{PlacedOrder, StateStore, StrategyState};
 use crate::strategys::strategy::Strategy;
+use crate::utils::error::AqtError;
 use log::{info, warn};
-use longport::quote::{Candlestick};
-use longport::trade::{Order, OrderSide, OrderStatus, StockPosition, StockPositionChannel};
-use longport::{decimal, Decimal, QuoteContext, TradeContext};
+use longport::trade::{OrderSide, OrderStatus, SubmitOrderResponse};
+use longport::quote::MarketTemperature;
+use longport::{decimal, Decimal};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::error::Error;
 use std::sync::Arc;
 use time::OffsetDateTime;
 
 /// VecorStrategy 结构体实现了 Strategy trait，用于执行具体的交易策略
-pub struct VecorStrategy {
-    /// 服务实例，用于访问各种交易和行情数据
-    service: Service,
+pub struct VecorStrategy<B: Broker, M: MarketDataSource> {
+    /// 策略实例名称，作为下单意图幂等键的前缀
+    name: String,
+    /// 券商实例，用于下单、查询持仓、资金和订单
+    broker: Arc<B>,
+    /// 行情数据来源，用于获取K线等行情数据
+    market: Arc<M>,
     /// 股票配置映射，存储每个股票的配置信息
     sym_config: Vec<SymbolConfig>,
-    next_run_time: Vec<SymbolTimeData>,
+    /// 命名的指标组合，股票通过 `profile` 引用
+    profiles: HashMap<String, IndicatorProfile>,
+    /// 运行状态：下一次判断时间、最近的判断、下过的订单和平仓管理，配置了 `store` 时每次变化后保存
+    state: StrategyState,
+    /// 状态文件，未配置时状态只保存在内存中
+    store: Option<StateStore>,
+    /// 每个股票、周期的指标状态，每次判断前用最新K线增量推进
+    indicators: HashMap<String, IndicatorState>,
+    /// 有未完成开仓订单的股票及上次同步持仓的时间（秒级时间戳），行情到来时定期同步，成交后立即受平仓规则保护
+    entry_syncs: HashMap<String, i64>,
+    /// 配置中的策略参数
+    params: VecorParams,
 }
 
-impl Strategy for VecorStrategy {
-    /// 创建一个新的 VecorStrategy 实例
-    fn new(quote_ctx: Arc<QuoteContext>, trade_ctx: Arc<TradeContext>) -> Self {
-        let cfgs = config::Configs::load();
-        let sym_config = cfgs.unwrap().symbols;
+/// vecor_v1 的策略参数，对应配置中的 `params`；未知的参数视为配置错误
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VecorParams {
+    /// 有未完成开仓订单时，行情触发同步持仓的最小间隔（秒），默认 5
+    pub entry_sync_secs: i64,
+}
 
-        VecorStrategy {
-            service: Service::new(quote_ctx, trade_ctx),
-            sym_config,
-            next_run_time: vec![],
-        }
+impl Default for VecorParams {
+    fn default() -> Self {
+        VecorParams { entry_sync_secs: 5 }
+    }
+}
+
+impl<B: Broker, M: MarketDataSource> Strategy for VecorStrategy<B, M> {
+    type Broker = B;
+    type Market = M;
+
+    /// 创建一个新的 VecorStrategy 实例
+    fn new(broker: Arc<B>, market: Arc<M>) -> Self {
+        let cfgs = config::Configs::load().unwrap();
+        Self::with_config(broker, market, cfgs.symbols, cfgs.profiles)
     }
 
     /// 异步运行策略逻辑
     async fn run(&mut self) -> Result<(), Box<dyn Error>> {
         info!("vecor v1 策略程序开始执行");
+        self.restore().await?;
         Ok(())
     }
 
     /// 异步执行策略逻辑，处理传入的市场数据
     async fn execute(&mut self, event: &MarketData) -> Result<(), Box<dyn Error>> {
+        // 每笔行情检查止损、跟踪止损、保本和持仓时间
+        self.check_exit(event).await?;
+        // 该股票已由K线收盘事件驱动时，行情只用于撮合，不再推测K线收盘；
+        // 没有收到过K线的股票（订阅失败或没有推送）仍按行情判断
+        if self.state.is_bar_driven(&event.symbol) {
+            return Ok(());
+        }
         // 判断当前的数据时间
         let ts = event.ts.unix_timestamp();
         let market_px = event.price.clone();
-        let (index, next_times) = VecorStrategy::get_sym_time_info(self.next_run_time.clone(), event.symbol.clone());
+        let (index, next_times) = Self::get_sym_time_info(self.state.next_run_time.clone(), event.symbol.clone());
         // 只处理收尾的K线
         if (next_times.next_time == 0 || next_times.next_time < ts as u64)
             && !market_px.clone().is_zero()
         {
             // 获取币种信息
-            let sym = VecorStrategy::get_sym_info(self.sym_config.clone(), event.symbol.clone());
-            let candles = self
-                .service
+            let sym = Self::get_sym_info(self.sym_config.clone(), event.symbol.clone());
+            let candles_list = self
+                .market
                 .get_candlesticks(event.symbol.clone(), sym.clone().period)
-                .await;
+                .await?;
             // info!("获取{}股票K线数据", event.symbol.clone());
             // 防止为空
-            if candles.clone().is_empty() {
-                return Ok(());
-            }
-            let candles_list = VecorStrategy::handle_candles(event.symbol.clone(), candles.clone());
-            // 防止为空
             if candles_list.clone().is_empty() {
                 return Ok(());
             }
-            let (symts,is_next) =  VecorStrategy::timestamp_to_time(candles_list.clone(), event.symbol.clone());
+            let (symts,is_next) =  Self::timestamp_to_time(candles_list.clone(), event.symbol.clone());
             if next_times.next_time == 0 {
-                self.next_run_time.push(symts); // 插入新的 SymbolTimeData 到 Vec 中 
+                self.state.next_run_time.push(symts); // 插入新的 SymbolTimeData 到 Vec 中 
                 // 新的k线
                 if is_next {
+                    self.save_state();
                     return Ok(());
                 }
             } else {
                 // 如果已经有记录，则可以在这里进行更新操作
                 // 更新指定索引位置的值
-                self.next_run_time[index] = symts; 
+                self.state.next_run_time[index] = symts; 
             }
-            // 下单
-            // 获取用户的持仓
-            let positions = self.service.stock_positions().await;
-            let sym_position = VecorStrategy::handler_positions(positions, event.symbol.clone());
-
-            // TODO 判断是否达到收益预期 进行回撤、仓位判断 决定是否抛售
-            if VecorStrategy::handler_close_position(sym.clone(), candles, sym_position.clone()) {
-                info!("{:?}", market_px.clone());
-                let resp = self
-                    .service
-                    .submit_order(
-                        event.symbol.clone(),
-                        OrderSide::Sell,
-                        market_px.clone(),
-                        sym_position.available_quantity,
-                    )
-                    .await;
-                info!("{:?}", resp);
-                return Ok(());
+            let result = self.evaluate(&event.symbol, market_px, sym, candles_list).await;
+            self.save_state();
+            result?;
+        }
+        Ok(())
+    }
+
+    /// 处理K线收盘事件，使用已收盘的K线进行判断
+    async fn on_bar(&mut self, bar: &BarEvent) -> Result<(), Box<dyn Error>> {
+        if !bar.is_closed() {
+            return Ok(());
+        }
+        let sym = Self::get_sym_info(self.sym_config.clone(), bar.symbol.clone());
+        if sym.symbol.is_empty() || sym.period != bar.period {
+            return Ok(());
+        }
+        self.state.bar_driven.insert(bar.symbol.clone());
+        // 重启前已经判断过的K线不再处理
+        if self.state.is_processed(&bar.symbol, bar.candle.timestamp) {
+            return Ok(());
+        }
+        let mut candles_list = self
+            .market
+            .get_candlesticks(bar.symbol.clone(), sym.period.clone())
+            .await?;
+        // 只保留到收盘K线为止的数据，并以推送的收盘K线为准
+        candles_list.retain(|c| c.timestamp < bar.candle.timestamp);
+        candles_list.push(bar.candle.clone());
+        let market_px = decimal!(bar.candle.close);
+        if market_px.is_zero() {
+            return Ok(());
+        }
+        self.state.last_bars.insert(bar.symbol.clone(), bar.candle.timestamp);
+        let result = self.evaluate(&bar.symbol, market_px, sym, candles_list).await;
+        self.save_state();
+        result?;
+        Ok(())
+    }
+
+    /// 停止策略执行
+    fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        warn!("vecor v1 策略程序停止");
+        Ok(())
+    }
+}
+
+// 增加额外的函数
+impl<B: Broker, M: MarketDataSource> VecorStrategy<B, M> {
+    /// 使用指定的股票配置和指标组合创建策略，用于同时运行多个策略实例
+    pub fn with_config(
+        broker: Arc<B>,
+        market: Arc<M>,
+        sym_config: Vec<SymbolConfig>,
+        profiles: HashMap<String, IndicatorProfile>,
+    ) -> Self {
+        VecorStrategy {
+            name: "vecor_v1".to_string(),
+            broker,
+            market,
+            sym_config,
+            profiles,
+            state: StrategyState::default(),
+            store: None,
+            indicators: HashMap::new(),
+            entry_syncs: HashMap::new(),
+            params: VecorParams::default(),
+        }
+    }
+
+    /// 设置策略实例名称，默认为 `vecor_v1`；同一个股票由多个策略交易时各自的下单意图互不影响
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// 设置策略参数，未设置时使用默认值
+    pub fn with_params(mut self, params: VecorParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// 启动时从状态文件恢复，运行中每次状态变化后保存
+    pub fn with_state(mut self, store: StateStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// 当前运行状态
+    pub fn state(&self) -> &StrategyState {
+        &self.state
+    }
+
+    /// 恢复上次保存的状态，并与券商的订单、持仓对账后再开始交易
+    ///
+    /// 状态文件损坏或查询券商失败时返回错误，策略不会启动。
+    async fn restore(&mut self) -> Result<(), AqtError> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        if let Some(state) = store.load()? {
+            info!("从 {} 恢复策略状态，记录订单 {} 笔", store.path().display(), state.orders.len());
+            self.state = state;
+        }
+        let now = self.broker.now();
+        let mut orders = Vec::new();
+        for sym in &self.sym_config {
+            orders.extend(self.broker.get_today_orders(&sym.symbol).await?);
+        }
+        for placed in self.state.reconcile(&self.name, &orders, now.unix_timestamp()) {
+            warn!("{} 订单 {} 在券商没有记录，已从状态中移除", placed.symbol, placed.order_id);
+        }
+        self.state.record_fills(&orders);
+        let symbols: Vec<String> = self.sym_config.iter().map(|s| s.symbol.clone()).collect();
+        for symbol in &symbols {
+            self.record_earlier_fills(symbol, now).await;
+        }
+        for order in orders.iter().filter(|o| o.is_open()) {
+            if !self.state.orders.iter().any(|p| p.order_id == order.order_id) {
+                warn!("{} 存在不是由策略提交的未完成订单 {}", order.symbol, order.order_id);
             }
-            // TODO 聚合技术判断
-            let inds = VecorStrategy::handler_indicators(candles_list, sym.clone()).await;
-            info!("对{}进行技术指标聚合判断:{}", event.symbol.clone(), inds);
-            if inds == OrderSide::Buy
-                && !sym_position.cost_price.is_zero()
-                && sym_position.cost_price * decimal!(0.99) <= market_px.clone()
-            {
-                return Ok(());
+        }
+        // 持仓以券商为准，同步给平仓管理
+        let positions = self.broker.stock_positions().await?;
+        for sym in &self.sym_config {
+            let position = Self::handler_positions(positions.clone(), sym.symbol.clone());
+            if !position.quantity.is_zero() {
+                info!("{} 当前持仓 {}，成本 {}", sym.symbol, position.quantity, position.cost_price);
             }
-            if inds == OrderSide::Sell
-                && !sym_position.cost_price.is_zero()
-                && sym_position.cost_price >= market_px.clone() * decimal!(0.99)
-            {
-                return Ok(());
+            self.state.exits.update_position(&sym.symbol, &position, &orders, now);
+            if self.state.has_open_entry(&sym.symbol) {
+                self.entry_syncs.insert(sym.symbol.clone(), 0);
             }
-            // TODO 指标指出可以买卖
-            if inds != OrderSide::Unknown {
-                // info!("获取用户的资金");
-                // 获取用户的资金
-                let balance = self.service.account_balance().await;
-                if balance.is_empty() {
+        }
+        self.save_state();
+        Ok(())
+    }
+
+    /// 有未完成的开仓订单时按行情定期同步持仓和跟踪止损，开仓订单结束后停止；
+    /// 查询失败时只记录日志，下一笔行情重试
+    async fn sync_entry(&mut self, sym: &SymbolConfig, now: OffsetDateTime) {
+        let symbol = sym.symbol.as_str();
+        let Some(last) = self.entry_syncs.get(symbol).copied() else {
+            return;
+        };
+        let now_ts = now.unix_timestamp();
+        if now_ts - last < self.params.entry_sync_secs {
+            return;
+        }
+        self.entry_syncs.insert(symbol.to_string(), now_ts);
+        let synced = async {
+            let positions = self.broker.stock_positions().await?;
+            let orders = self.broker.get_today_orders(symbol).await?;
+            let candles = match sym.exit.trailing {
+                Some(_) => self.market.get_candlesticks(symbol.to_string(), sym.period.clone()).await?,
+                None => Vec::new(),
+            };
+            Ok::<_, AqtError>((Self::handler_positions(positions, symbol.to_string()), orders, candles))
+        };
+        let (position, orders, candles) = match synced.await {
+            Ok(synced) => synced,
+            Err(e) => {
+                warn!("{} 同步持仓失败: {}", symbol, e);
+                return;
+            }
+        };
+        for placed in self.state.reconcile(&self.name, &orders, now_ts) {
+            warn!("{} 订单 {} 在券商没有记录，已从状态中移除", placed.symbol, placed.order_id);
+        }
+        self.state.record_fills(&orders);
+        self.state.exits.update_position(symbol, &position, &orders, now);
+        self.state.exits.update_levels(sym, &candles);
+        if !self.state.has_open_entry(symbol) {
+            self.entry_syncs.remove(symbol);
+        }
+        self.save_state();
+    }
+
+    /// 保存状态，失败时只记录日志，不影响交易
+    fn save_state(&self) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save(&self.state) {
+                warn!("保存策略状态失败: {}", e);
+            }
+        }
+    }
+
+    /// 之前交易日提交、仍未计入盈亏统计的平仓订单（隔日成交或部分成交后撤销）按历史订单计入，
+    /// 查询失败时只记录日志，下次判断时重试
+    async fn record_earlier_fills(&mut self, symbol: &str, now: OffsetDateTime) {
+        let day_start = now.unix_timestamp() - now.unix_timestamp().rem_euclid(24 * 60 * 60);
+        let Some(since) = self.state.earliest_unrecorded_exit(symbol, day_start) else {
+            return;
+        };
+        let start = OffsetDateTime::from_unix_timestamp(since).ok();
+        match self.broker.get_history_orders(symbol, start, None).await {
+            Ok(orders) => {
+                self.state.record_fills(&orders);
+            }
+            Err(e) => warn!("{} 查询历史订单失败: {}", symbol, e),
+        }
+    }
+
+    /// 提交下单意图并记录订单，同一个幂等键已经提交过时不再下单，返回 `None`
+    async fn submit_intent(&mut self, intent: OrderIntent) -> Result<Option<SubmitOrderResponse>, AqtError> {
+        if self.state.has_intent(&intent.key) {
+            info!("{} 下单意图 {} 已经提交过，不再重复下单", intent.symbol, intent.key);
+            return Ok(None);
+        }
+        let resp = self.broker.submit_intent(&intent).await?;
+        let now = self.broker.now().unix_timestamp();
+        self.state.record_order(PlacedOrder::from_intent(resp.order_id.clone(), &intent, now));
+        Ok(Some(resp))
+    }
+
+    /// 根据已收盘的K线判断是否止盈以及技术指标是否给出买卖信号，并下单
+    ///
+    /// 查询持仓、资金、订单或下单失败时返回错误，本次判断直接放弃。
+    async fn evaluate(&mut self, symbol: &str, market_px: Decimal, sym: SymbolConfig, candles_list: Vec<Candle>) -> Result<(), AqtError> {
+        // 下单
+        // 获取用户的持仓
+        let positions = self.broker.stock_positions().await?;
+        let sym_position = Self::handler_positions(positions, symbol.to_string());
+        // 获取用户的订单
+        let orders = self
+            .broker
+            .get_today_orders(symbol)
+            .await?;
+        // 已结束的平仓订单按成交计入盈亏统计
+        self.state.record_fills(&orders);
+        let now = self.broker.now();
+        self.record_earlier_fills(symbol, now).await;
+        // 同步持仓和跟踪止损，供每笔行情检查平仓
+        self.state.exits.update_position(symbol, &sym_position, &orders, now);
+        self.state.exits.update_levels(&sym, &candles_list);
+        // 冷却时间已过仍未成交的订单先撤销，与是否给出开仓信号无关
+        self.cancel_own_stale_orders(symbol, &sym, &orders, now.unix_timestamp()).await;
+        // 同一根K线的同一个操作使用相同的幂等键，重复判断不会重复下单
+        let bar_ts = candles_list.last().map_or(0, |c| c.timestamp);
+
+        // TODO 判断是否达到收益预期 进行回撤、仓位判断 决定是否抛售
+        if Self::handler_close_position(sym.clone(), &candles_list, sym_position.clone()) {
+            info!("{:?}", market_px.clone());
+            let intent = OrderIntent::new(&self.name, symbol, bar_ts, "take_profit", OrderSide::Sell, market_px, sym_position.available_quantity);
+            let Some(resp) = self.submit_intent(intent).await? else {
+                return Ok(());
+            };
+            info!("{:?}", resp);
+            self.state.track_exit(&resp.order_id, sym_position.cost_price);
+            self.state.exits.record(symbol, ExitReason::TakeProfit, market_px, sym_position.quantity, now);
+            return Ok(());
+        }
+        // 空头持仓达到止盈条件时买入平仓
+        if Self::handler_cover_position(sym.clone(), &candles_list, sym_position.clone()) {
+            let intent = OrderIntent::new(&self.name, symbol, bar_ts, "cover_take_profit", OrderSide::Buy, market_px, sym_position.quantity.abs());
+            let Some(resp) = self.submit_intent(intent).await? else {
+                return Ok(());
+            };
+            info!("{} 空头平仓 {:?}", symbol, resp);
+            self.state.track_exit(&resp.order_id, sym_position.cost_price);
+            self.state.exits.record(symbol, ExitReason::TakeProfit, market_px, sym_position.quantity, now);
+            return Ok(());
+        }
+        // TODO 聚合技术判断
+        let profile = sym.indicator_profile(&self.profiles);
+        let temperature = if profile.needs_market() {
+            self.market
+                .get_market_temperature()
+                .await
+                .inspect_err(|e| warn!("获取市场温度失败: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        let state = self.indicators.entry(symbol.to_string()).or_default();
+        state.sync(&profile, &candles_list);
+        let held = if sym_position.is_long() {
+            1
+        } else if sym_position.is_short() {
+            -1
+        } else {
+            0
+        };
+        let decision = Self::handler_indicators(candles_list.clone(), sym.clone(), &profile, Some(state), temperature, held).await;
+        let inds = decision.side();
+        info!("对{}进行技术指标聚合判断:{}", symbol, decision.explain());
+        self.state.record_decision(symbol, &decision, now.unix_timestamp());
+        // 允许做空且没有多头持仓时，卖出信号用于开空；有空头持仓时，买入信号用于平空
+        let open_short = sym.allow_short && inds == OrderSide::Sell && !sym_position.is_long();
+        let cover_short = inds == OrderSide::Buy && sym_position.is_short();
+        // 高级别周期趋势与开仓方向不一致时不开仓
+        if (inds == OrderSide::Buy && !cover_short) || open_short {
+            if let Some(trend) = self.trend_decision(symbol, &sym, &candles_list).await? {
+                if trend.side() != inds {
+                    info!("{} 高级别周期趋势未确认，放弃{:?}:{}", symbol, inds, trend.explain());
                     return Ok(());
                 }
-                info!("获取用户的资金{:?}", balance);
-                // 循环balance获取美元金额
-                let mut usd_bal = Decimal::new(0, 3);
-                let mut total_cash = Decimal::new(0, 3);
-                for b in balance {
-                    for cash_info in b.cash_infos {
-                        if cash_info.currency == "USD" {
-                            usd_bal = cash_info.withdraw_cash;
-                            total_cash = cash_info.available_cash;
-                        }
+            }
+        }
+        if inds == OrderSide::Buy
+            && !cover_short
+            && !sym_position.cost_price.is_zero()
+            && sym_position.cost_price * decimal!(0.99) <= market_px.clone()
+        {
+            return Ok(());
+        }
+        if inds == OrderSide::Sell
+            && !open_short
+            && !sym_position.cost_price.is_zero()
+            && sym_position.cost_price >= market_px.clone() * decimal!(0.99)
+        {
+            return Ok(());
+        }
+        // 加空要求价格比空头成本高 1% 以上
+        if open_short
+            && sym_position.is_short()
+            && market_px <= sym_position.cost_price * decimal!(1.01)
+        {
+            return Ok(());
+        }
+        // TODO 指标指出可以买卖
+        if inds != OrderSide::Unknown {
+            // info!("获取用户的资金");
+            // 获取用户的资金
+            let balance = self.broker.account_balance().await?;
+            if balance.is_empty() {
+                return Ok(());
+            }
+            info!("获取用户的资金{:?}", balance);
+            // 循环balance获取美元金额
+            let mut usd_bal = Decimal::new(0, 3);
+            let mut total_cash = Decimal::new(0, 3);
+            for b in balance {
+                for cash_info in b.cash_infos {
+                    if cash_info.currency == "USD" {
+                        usd_bal = cash_info.withdraw_cash;
+                        total_cash = cash_info.available_cash;
                     }
                 }
+            }
 
-                // 获取用户的订单
-                let orders = self
-                    .service
-                    .get_today_orders(event.symbol.clone().as_str())
-                    .await;
-                let mut quantity = decimal!(0.0);
-                // 根据总资产进行下单
-                if usd_bal > decimal!(0.0) && inds == OrderSide::Buy {
-                    let volume = sym.volume;
-                    let cash = total_cash.checked_mul(decimal!(volume)).unwrap();
-                    if usd_bal >= cash * decimal!(1.05) {
-                        quantity = (cash / decimal!(market_px.clone())).ceil();
-                    }
-                }
-                if !sym_position.available_quantity.is_zero() && inds == OrderSide::Sell {
-                    quantity = sym_position.available_quantity;
+            let mut quantity = decimal!(0.0);
+            // 按股票配置的仓位模型计算开仓数量
+            let stats = self.state.trades.get(symbol).cloned().unwrap_or_default();
+            let planned = sym.sizer.build(sym.volume).quantity(&SizingContext {
+                capital: total_cash,
+                price: market_px,
+                candles: &candles_list,
+                stats: &stats,
+            });
+            if usd_bal > decimal!(0.0)
+                && inds == OrderSide::Buy
+                && !cover_short
+                && usd_bal >= planned * market_px * BUY_BUFFER
+            {
+                quantity = planned;
+            }
+            // 买入平空
+            if cover_short {
+                quantity = sym_position.quantity.abs();
+            }
+            if sym_position.available_quantity > decimal!(0.0) && inds == OrderSide::Sell {
+                quantity = sym_position.available_quantity;
+            }
+            if open_short && usd_bal > decimal!(0.0) {
+                quantity = planned;
+                // 检查保证金和可卖空数量，不足时按可卖空数量下单
+                let max_quantity = self
+                    .broker
+                    .estimate_max_quantity(symbol, OrderSide::Sell, market_px)
+                    .await?
+                    .floor();
+                if max_quantity < quantity {
+                    info!("{} 可卖空数量 {} 小于计划数量 {}", symbol, max_quantity, quantity);
+                    quantity = max_quantity.max(decimal!(0.0));
                 }
+            }
 
-                // 数量为0直接返回
-                if quantity.is_zero() {
-                    return Ok(());
-                }
+            // 数量为0直接返回
+            if quantity.is_zero() {
+                return Ok(());
+            }
 
-                // 获取订单状态，是否可以下单
-                let order_status =
-                    VecorStrategy::handler_orders(&self.service, orders, event.symbol.clone())
-                        .await;
-                if order_status {
-                    let resp = self
-                        .service
-                        .submit_order(event.symbol.clone(), inds, market_px.clone(), quantity)
-                        .await;
-                    info!("{:?}", resp);
+            // 获取订单状态，是否可以下单
+            if !Self::handler_orders(&orders, symbol, now.unix_timestamp(), sym.cooldown_secs) {
+                return Ok(());
+            }
+            let action = if cover_short {
+                "cover"
+            } else if open_short {
+                "short"
+            } else if inds == OrderSide::Sell {
+                "sell"
+            } else {
+                "buy"
+            };
+            let intent = OrderIntent::new(&self.name, symbol, bar_ts, action, inds, market_px, quantity);
+            if let Some(resp) = self.submit_intent(intent).await? {
+                info!("{:?}", resp);
+                info!("{} 下单依据:\n{}", symbol, decision.explain());
+                if cover_short || (inds == OrderSide::Sell && !open_short) {
+                    self.state.track_exit(&resp.order_id, sym_position.cost_price);
+                } else {
+                    self.entry_syncs.insert(symbol.to_string(), 0);
                 }
             }
         }
         Ok(())
     }
 
-    /// 停止策略执行
-    fn stop(&mut self) -> Result<(), Box<dyn Error>> {
-        warn!("vecor v1 策略程序停止");
-        Ok(())
-    }
-}
-
-// 增加额外的函数
-impl VecorStrategy {
     pub fn get_sym_info(sym_config: Vec<SymbolConfig>, symbol: String) -> SymbolConfig {
         let mut sym = SymbolConfig::new();
         for cfg in sym_config.iter() {
             if cfg.symbol == symbol {
-                sym = SymbolConfig {
-                    symbol: cfg.symbol.clone(),
-                    symbol_type: cfg.symbol_type.clone(),
-                    volume: cfg.volume.clone(),
-                    period: cfg.period.clone(),
-                    tp_ratio: cfg.tp_ratio.clone(),
-                    sl_ratio: cfg.sl_ratio.clone(),
-                };
+                sym = cfg.clone();
             }
         }
         sym
@@ -255,143 +587,172 @@ impl VecorStrategy {
         (index, sym)
     }
 
-    pub fn handle_candles(symbol: String, candles: Vec<Candlestick>) -> Vec<Candle> {
-        let candles = candles.clone();
-        let cs = candles
+    /// handler_orders 处理订单
+    /// - 判断是不是在 `cooldown_secs` 冷却时间内下过单，被拒绝的订单不计入
+    /// - 判断是否还有未完成的订单
+    pub fn handler_orders(orders: &[Order], symbol: &str, now_ts: i64, cooldown_secs: i64) -> bool {
+        orders
             .iter()
-            .map(|c| Candle {
-                symbol: Option::from(symbol.clone()),
-                timestamp: c.timestamp.to_utc().unix_timestamp() as u64,
-                open: f64::try_from(c.open).unwrap(),
-                high: f64::try_from(c.high).unwrap(),
-                low: f64::try_from(c.low).unwrap(),
-                close: f64::try_from(c.close).unwrap(),
-                volume: c.volume as f64,
-            })
-            .collect::<Vec<_>>();
-        cs
+            .filter(|o| o.symbol == symbol && o.status != OrderStatus::Rejected)
+            .all(|o| o.submitted_at.unix_timestamp() <= now_ts - cooldown_secs && !o.is_open())
     }
 
-    /// handler_orders 处理订单
-    /// - 判断是不是2个小时内下过单
-    /// - 判断订单状态是否合适继续下单
-    pub async fn handler_orders(service: &Service, orders: Vec<Order>, symbol: String) -> bool {
-        // let ts = 1_000;
-        let h2ts = 10 * 60 * 60;
-        let now_ts = OffsetDateTime::now_utc().unix_timestamp();
-        // 判断是不是2个小时内下过单
-        for o in orders {
-            if o.symbol == symbol {
-                // 检查订单提交时间是否在最近两小时内
-                // let used_time = (now_ts - o.submitted_at.to_utc().clone().unix_timestamp())/3600;
-                // info!("used_time   {:?}",used_time);
-                // info!("submitted_at   {:?}",o.submitted_at.to_utc().clone().unix_timestamp());
-                // info!("OffsetDateTime {:?}",now_ts.clone());
-                if o.submitted_at.to_utc().unix_timestamp() > (now_ts - h2ts) {
-                    return false; // 若在两小时内返回false，避免频繁下单
-                }
-
-                // 判断订单状态是否为新订单、等待提交或部分成交
-                if o.status == OrderStatus::New
-                    || o.status == OrderStatus::WaitToNew
-                    || o.status == OrderStatus::PartialFilled
-                    || o.status == OrderStatus::NotReported
-                {
-                    // 取消订单
-                    let _ = service.cancel_order(o.order_id).await;
-                    return false; // 如果满足条件则返回false，防止重复操作
-                }
+    /// 撤销冷却时间之前提交、仍未完成的订单，撤单失败只记录日志
+    pub async fn cancel_stale_orders(broker: &B, orders: &[Order], symbol: &str, now_ts: i64, cooldown_secs: i64) {
+        let stale = orders
+            .iter()
+            .filter(|o| o.symbol == symbol && o.is_open())
+            .filter(|o| o.submitted_at.unix_timestamp() <= now_ts - cooldown_secs);
+        for o in stale {
+            match broker.cancel_order(o.order_id.clone()).await {
+                Ok(()) => info!("{} 撤销未成交订单 {}", symbol, o.order_id),
+                Err(e) => warn!("取消订单失败: {}", e),
             }
         }
-        true
     }
+
+    /// 撤销本策略提交的过期订单，不影响手动下单或其他策略的订单
+    async fn cancel_own_stale_orders(&self, symbol: &str, sym: &SymbolConfig, orders: &[Order], now_ts: i64) {
+        let own: Vec<Order> = orders
+            .iter()
+            .filter(|o| self.state.orders.iter().any(|p| p.order_id == o.order_id))
+            .cloned()
+            .collect();
+        Self::cancel_stale_orders(self.broker.as_ref(), &own, symbol, now_ts, sym.cooldown_secs).await;
+    }
+
     pub fn handler_positions(
-        positions: Vec<StockPositionChannel>,
+        positions: Vec<Position>,
         symbol: String,
-    ) -> StockPosition {
-        let mut sym_position = StockPosition {
-            symbol: "".to_string(),
-            symbol_name: "".to_string(),
-            quantity: Default::default(),
-            available_quantity: Default::default(),
-            currency: "".to_string(),
-            cost_price: Default::default(),
-            market: Default::default(),
-            init_quantity: None,
+    ) -> Position {
+        let mut sym_position = Position::default();
+
+        // 持仓是否存在
+        for p in positions {
+            if p.symbol == symbol {
+                sym_position = p;
+            }
+        }
+        sym_position
+    }
+
+    /// 行情触发平仓规则时立即提交平仓订单，提交失败时下一笔行情重新检查
+    async fn check_exit(&mut self, event: &MarketData) -> Result<(), AqtError> {
+        let Some(sym) = self.sym_config.iter().find(|s| s.symbol == event.symbol).cloned() else {
+            return Ok(());
+        };
+        let now = self.broker.now();
+        // 开仓订单成交后不必等到下一根K线收盘才受保护
+        self.sync_entry(&sym, now).await;
+        let signal = self.state.exits.check(&sym, event.price, now);
+        // 移到保本止损后立即保存，重启后保持
+        if self.state.exits.take_changed() {
+            self.save_state();
+        }
+        let Some(signal) = signal else {
+            return Ok(());
         };
+        // 同一根K线内同一原因的平仓只提交一次；还没有收盘K线时按行情时间
+        let bar_ts = self
+            .state
+            .last_closed_bar(&event.symbol)
+            .unwrap_or(event.ts.unix_timestamp() as u64);
+        let action = format!("exit_{}", signal.reason.code());
+        let intent = OrderIntent::new(&self.name, &event.symbol, bar_ts, &action, signal.side, event.price, signal.quantity);
+        let resp = self
+            .submit_intent(intent)
+            .await
+            .inspect_err(|_| self.state.exits.reset_pending(&event.symbol))?;
+        let Some(resp) = resp else {
+            return Ok(());
+        };
+        info!("{} {}平仓 {:?}", event.symbol, signal.reason, resp);
+        self.state.exits.record(&event.symbol, signal.reason, event.price, signal.held, now);
+        self.state.track_exit(&resp.order_id, signal.cost);
+        self.save_state();
+        Ok(())
+    }
 
-        if !positions.is_empty() {
-            // 持仓是否存在
-            for pchannel in positions {
-                for p in pchannel.positions {
-                    if p.symbol == symbol {
-                        sym_position = p;
-                    }
+    /// 高级别周期的趋势判断，未配置趋势过滤时返回 `None`
+    ///
+    /// 由入场周期K线聚合时，`candles` 聚合出的K线不够指标预热则补取更早的入场周期K线；
+    /// 高级别K线仍然不足时跳过趋势过滤并返回 `None`，而不是用无效的投票阻止开仓。
+    pub async fn trend_decision(&mut self, symbol: &str, sym: &SymbolConfig, candles: &[Candle]) -> Result<Option<Decision>, AqtError> {
+        let Some(trend) = &sym.trend else {
+            return Ok(None);
+        };
+        let profile = trend.profile();
+        let warmup = profile.warmup();
+        let trend_candles = if trend.aggregate {
+            let mut aggregated = aggregate_candles(candles, &trend.period);
+            if let (true, Some(secs), Some(last)) = (aggregated.len() < warmup, period_seconds(&trend.period), candles.last()) {
+                let since = last.timestamp.saturating_sub((warmup as u64 + 1) * secs);
+                let history = self
+                    .market
+                    .get_candlesticks_since(symbol.to_string(), sym.period.clone(), Some(since))
+                    .await?;
+                if history.len() > candles.len() {
+                    aggregated = aggregate_candles(&history, &trend.period);
                 }
             }
+            aggregated
+        } else {
+            self.market
+                .get_candlesticks(symbol.to_string(), trend.period.clone())
+                .await?
+        };
+        if trend_candles.len() < warmup {
+            warn!(
+                "{} {} 周期K线只有 {} 根，不足指标预热所需的 {} 根，跳过趋势过滤",
+                symbol,
+                trend.period,
+                trend_candles.len(),
+                warmup
+            );
+            return Ok(None);
         }
-        sym_position
+        let state = self.indicators.entry(format!("{}@{}", symbol, trend.period)).or_default();
+        state.sync(&profile, &trend_candles);
+        // 趋势只用于确认开仓方向
+        let decision = Self::handler_indicators(trend_candles, sym.clone(), &profile, Some(state), None, 0).await;
+        Ok(Some(decision))
     }
 
-    pub async fn handler_indicators(candles: Vec<Candle>, symbol: SymbolConfig) -> OrderSide {
+    pub async fn handler_indicators(
+        candles: Vec<Candle>,
+        symbol: SymbolConfig,
+        profile: &IndicatorProfile,
+        state: Option<&IndicatorState>,
+        temperature: Option<MarketTemperature>,
+        held: i64,
+    ) -> Decision {
         // 首先处理异步调用，避免在同步代码中混合异步调用
-        let mut sym_str = symbol.symbol;
-        sym_str = sym_str.replace(".US", "");
-        sym_str = format!("{}:{}", symbol.symbol_type, sym_str);
-        let technicals = TradingTechnicals::new(sym_str.as_str()).await;
-
-        let defult_rules = DefultRules {};
-        let rules = defult_rules.create();
-        let mut calculate = Calculate::new(Box::new(rules));
-        let kdj = Box::new(KdjCalculate {
-            candles: candles.clone(),
-        });
-        let macd = Box::new(MacdCalculate {
-            candles: candles.clone(),
-        });
-        let ut_bot = Box::new(UTBotCalculate {
-            candles: candles.clone(),
-        });
-        let stc = Box::new(STCCalculate {
-            candles: candles.clone(),
-        });
-        let cyc = Box::new(CycCalculate {
-            candles: candles.clone(),
-        });
-        let techs = Box::new(TechnicalsCalculate {
-            technicals: technicals.clone(),
-        });
-
-        calculate.add_calculator(kdj);
-        calculate.add_calculator(macd);
-        calculate.add_calculator(stc);
-        calculate.add_calculator(ut_bot);
-        calculate.add_calculator(cyc);
-        calculate.add_calculator(techs);
-
-        let res = calculate.execute_rules();
-        if res > 0 {
-            return OrderSide::Buy;
-        }
-        if res < 0 {
-            return OrderSide::Sell;
-        }
-        OrderSide::Unknown
+        let technicals = if profile.needs_technicals() {
+            let mut sym_str = symbol.symbol;
+            sym_str = sym_str.replace(".US", "");
+            sym_str = format!("{}:{}", symbol.symbol_type, sym_str);
+            Some(TradingTechnicals::new(sym_str.as_str()).await)
+        } else {
+            None
+        };
+
+        let calculate = profile.build_with_state(state, &candles, technicals, temperature);
+        calculate.execute_rules_holding(held)
     }
 
     // 持仓是否达到止盈条件
     pub fn handler_close_position(
         sym: SymbolConfig,
-        candle: Vec<Candlestick>,
-        stock: StockPosition,
+        candle: &[Candle],
+        stock: Position,
     ) -> bool {
-        // 检查蜡烛图数据是否足够且有持仓
-        if candle.len() < 3 || stock.available_quantity.is_zero() {
+        // 检查蜡烛图数据是否足够且有多头持仓
+        if candle.len() < 3 || stock.available_quantity <= decimal!(0.0) {
             return false;
         }
 
         // 获取当前价格和持仓成本价
-        let cur_price = candle.last().unwrap().close;
+        let cur_price = decimal!(candle.last().unwrap().close);
         let cost_price = stock.cost_price;
 
         // 计算止盈价格（基于配置的止盈比例）
@@ -399,7 +760,7 @@ impl VecorStrategy {
 
         // 如果当前价格高于止盈价格，并且前一个价格出现回落，则触发止盈条件
         if tp_ratio * cost_price < cur_price {
-            let prev_price = candle.get(candle.len() - 2).unwrap().close;
+            let prev_price = decimal!(candle.get(candle.len() - 2).unwrap().close);
 
             // 当前价格较前一个价格下跌超过0.1%，认为开始回撤，满足卖出条件
             if (prev_price - cur_price) / prev_price > decimal!(0.001) {
@@ -408,6 +769,31 @@ impl VecorStrategy {
         }
         false
     }
+
+    // 空头持仓是否达到止盈条件，止损由平仓管理在每笔行情上检查
+    pub fn handler_cover_position(
+        sym: SymbolConfig,
+        candle: &[Candle],
+        stock: Position,
+    ) -> bool {
+        // 检查蜡烛图数据是否足够且有空头持仓
+        if candle.len() < 3 || !stock.is_short() {
+            return false;
+        }
+
+        let cur_price = decimal!(candle.last().unwrap().close);
+        let cost_price = stock.cost_price;
+
+        // 价格低于止盈价格，并且开始反弹超过0.1%，止盈平仓
+        let tp_ratio = decimal!(1) - decimal!(sym.tp_ratio) * decimal!(0.01);
+        if cur_price < cost_price * tp_ratio {
+            let prev_price = decimal!(candle.get(candle.len() - 2).unwrap().close);
+            if (cur_price - prev_price) / prev_price > decimal!(0.001) {
+                return true;
+            }
+        }
+        false
+    }
 }
 // 1752711471
 // 1752768900
\ No newline at end of file
@@ -1 +1,2 @@
-pub mod sty;
\ No newline at end of file
+pub mod sty;
+pub mod replay;
@@ -0,0 +1,61 @@
+use std::path::Path;
+use std::sync::Arc;
+use log::info;
+use longport::{Config, QuoteContext, TradeContext};
+use tokio::sync::mpsc;
+use crate::collect::replayer::{Pacing, QuoteReplayer};
+use crate::config::config::{Configs, PaperConfig};
+use crate::services::paper_broker::PaperBroker;
+use crate::services::pricing::Pricer;
+use crate::services::service::Service;
+use crate::store::cached_market::CachedMarket;
+use crate::store::candle_store::CandleStore;
+use crate::tasks::sty::start_strategies;
+
+/// 回放录制的行情驱动策略。
+///
+/// 为避免回放时误下真实订单，始终通过 `PaperBroker` 模拟成交；
+/// 未配置 `paper` 时使用 100000 USD 的默认初始资金。回放不读写策略状态文件，也不启用熔断。
+pub async fn start_replay(mut config: Configs, path: &Path, pacing: Pacing) -> Result<(), Box<dyn std::error::Error>> {
+    config.state = None;
+    let replayer = QuoteReplayer::load(path)?;
+    info!("加载录制文件 {}，行情 {} 条", path.display(), replayer.quotes().count());
+
+    // K线仍通过长桥获取
+    let cfg = Arc::new(Config::from_env()?);
+    let (quote_ctx, _) = QuoteContext::try_new(cfg.clone()).await?;
+    let (trade_ctx, _) = TradeContext::try_new(cfg).await?;
+    let service = Arc::new(Service::new(Arc::new(quote_ctx), Arc::new(trade_ctx)));
+    let market = Arc::new(CachedMarket::new(service, CandleStore::new(&config.store.dir)));
+
+    let paper_cfg = config.paper.clone().unwrap_or(PaperConfig {
+        initial_cash: 100000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    });
+    // 回放没有盘口数据，按盘口定价的股票按现价上下浮动 5% 委托
+    let paper = Arc::new(PaperBroker::new(&paper_cfg).with_pricer(Pricer::from_config(&config)));
+    let (sender, mut receiver) = mpsc::channel(1024);
+    let (paper_sender, paper_receiver) = mpsc::channel(1024);
+    let mut executor = start_strategies(&config, paper.clone(), market, None)?;
+    let forward_paper = paper.clone();
+    tokio::spawn(async move {
+        while let Some(data) = receiver.recv().await {
+            forward_paper.on_market_data(&data);
+            if paper_sender.send(data).await.is_err() {
+                break;
+            }
+        }
+    });
+    let executor_handle = tokio::spawn(async move {
+        executor.run(paper_receiver, None).await;
+    });
+
+    let sent = replayer.replay(&sender, pacing).await;
+    // 关闭通道，执行器处理完剩余行情后退出
+    drop(sender);
+    executor_handle.await?;
+    info!("回放完成，共 {} 条行情，模拟成交 {} 笔，净值 {}", sent, paper.fills().len(), paper.equity());
+    Ok(())
+}
@@ -1,11 +1,154 @@
+use std::path::Path;
 use std::sync::Arc;
-use log::error;
+use std::time::Duration;
+use log::{info, warn};
 use longport::{Config, QuoteContext, TradeContext};
+use time::OffsetDateTime;
 use tokio::sync::mpsc;
+use crate::collect::candlestick::CandlestickCollectors;
+use crate::collect::depth::DepthCollectors;
 use crate::collect::quote::QuoteCollectors;
+use crate::collect::recorder::QuoteRecorder;
 use crate::config::config::Configs;
-use crate::strategys::executor::Executor;
-use crate::strategys::vecor_v1::VecorStrategy;
+use crate::services::broker::Broker;
+use crate::services::paper_broker::PaperBroker;
+use crate::services::pricing::{DepthBook, Pricer};
+use crate::services::circuit_breaker::{CircuitBreaker, CircuitBreakerBroker};
+use crate::services::risk::RiskCheckedBroker;
+use crate::services::service::Service;
+use crate::store::cached_market::CachedMarket;
+use crate::store::candle_store::CandleStore;
+use crate::strategys::multi_executor::{ExecutorHandle, MultiExecutor};
+use crate::strategys::registry::StrategyRegistry;
+use crate::utils::error::AqtError;
+
+/// 重新读取配置文件的间隔
+const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 按配置从注册表创建每个策略并启动，每个策略只接收自己负责的股票
+///
+/// 运行期间定期重新读取配置文件，按 `enabled` 单独启动或停止策略，见 [`watch_strategies`]。
+/// 配置了 `risk` 时，所有策略的订单先经过风控再提交给券商，配置了相关系数上限时在后台定期更新收益率，
+/// 配置了当日亏损上限时在交易日开始时记录净资产基准（配置了 `state` 时保存到状态目录）；
+/// 传入 `breaker` 时订单还要经过熔断器，并在后台定期检查当日净资产回撤。
+/// 任一策略创建失败（如策略类型未注册、参数错误）时返回错误，不启动任何策略。
+pub(crate) fn start_strategies<B: Broker + 'static>(
+    config: &Configs,
+    broker: Arc<B>,
+    market: Arc<CachedMarket<Service>>,
+    breaker: Option<Arc<CircuitBreaker>>,
+) -> Result<MultiExecutor, AqtError> {
+    match &config.risk {
+        Some(limits) => {
+            info!("启用下单前风控 {:?}", limits);
+            let mut risk = RiskCheckedBroker::new(broker, limits.clone()).with_groups(config.symbol_groups());
+            if let Some(state) = &config.state {
+                risk = risk.with_state_file(Path::new(&state.dir).join("risk_daily_loss.json"));
+            }
+            let broker = Arc::new(risk);
+            if limits.max_daily_loss.is_some() {
+                let risk = broker.clone();
+                tokio::spawn(async move {
+                    risk.monitor_daily_loss().await;
+                });
+            }
+            if limits.max_correlation.is_some() {
+                let (risk, market) = (broker.clone(), market.clone());
+                let symbols = config.symbol_groups().into_keys().collect();
+                tokio::spawn(async move {
+                    risk.monitor_correlation(market, symbols).await;
+                });
+            }
+            with_breaker(config, broker, market, breaker)
+        }
+        None => with_breaker(config, broker, market, breaker),
+    }
+}
+
+fn with_breaker<B: Broker + 'static>(
+    config: &Configs,
+    broker: Arc<B>,
+    market: Arc<CachedMarket<Service>>,
+    breaker: Option<Arc<CircuitBreaker>>,
+) -> Result<MultiExecutor, AqtError> {
+    let Some(breaker) = breaker else {
+        return build_strategies(config, broker, market);
+    };
+    info!("启用熔断 {:?}", breaker.config());
+    let broker = Arc::new(CircuitBreakerBroker::new(broker, breaker));
+    let executor = build_strategies(config, broker.clone(), market)?;
+    tokio::spawn(async move {
+        broker.monitor().await;
+    });
+    Ok(executor)
+}
+
+fn build_strategies<B: Broker + 'static>(
+    config: &Configs,
+    broker: Arc<B>,
+    market: Arc<CachedMarket<Service>>,
+) -> Result<MultiExecutor, AqtError> {
+    let mut registry = StrategyRegistry::new();
+    if let Some(state) = &config.state {
+        info!("策略状态保存到 {}", state.dir);
+        registry = registry.with_state_dir(&state.dir);
+    }
+    let mut strategies = Vec::new();
+    for strategy in config.strategy_configs() {
+        let built = registry.build(&strategy, broker.clone(), market.clone(), &config.profiles)?;
+        info!("策略 {} 使用 {}", strategy.name, strategy.strategy);
+        strategies.push((strategy, built));
+    }
+    let mut executor = MultiExecutor::default();
+    for (strategy, built) in strategies {
+        let symbols = strategy.symbols.iter().map(|s| s.symbol.clone()).collect();
+        executor.start(&strategy.name, symbols, built);
+    }
+    let handle = executor.handle();
+    tokio::spawn(async move {
+        watch_strategies(handle, registry, broker, market).await;
+    });
+    Ok(executor)
+}
+
+/// 定期重新读取配置文件，启动新启用的策略、停止被禁用的策略，其他策略不受影响。
+///
+/// 只能使用启动时已订阅行情的股票，新增股票需要重启进程；出错退出的策略在下次检查时重新创建。
+async fn watch_strategies<B: Broker + 'static>(
+    handle: ExecutorHandle,
+    registry: StrategyRegistry<B, CachedMarket<Service>>,
+    broker: Arc<B>,
+    market: Arc<CachedMarket<Service>>,
+) {
+    let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+    interval.tick().await;
+    while !handle.is_closed() {
+        interval.tick().await;
+        let config = match Configs::load() {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("重新读取配置失败，策略保持不变: {}", e);
+                continue;
+            }
+        };
+        let running = handle.names().await;
+        for strategy in &config.strategies {
+            match (strategy.enabled, running.contains(&strategy.name)) {
+                (true, false) => match registry.build(strategy, broker.clone(), market.clone(), &config.profiles) {
+                    Ok(built) => {
+                        let symbols = strategy.symbols.iter().map(|s| s.symbol.clone()).collect();
+                        handle.start(&strategy.name, symbols, built);
+                    }
+                    Err(e) => warn!("创建策略 {} 失败: {}", strategy.name, e),
+                },
+                (false, true) => {
+                    handle.stop(&strategy.name);
+                }
+                _ => {}
+            }
+        }
+    }
+}
 
 pub async fn start_sty(config: Configs) -> Result<(), Box<dyn std::error::Error>> {
     // 初始化长桥配置
@@ -14,27 +157,84 @@ pub async fn start_sty(config: Configs) -> Result<(), Box<dyn std::error::Error>
     // 创建 QuoteContext 和 TradeContext 实例
     let (quote_ctx, _) = QuoteContext::try_new(cfg.clone()).await.unwrap();
     let (trade_ctx, _) = TradeContext::try_new(cfg).await.unwrap();
+    // 所有策略共用同一份行情订阅
+    let subscriptions = config.subscriptions();
     let mut symbols = Vec::new();
-    for symbol in config.symbols {
-        symbols.push(symbol.symbol.clone());
+    for (symbol, _) in &subscriptions {
+        if !symbols.contains(symbol) {
+            symbols.push(symbol.clone());
+        }
     }
     let (sender, receiver) = mpsc::channel(1024);
+    // K线推送：策略在K线收盘时进行判断
+    let (bar_sender, bar_receiver) = mpsc::channel(1024);
 
-    // 创建执行器
-    let mut executor = Executor::<VecorStrategy>::new(
-        Arc::new(quote_ctx),
-        Arc::new(trade_ctx),
-        receiver,
-    );
-    // 在单独的任务中运行执行器
-    let executor_handle = tokio::spawn(async move {
-        // 循环执行
-        if let Err(e) = executor.run().await {
-            error!("Executor error: {}", e);
+    // 按盘口定价的股票订阅深度数据
+    let mut pricer = Pricer::from_config(&config);
+    if pricer.needs_depth() {
+        let book = Arc::new(DepthBook::new());
+        let mut depth_collector = DepthCollectors::new(pricer.depth_symbols()).await;
+        depth_collector.set_book(book.clone());
+        tokio::spawn(async move {
+            depth_collector.subscribe().await;
+        });
+        pricer = pricer.with_book(book);
+    }
+    // 长桥服务同时作为券商和行情数据来源
+    let service = Arc::new(Service::new(Arc::new(quote_ctx), Arc::new(trade_ctx)).with_pricer(pricer.clone()));
+    // K线先增量同步到本地存储，再提供给策略
+    let market = Arc::new(CachedMarket::new(service.clone(), CandleStore::new(&config.store.dir)));
+
+    // 熔断器由所有策略共用，并需要最新行情估算持仓市值
+    let breaker = config.breaker.clone().map(|cfg| Arc::new(CircuitBreaker::new(cfg)));
+    let (paper, mut executor) = match &config.paper {
+        Some(paper_cfg) => {
+            // 模拟盘：行情先交给 PaperBroker 撮合，再转发给策略
+            info!("以模拟盘模式启动，初始资金 {}", paper_cfg.initial_cash);
+            let paper = Arc::new(PaperBroker::new(paper_cfg).with_pricer(pricer));
+            let executor = start_strategies(&config, paper.clone(), market, breaker.clone())?;
+            (Some(paper), executor)
         }
+        // 创建执行器，每个策略在单独的任务中运行
+        None => (None, start_strategies(&config, service, market, breaker.clone())?),
+    };
+    let executor_handle = if paper.is_none() && breaker.is_none() {
+        tokio::spawn(async move {
+            // 循环路由行情
+            executor.run(receiver, Some(bar_receiver)).await;
+        })
+    } else {
+        let (forward_sender, forward_receiver) = mpsc::channel(1024);
+        tokio::spawn(async move {
+            let mut receiver = receiver;
+            while let Some(data) = receiver.recv().await {
+                if let Some(paper) = &paper {
+                    paper.on_market_data(&data);
+                }
+                if let Some(breaker) = &breaker {
+                    breaker.observe(&data);
+                }
+                if forward_sender.send(data).await.is_err() {
+                    break;
+                }
+            }
+        });
+        tokio::spawn(async move {
+            executor.run(forward_receiver, Some(bar_receiver)).await;
+        })
+    };
+    // 订阅K线推送
+    let mut bar_collector = CandlestickCollectors::new(subscriptions).await;
+    tokio::spawn(async move {
+        bar_collector.subscribe(bar_sender).await;
     });
     // 异步执行收集器
     let mut collector = QuoteCollectors::new(symbols).await;
+    if let Some(rec_cfg) = config.recorder {
+        let path = Path::new(&rec_cfg.dir).join(format!("quotes-{}.jsonl", OffsetDateTime::now_utc().date()));
+        info!("行情录制到 {}", path.display());
+        collector.set_recorder(Arc::new(QuoteRecorder::new(&path, rec_cfg.raw)?));
+    }
     collector.subscribe(sender).await;
     // 等待执行器完成
     executor_handle.await?;
@@ -0,0 +1,48 @@
+use thiserror::Error;
+use crate::services::risk::RiskReason;
+
+/// 长桥接口触发限流时返回的错误码
+const RATE_LIMIT_CODES: [i64; 2] = [429, 429002];
+
+/// 系统统一的错误类型，调用方可以据此决定重试、跳过还是停止
+#[derive(Debug, Error)]
+pub enum AqtError {
+    /// 配置缺失或格式错误
+    #[error("配置错误: {0}")]
+    Config(String),
+    /// 网络或连接失败，通常可以重试
+    #[error("网络错误: {0}")]
+    Network(String),
+    /// 接口明确拒绝了请求，例如资金不足、订单不存在
+    #[error("接口拒绝请求({code}): {message}")]
+    ApiRejected { code: i64, message: String },
+    /// 请求过于频繁，稍后可以重试
+    #[error("请求被限流: {0}")]
+    RateLimited(String),
+    /// 返回的数据无法解析或不完整
+    #[error("数据错误: {0}")]
+    Data(String),
+    /// 被本地风控拦截
+    #[error("风控拒绝: {0}")]
+    RiskRejected(RiskReason),
+}
+
+impl AqtError {
+    /// 是否为可以稍后重试的错误
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AqtError::Network(_) | AqtError::RateLimited(_))
+    }
+}
+
+impl From<longport::Error> for AqtError {
+    fn from(e: longport::Error) -> Self {
+        let e = e.into_simple_error();
+        let message = e.message().to_string();
+        match e.code() {
+            Some(code) if RATE_LIMIT_CODES.contains(&code) => AqtError::RateLimited(message),
+            Some(code) => AqtError::ApiRejected { code, message },
+            // 没有业务错误码说明请求没有得到服务端的响应
+            None => AqtError::Network(message),
+        }
+    }
+}
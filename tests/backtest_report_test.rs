@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use aqt_stock::backtest::engine::{BacktestResult, EquityPoint};
+use aqt_stock::backtest::report::BacktestReport;
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::services::paper_broker::Fill;
+use longport::decimal;
+use longport::trade::OrderSide;
+use time::OffsetDateTime;
+
+fn fill(side: OrderSide, price: f64, quantity: f64, ts: i64) -> Fill {
+    Fill {
+        order_id: "1".to_string(),
+        symbol: "TEST.US".to_string(),
+        side,
+        price: decimal!(price),
+        quantity: decimal!(quantity),
+        ts: OffsetDateTime::from_unix_timestamp(ts).unwrap(),
+    }
+}
+
+#[test]
+fn report_metrics() {
+    let day = 86400;
+    let equity = [100.0, 120.0, 90.0, 110.0, 130.0];
+    let candles = equity
+        .iter()
+        .enumerate()
+        .map(|(i, e)| Candle { timestamp: i as u64 * day, close: *e, ..Default::default() })
+        .collect();
+    let result = BacktestResult {
+        initial_cash: 100.0,
+        equity_curve: equity
+            .iter()
+            .enumerate()
+            .map(|(i, e)| EquityPoint { ts: i as u64 * day, equity: *e })
+            .collect(),
+        fills: vec![
+            fill(OrderSide::Buy, 10.0, 2.0, 0),
+            fill(OrderSide::Sell, 12.0, 1.0, day as i64),
+            fill(OrderSide::Sell, 9.0, 1.0, 2 * day as i64),
+        ],
+        candles: HashMap::from([("TEST.US".to_string(), candles)]),
+    };
+
+    let report = BacktestReport::from_result(&result);
+    assert!((report.total_return - 0.3).abs() < 1e-9);
+    assert!((report.max_drawdown - 0.25).abs() < 1e-9);
+    assert_eq!(report.max_drawdown_duration, 2 * day);
+    assert_eq!(report.trades, 2);
+    assert!((report.win_rate - 0.5).abs() < 1e-9);
+    assert!((report.profit_factor - 2.0).abs() < 1e-9);
+    assert!((report.exposure - 0.5).abs() < 1e-9);
+    assert!((report.symbols[0].benchmark_return - 0.3).abs() < 1e-9);
+}
+
+#[test]
+fn short_round_trips_are_paired() {
+    let day = 86400;
+    let result = BacktestResult {
+        initial_cash: 100.0,
+        equity_curve: (0..4).map(|i| EquityPoint { ts: i * day, equity: 100.0 }).collect(),
+        fills: vec![
+            // 空仓时卖出开空，买入平空
+            fill(OrderSide::Sell, 10.0, 2.0, 0),
+            fill(OrderSide::Buy, 8.0, 2.0, day as i64),
+            // 开空后买入超过空头数量：平空后剩余部分开多
+            fill(OrderSide::Sell, 10.0, 1.0, 2 * day as i64),
+            fill(OrderSide::Buy, 11.0, 3.0, 2 * day as i64),
+            fill(OrderSide::Sell, 12.0, 2.0, 3 * day as i64),
+        ],
+        candles: HashMap::new(),
+    };
+
+    let report = BacktestReport::from_result(&result);
+    let trades = &report.trade_list;
+    assert_eq!(trades.len(), 3);
+    assert!(trades[0].short);
+    assert_eq!((trades[0].entry_price, trades[0].exit_price, trades[0].quantity), (10.0, 8.0, 2.0));
+    assert!((trades[0].pnl - 4.0).abs() < 1e-9);
+    assert!((trades[0].return_pct - 0.2).abs() < 1e-9);
+    assert!(trades[1].short);
+    assert!((trades[1].pnl + 1.0).abs() < 1e-9);
+    assert!(!trades[2].short);
+    assert_eq!((trades[2].entry_price, trades[2].exit_price, trades[2].quantity), (11.0, 12.0, 2.0));
+    assert!((trades[2].pnl - 2.0).abs() < 1e-9);
+    // 空头持仓的区间同样计入持仓时间
+    assert!((report.exposure - 2.0 / 3.0).abs() < 1e-9);
+}
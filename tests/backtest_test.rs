@@ -0,0 +1,80 @@
+use std::fs;
+use std::sync::Arc;
+use aqt_stock::backtest::engine::Backtest;
+use aqt_stock::backtest::market::BacktestMarket;
+use aqt_stock::config::config::{BacktestConfig, SymbolConfig};
+use aqt_stock::models::market::MarketData;
+use aqt_stock::services::broker::{Broker, MarketDataSource};
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::strategys::strategy::Strategy;
+use longport::decimal;
+use longport::trade::OrderSide;
+
+// 第一根K线收盘买入 10 股，之后每根K线检查可见K线数量
+struct BuyOnceStrategy {
+    broker: Arc<PaperBroker>,
+    market: Arc<BacktestMarket>,
+    bought: bool,
+}
+
+impl Strategy for BuyOnceStrategy {
+    type Broker = PaperBroker;
+    type Market = BacktestMarket;
+
+    fn new(broker: Arc<PaperBroker>, market: Arc<BacktestMarket>) -> Self {
+        BuyOnceStrategy { broker, market, bought: false }
+    }
+
+    async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    async fn execute(&mut self, event: &MarketData) -> Result<(), Box<dyn std::error::Error>> {
+        let candles = self.market.get_candlesticks(event.symbol.clone(), "1d".to_string()).await?;
+        // 只能看到已收盘的K线
+        assert_eq!(candles.last().unwrap().close, f64::try_from(event.close).unwrap());
+        if !self.bought {
+            self.bought = true;
+            self.broker.submit_order(event.symbol.clone(), OrderSide::Buy, event.price, decimal!(10.0)).await?;
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn backtest_replays_candles() {
+    let dir = std::env::temp_dir().join("aqt_backtest_test");
+    fs::create_dir_all(&dir).unwrap();
+    let day = 24 * 60 * 60;
+    let mut csv = String::from("timestamp,open,high,low,close,volume\n");
+    for (i, close) in [10.0, 11.0, 12.0, 13.0].iter().enumerate() {
+        csv.push_str(&format!("{},{},{},{},{},1000\n", i as u64 * day, close - 0.5, close + 1.0, close - 1.0, close));
+    }
+    fs::write(dir.join("TEST.US_1d.csv"), csv).unwrap();
+
+    let mut symbol = SymbolConfig::new();
+    symbol.symbol = "TEST.US".to_string();
+    symbol.period = "1d".to_string();
+    let backtest = Backtest::new(
+        BacktestConfig {
+            data_dir: dir.to_string_lossy().to_string(),
+            initial_cash: 1000.0,
+            currency: "USD".to_string(),
+            participation: None,
+            short_margin: None,
+            output_dir: "backtest_output".to_string(),
+        },
+        vec![symbol],
+    );
+    let result = backtest.run::<BuyOnceStrategy>().await.unwrap();
+
+    // 第一根K线收盘下单，第二根K线开盘价 10.5 成交
+    assert_eq!(result.fills.len(), 1);
+    assert_eq!(result.fills[0].price, decimal!(10.5));
+    assert_eq!(result.equity_curve.len(), 4);
+    assert_eq!(result.equity_curve[3].equity, 1000.0 - 105.0 + 130.0);
+}
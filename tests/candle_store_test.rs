@@ -0,0 +1,173 @@
+use std::fs;
+use std::sync::{Arc, Mutex};
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::services::broker::MarketDataSource;
+use aqt_stock::store::cached_market::CachedMarket;
+use aqt_stock::store::candle_store::CandleStore;
+use aqt_stock::utils::error::AqtError;
+use longport::quote::MarketTemperature;
+
+mod common;
+use common::temp_dir;
+
+fn candle(ts: u64, close: f64) -> Candle {
+    Candle {
+        symbol: Some("TEST.US".to_string()),
+        timestamp: ts,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 100.0,
+    }
+}
+
+// 记录每次增量请求的起始时间，`offline` 时模拟网络错误；不带起始时间时只返回最近 `batch` 根
+struct MockMarket {
+    candles: Vec<Candle>,
+    requests: Mutex<Vec<Option<u64>>>,
+    offline: bool,
+    batch: usize,
+}
+
+impl MarketDataSource for MockMarket {
+    async fn get_candlesticks(&self, _symbol: String, _period: String) -> Result<Vec<Candle>, AqtError> {
+        Ok(self.candles.clone())
+    }
+
+    async fn get_candlesticks_since(&self, _symbol: String, _period: String, since: Option<u64>) -> Result<Vec<Candle>, AqtError> {
+        self.requests.lock().unwrap().push(since);
+        if self.offline {
+            return Err(AqtError::Network("offline".to_string()));
+        }
+        let Some(since) = since else {
+            return Ok(self.candles[self.candles.len().saturating_sub(self.batch)..].to_vec());
+        };
+        Ok(self.candles.iter().filter(|c| c.timestamp >= since).cloned().collect())
+    }
+
+    async fn get_market_temperature(&self) -> Result<MarketTemperature, AqtError> {
+        Ok(MarketTemperature {
+            temperature: 50,
+            description: "mock".to_string(),
+            valuation: 50,
+            sentiment: 50,
+            timestamp: time::OffsetDateTime::UNIX_EPOCH,
+        })
+    }
+}
+
+#[tokio::test]
+async fn store_syncs_incrementally() {
+    let dir = temp_dir("aqt_candle_store_sync");
+    let store = CandleStore::new(&dir);
+    let mut market = MockMarket {
+        candles: vec![candle(60, 1.0), candle(120, 2.0)],
+        requests: Mutex::new(Vec::new()),
+        offline: false,
+        batch: 10,
+    };
+    assert_eq!(store.sync(&market, "TEST.US", "1m").await.unwrap(), 2);
+
+    // 最后一根K线更新，并新增一根
+    market.candles = vec![candle(60, 1.0), candle(120, 2.5), candle(180, 3.0)];
+    assert_eq!(store.sync(&market, "TEST.US", "1m").await.unwrap(), 1);
+    assert_eq!(*market.requests.lock().unwrap(), vec![None, Some(120)]);
+
+    // 重新打开存储，从文件读取
+    let store = CandleStore::new(&dir);
+    let candles = store.load("TEST.US", "1m").unwrap();
+    assert_eq!(candles.iter().map(|c| c.close).collect::<Vec<_>>(), vec![1.0, 2.5, 3.0]);
+    assert_eq!(store.range("TEST.US", "1m", Some(100), Some(180)).unwrap().len(), 2);
+    assert_eq!(store.latest("TEST.US", "1m", 1).unwrap()[0].timestamp, 180);
+}
+
+#[test]
+fn store_repairs_duplicate_bars() {
+    let dir = temp_dir("aqt_candle_store_repair");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("TEST.US_1d.csv"),
+        "timestamp,open,high,low,close,volume\n200,2,2,2,2,1\n100,1,1,1,1,1\n200,3,3,3,3,1\n",
+    )
+    .unwrap();
+    let store = CandleStore::new(&dir);
+    let candles = store.load("TEST.US", "1d").unwrap();
+    assert_eq!(candles.len(), 2);
+    assert_eq!(candles[1].close, 3.0);
+    let content = fs::read_to_string(dir.join("TEST.US_1d.csv")).unwrap();
+    assert_eq!(content.lines().count(), 3);
+}
+
+#[test]
+fn merge_rewrites_only_changed_tail() {
+    let dir = temp_dir("aqt_candle_store_tail");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("TEST.US_1m.csv");
+    // 与写入格式不同的内容，整个文件重写时会被改变
+    let original = "timestamp,open,high,low,close,volume\n60,1.0,1.0,1.0,1.0,100.0\n120,2.0,2.0,2.0,2.0,100.0\n";
+    fs::write(&path, original).unwrap();
+    let store = CandleStore::new(&dir);
+
+    // 数据没有变化时不写文件
+    assert_eq!(store.merge("TEST.US", "1m", vec![candle(120, 2.0)]).unwrap(), 0);
+    assert_eq!(fs::read_to_string(&path).unwrap(), original);
+
+    // 最后一根K线更新，并新增一根，只替换文件末尾
+    assert_eq!(store.merge("TEST.US", "1m", vec![candle(120, 2.5), candle(180, 3.0)]).unwrap(), 1);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "timestamp,open,high,low,close,volume\n60,1.0,1.0,1.0,1.0,100.0\n120,2.5,2.5,2.5,2.5,100\n180,3,3,3,3,100\n"
+    );
+    let candles = CandleStore::new(&dir).load("TEST.US", "1m").unwrap();
+    assert_eq!(candles.iter().map(|c| c.close).collect::<Vec<_>>(), vec![1.0, 2.5, 3.0]);
+}
+
+#[tokio::test]
+async fn cached_market_falls_back_to_local_bars() {
+    let dir = temp_dir("aqt_cached_market_offline");
+    let market = Arc::new(MockMarket {
+        candles: vec![candle(60, 1.0)],
+        requests: Mutex::new(Vec::new()),
+        offline: true,
+        batch: 10,
+    });
+    let cached = CachedMarket::new(market, CandleStore::new(&dir));
+
+    // 本地没有数据时返回同步的错误，而不是空K线
+    let err = cached.get_candlesticks("TEST.US".to_string(), "1m".to_string()).await.unwrap_err();
+    assert!(err.is_retryable());
+
+    // 本地已有数据时同步失败仍返回本地K线
+    cached.store().merge("TEST.US", "1m", vec![candle(60, 1.0)]).unwrap();
+    let candles = cached.get_candlesticks("TEST.US".to_string(), "1m".to_string()).await.unwrap();
+    assert_eq!(candles.len(), 1);
+}
+
+#[tokio::test]
+async fn cached_market_backfills_bars_before_first_stored() {
+    let dir = temp_dir("aqt_cached_market_backfill");
+    let market = Arc::new(MockMarket {
+        candles: (1..=10).map(|i| candle(i * 60, i as f64)).collect(),
+        requests: Mutex::new(Vec::new()),
+        offline: false,
+        batch: 3,
+    });
+    let cached = CachedMarket::new(market.clone(), CandleStore::new(&dir));
+
+    // 本地没有数据，同步只拿到最近 3 根，需要补齐起始时间之后的更早K线
+    let candles = cached
+        .get_candlesticks_since("TEST.US".to_string(), "1m".to_string(), Some(120))
+        .await
+        .unwrap();
+    assert_eq!(candles.iter().map(|c| c.timestamp).collect::<Vec<_>>(), (2..=10).map(|i| i * 60).collect::<Vec<_>>());
+    assert_eq!(*market.requests.lock().unwrap(), vec![None, Some(120)]);
+    assert_eq!(cached.store().load("TEST.US", "1m").unwrap().len(), 9);
+
+    // 已经补齐过的范围不再重复拉取历史
+    cached
+        .get_candlesticks_since("TEST.US".to_string(), "1m".to_string(), Some(300))
+        .await
+        .unwrap();
+    assert_eq!(*market.requests.lock().unwrap(), vec![None, Some(120), Some(600)]);
+}
@@ -0,0 +1,68 @@
+use aqt_stock::collect::candlestick::BarTracker;
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::models::bar::BarEventKind;
+
+fn candle(ts: u64, close: f64) -> Candle {
+    Candle {
+        symbol: Some("AAPL.US".to_string()),
+        timestamp: ts,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 100.0,
+    }
+}
+
+#[test]
+fn confirmed_push_closes_bar() {
+    let mut tracker = BarTracker::new();
+    let events = tracker.on_candle("AAPL.US", "1h", candle(3600, 100.0), false);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, BarEventKind::Updated);
+
+    let events = tracker.on_candle("AAPL.US", "1h", candle(3600, 101.0), true);
+    let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+    assert_eq!(kinds, vec![BarEventKind::Updated, BarEventKind::Closed]);
+    assert_eq!(events[1].candle.close, 101.0);
+    assert_eq!(events[1].period, "1h");
+
+    // 已收盘K线的重复推送被忽略，下一根K线不会再次关闭上一根
+    assert!(tracker.on_candle("AAPL.US", "1h", candle(3600, 101.0), true).is_empty());
+    let events = tracker.on_candle("AAPL.US", "1h", candle(7200, 102.0), false);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, BarEventKind::Updated);
+}
+
+#[test]
+fn new_bar_closes_previous_unconfirmed_bar() {
+    let mut tracker = BarTracker::new();
+    tracker.on_candle("AAPL.US", "1h", candle(3600, 100.0), false);
+    tracker.on_candle("AAPL.US", "1h", candle(3600, 100.5), false);
+
+    let events = tracker.on_candle("AAPL.US", "1h", candle(7200, 101.0), false);
+    assert_eq!(events.len(), 2);
+    assert!(events[0].is_closed());
+    assert_eq!(events[0].candle.timestamp, 3600);
+    assert_eq!(events[0].candle.close, 100.5);
+    assert_eq!(events[1].kind, BarEventKind::Updated);
+    assert_eq!(events[1].candle.timestamp, 7200);
+
+    // 过期的推送被忽略
+    assert!(tracker.on_candle("AAPL.US", "1h", candle(3600, 99.0), false).is_empty());
+}
+
+#[test]
+fn periods_are_tracked_separately() {
+    let mut tracker = BarTracker::new();
+    tracker.on_candle("AAPL.US", "1h", candle(3600, 100.0), false);
+    tracker.on_candle("AAPL.US", "1d", candle(0, 100.0), false);
+
+    let events = tracker.on_candle("AAPL.US", "1h", candle(7200, 101.0), false);
+    assert!(events[0].is_closed());
+    assert_eq!(events[0].period, "1h");
+
+    let events = tracker.on_candle("AAPL.US", "1d", candle(0, 101.0), false);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, BarEventKind::Updated);
+}
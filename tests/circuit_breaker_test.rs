@@ -0,0 +1,142 @@
+use std::fs;
+use std::sync::Arc;
+use aqt_stock::config::config::{BreakerConfig, Configs, PaperConfig};
+use aqt_stock::models::market::MarketData;
+use aqt_stock::services::broker::Broker;
+use aqt_stock::services::circuit_breaker::{CircuitBreaker, CircuitBreakerBroker};
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::services::risk::RiskReason;
+use aqt_stock::utils::error::AqtError;
+use longport::trade::{OrderSide, OrderStatus};
+use longport::decimal;
+
+mod common;
+use common::{quote, temp_file};
+
+fn latch_file(name: &str) -> String {
+    temp_file(name).to_string_lossy().to_string()
+}
+
+fn setup(config: BreakerConfig) -> (Arc<PaperBroker>, CircuitBreakerBroker<PaperBroker>) {
+    let paper = Arc::new(PaperBroker::new(&PaperConfig {
+        initial_cash: 10000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    }));
+    let breaker = Arc::new(CircuitBreaker::new(config));
+    (paper.clone(), CircuitBreakerBroker::new(paper, breaker))
+}
+
+/// 行情同时交给模拟券商和熔断器
+fn feed(paper: &PaperBroker, broker: &CircuitBreakerBroker<PaperBroker>, data: MarketData) {
+    paper.on_market_data(&data);
+    broker.breaker().observe(&data);
+}
+
+#[tokio::test]
+async fn drawdown_trips_flattens_and_stays_latched() {
+    let config = BreakerConfig {
+        max_drawdown: Some(3.0),
+        cancel_orders: true,
+        flatten: true,
+        latch_file: latch_file("aqt_breaker_drawdown.latch"),
+        ..Default::default()
+    };
+    let (paper, broker) = setup(config.clone());
+    feed(&paper, &broker, quote("AAPL.US", 100.0, 1000));
+    feed(&paper, &broker, quote("MSFT.US", 300.0, 1000));
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(50.0)).await.unwrap();
+    feed(&paper, &broker, quote("AAPL.US", 100.0, 1100));
+    // 委托价低于市价，订单一直未成交
+    broker.submit_order("MSFT.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1.0)).await.unwrap();
+    assert_eq!(broker.check().await.unwrap(), None);
+
+    // 持仓下跌 10%，净资产回撤 5%
+    feed(&paper, &broker, quote("AAPL.US", 90.0, 1200));
+    let reason = broker.check().await.unwrap().unwrap();
+    assert!(reason.contains("回撤"));
+    assert!(fs::metadata(&config.latch_file).is_ok());
+    // 未成交的订单已被撤销
+    let orders = paper.get_today_orders("MSFT.US").await.unwrap();
+    assert!(!orders.is_empty() && orders.iter().all(|o| o.status == OrderStatus::Canceled));
+    feed(&paper, &broker, quote("AAPL.US", 90.0, 1300));
+    assert!(paper.stock_positions().await.unwrap().is_empty());
+
+    // 熔断后不再开仓
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(90.0), decimal!(1.0)).await;
+    assert!(matches!(resp, Err(AqtError::RiskRejected(RiskReason::CircuitOpen { .. }))));
+
+    // 重启后仍保持熔断，删除标记文件后解除
+    let restarted = CircuitBreaker::new(config);
+    assert!(restarted.tripped().is_some());
+    restarted.reset();
+    assert_eq!(restarted.tripped(), None);
+    assert_eq!(broker.breaker().tripped(), None);
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(90.0), decimal!(1.0)).await.unwrap();
+}
+
+#[tokio::test]
+async fn consecutive_failures_trip_breaker() {
+    let (paper, broker) = setup(BreakerConfig {
+        max_consecutive_failures: Some(2),
+        latch_file: latch_file("aqt_breaker_failures.latch"),
+        ..Default::default()
+    });
+    feed(&paper, &broker, quote("AAPL.US", 100.0, 1000));
+    // 资金不足的订单被券商拒绝，成功下单后重新计数
+    assert!(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1000.0)).await.is_err());
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1.0)).await.unwrap();
+    assert!(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1000.0)).await.is_err());
+    assert_eq!(broker.breaker().tripped(), None);
+
+    assert!(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1000.0)).await.is_err());
+    assert!(broker.breaker().tripped().unwrap().contains("连续下单失败 2 次"));
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1.0)).await;
+    assert!(matches!(resp, Err(AqtError::RiskRejected(RiskReason::CircuitOpen { .. }))));
+    broker.breaker().reset();
+}
+
+#[tokio::test]
+async fn breaker_fails_closed_when_latch_file_is_unusable() {
+    let blocker = std::env::temp_dir().join("aqt_breaker_blocker");
+    let _ = fs::remove_dir_all(&blocker);
+    let _ = fs::remove_file(&blocker);
+    let (paper, broker) = setup(BreakerConfig {
+        max_consecutive_failures: Some(1),
+        latch_file: blocker.join("breaker.latch").to_string_lossy().to_string(),
+        ..Default::default()
+    });
+    // 启动后标记文件的上级目录被普通文件占用，熔断时无法写入
+    fs::write(&blocker, "").unwrap();
+    feed(&paper, &broker, quote("AAPL.US", 100.0, 1000));
+    assert!(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1000.0)).await.is_err());
+    // 找不到标记文件也不会自行解除，直到显式解除
+    assert!(broker.breaker().tripped().is_some());
+    assert!(broker.breaker().tripped().is_some());
+    broker.breaker().reset();
+    assert_eq!(broker.breaker().tripped(), None);
+
+    // 标记文件无法读取时按已熔断处理
+    let unreadable = std::env::temp_dir().join("aqt_breaker_unreadable.latch");
+    let _ = fs::remove_file(&unreadable);
+    fs::create_dir_all(&unreadable).unwrap();
+    let breaker = CircuitBreaker::new(BreakerConfig {
+        latch_file: unreadable.to_string_lossy().to_string(),
+        ..Default::default()
+    });
+    assert!(breaker.tripped().unwrap().contains("无法读取熔断标记"));
+    breaker.reset();
+    assert!(breaker.tripped().is_some());
+}
+
+#[test]
+fn breaker_from_config() {
+    let cfg: Configs = serde_yaml::from_str("breaker:\n  max_drawdown: 5\n  flatten: true\n").unwrap();
+    let breaker = cfg.breaker.unwrap();
+    assert_eq!(breaker.max_drawdown, Some(5.0));
+    assert!(breaker.flatten);
+    assert!(!breaker.cancel_orders);
+    assert_eq!(breaker.check_interval_secs, 60);
+    assert_eq!(breaker.latch_file, "circuit_breaker.latch");
+}
@@ -0,0 +1,42 @@
+//! 集成测试共用的行情构造和临时文件工具，各测试文件通过 `mod common;` 引入
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use aqt_stock::models::market::MarketData;
+use longport::{decimal, Decimal};
+use time::OffsetDateTime;
+
+/// 当前时间的行情，开高低收均为 `price`，`volume` 为累计成交量
+pub fn quote(symbol: &str, price: f64, volume: i64) -> MarketData {
+    quote_at(symbol, price, volume, OffsetDateTime::now_utc())
+}
+
+/// 指定时间的行情
+pub fn quote_at(symbol: &str, price: f64, volume: i64, ts: OffsetDateTime) -> MarketData {
+    MarketData {
+        symbol: symbol.to_string(),
+        price: decimal!(price),
+        change: Decimal::ZERO,
+        volume,
+        high: decimal!(price),
+        low: decimal!(price),
+        open: decimal!(price),
+        close: decimal!(price),
+        ts,
+    }
+}
+
+/// 系统临时目录下的空目录路径，已存在时先删除
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// 系统临时目录下的文件路径，已存在时先删除
+pub fn temp_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(name);
+    let _ = fs::remove_file(&path);
+    path
+}
@@ -0,0 +1,145 @@
+use aqt_stock::computes::profile::UtBotParams;
+use aqt_stock::config::config::SymbolConfig;
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::models::order::Order;
+use aqt_stock::models::position::Position;
+use aqt_stock::strategys::exit_manager::{ExitManager, ExitReason};
+use longport::decimal;
+use longport::trade::{OrderSide, OrderStatus, OrderType};
+use time::{Duration, OffsetDateTime};
+
+fn position(quantity: f64, cost: f64) -> Position {
+    Position {
+        symbol: "AAPL.US".to_string(),
+        quantity: decimal!(quantity),
+        available_quantity: decimal!(quantity),
+        cost_price: decimal!(cost),
+        ..Default::default()
+    }
+}
+
+fn symbol(sl_ratio: i32) -> SymbolConfig {
+    let mut sym = SymbolConfig::new();
+    sym.symbol = "AAPL.US".to_string();
+    sym.sl_ratio = sl_ratio;
+    sym
+}
+
+fn t0() -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap()
+}
+
+#[test]
+fn fixed_stop_loss_for_long_and_short() {
+    let sym = symbol(3);
+    let mut exits = ExitManager::new();
+    exits.update_position("AAPL.US", &position(10.0, 100.0), &[], t0());
+    assert_eq!(exits.check(&sym, decimal!(98.0), t0()), None);
+    let signal = exits.check(&sym, decimal!(96.9), t0()).unwrap();
+    assert_eq!((signal.side, signal.quantity, signal.reason), (OrderSide::Sell, decimal!(10.0), ExitReason::StopLoss));
+    // 订单提交前不记录平仓
+    assert!(exits.records().is_empty());
+    // 已发出平仓订单，持仓数量变化前不重复触发
+    assert_eq!(exits.check(&sym, decimal!(95.0), t0()), None);
+    exits.reset_pending("AAPL.US");
+    let signal = exits.check(&sym, decimal!(95.0), t0()).unwrap();
+    exits.record("AAPL.US", signal.reason, decimal!(95.0), signal.held, t0());
+
+    // 空头价格上涨超过止损比例时买入平仓
+    exits.update_position("AAPL.US", &position(-5.0, 100.0), &[], t0());
+    assert_eq!(exits.check(&sym, decimal!(102.0), t0()), None);
+    let signal = exits.check(&sym, decimal!(103.5), t0()).unwrap();
+    assert_eq!((signal.side, signal.quantity, signal.held), (OrderSide::Buy, decimal!(5.0), decimal!(-5.0)));
+    exits.record("AAPL.US", signal.reason, decimal!(103.5), signal.held, t0());
+
+    // 平仓后清除状态
+    exits.update_position("AAPL.US", &Position::default(), &[], t0());
+    assert_eq!(exits.check(&sym, decimal!(50.0), t0()), None);
+    let reasons: Vec<_> = exits.records().iter().map(|r| r.reason).collect();
+    assert_eq!(reasons, vec![ExitReason::StopLoss; 2]);
+}
+
+fn cover_order(quantity: f64, status: OrderStatus) -> Order {
+    Order {
+        order_id: "1".to_string(),
+        symbol: "AAPL.US".to_string(),
+        side: OrderSide::Buy,
+        status,
+        order_type: OrderType::LO,
+        quantity: decimal!(quantity),
+        executed_quantity: decimal!(0.0),
+        price: Some(decimal!(103.5)),
+        executed_price: None,
+        submitted_at: t0(),
+        remark: String::new(),
+    }
+}
+
+#[test]
+fn working_exit_orders_reduce_available_and_keep_pending() {
+    let sym = symbol(3);
+    let mut exits = ExitManager::new();
+    // 空头已有未成交的平空订单，只能再平剩余数量
+    exits.update_position("AAPL.US", &position(-10.0, 100.0), &[cover_order(4.0, OrderStatus::New)], t0());
+    let signal = exits.check(&sym, decimal!(103.5), t0()).unwrap();
+    assert_eq!(signal.quantity, decimal!(6.0));
+
+    // 持仓数量不变、平仓订单仍在挂单时继续等待
+    let working = [cover_order(4.0, OrderStatus::New), cover_order(6.0, OrderStatus::New)];
+    exits.update_position("AAPL.US", &position(-10.0, 100.0), &working, t0());
+    assert_eq!(exits.check(&sym, decimal!(104.0), t0()), None);
+
+    // 部分成交后持仓数量变化，剩余数量都在挂单中，没有可平仓数量
+    exits.update_position("AAPL.US", &position(-6.0, 100.0), &[cover_order(6.0, OrderStatus::New)], t0());
+    assert_eq!(exits.check(&sym, decimal!(104.0), t0()), None);
+
+    // 平仓订单被撤销后重新触发
+    exits.update_position("AAPL.US", &position(-6.0, 100.0), &[cover_order(6.0, OrderStatus::Canceled)], t0());
+    assert_eq!(exits.check(&sym, decimal!(104.0), t0()).unwrap().quantity, decimal!(6.0));
+}
+
+#[test]
+fn breakeven_and_time_stop() {
+    let mut sym = symbol(10);
+    sym.exit.breakeven_ratio = Some(5.0);
+    sym.exit.max_holding_secs = Some(3600);
+    let mut exits = ExitManager::new();
+    exits.update_position("AAPL.US", &position(10.0, 100.0), &[], t0());
+
+    // 盈利未达到 5% 时回落到成本价不平仓
+    assert_eq!(exits.check(&sym, decimal!(104.0), t0()), None);
+    assert_eq!(exits.check(&sym, decimal!(100.0), t0()), None);
+    // 盈利达到 5% 后回落到成本价保本平仓
+    assert_eq!(exits.check(&sym, decimal!(105.0), t0()), None);
+    assert_eq!(exits.check(&sym, decimal!(99.5), t0()).unwrap().reason, ExitReason::Breakeven);
+
+    // 加仓不重新计时，超过最长持仓时间后平仓
+    exits.update_position("AAPL.US", &position(20.0, 100.0), &[], t0() + Duration::minutes(30));
+    assert_eq!(exits.check(&sym, decimal!(101.0), t0() + Duration::minutes(59)), None);
+    let signal = exits.check(&sym, decimal!(101.0), t0() + Duration::hours(1)).unwrap();
+    assert_eq!((signal.reason, signal.quantity), (ExitReason::TimeStop, decimal!(20.0)));
+}
+
+#[test]
+fn trailing_stop_only_moves_in_favour() {
+    let mut sym = symbol(0);
+    sym.exit.trailing = Some(UtBotParams { key: 1.0, atr: 3, heikin: false });
+    let mut exits = ExitManager::new();
+    exits.update_position("AAPL.US", &position(10.0, 100.0), &[], t0());
+
+    let mut candles: Vec<Candle> = (0..20)
+        .map(|i| {
+            let close = 100.0 + i as f64;
+            Candle { timestamp: i * 3600, open: close - 0.5, high: close + 1.0, low: close - 1.0, close, ..Default::default() }
+        })
+        .collect();
+    exits.update_levels(&sym, &candles);
+    // 上涨趋势中跟踪止损位于价格下方
+    assert_eq!(exits.check(&sym, decimal!(119.0), t0()), None);
+
+    // 价格回落后 UTBot 止损下移，但跟踪止损保持不变
+    candles.push(Candle { timestamp: 20 * 3600, open: 110.0, high: 111.0, low: 100.0, close: 101.0, ..Default::default() });
+    exits.update_levels(&sym, &candles);
+    let signal = exits.check(&sym, decimal!(110.0), t0()).unwrap();
+    assert_eq!(signal.reason, ExitReason::TrailingStop);
+}
@@ -0,0 +1,118 @@
+use std::cell::RefCell;
+use aqt_stock::computes::indicator_state::IndicatorState;
+use aqt_stock::computes::params::IndicatorParams;
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::indicators::cyc::CYC;
+use aqt_stock::indicators::kdj::KDJ;
+use aqt_stock::indicators::macd::MACD;
+use aqt_stock::indicators::schaff_trend_cycle::SchaffTrendCycle;
+use aqt_stock::indicators::utbot::UTBot;
+
+fn candle(i: usize, shift: f64) -> Candle {
+    let close = 100.0 + 8.0 * (i as f64 / 5.0).sin() + 3.0 * (i as f64 / 2.3).cos() + shift;
+    Candle {
+        symbol: None,
+        timestamp: i as u64,
+        open: close - 0.4,
+        high: close + 1.0 + (i % 3) as f64 * 0.2,
+        low: close - 1.2,
+        close,
+        volume: 1000.0 + (i % 7) as f64 * 150.0,
+    }
+}
+
+// 每根K线先推送一个未收盘版本，再用 update_last 修正为最终版本
+fn feed(n: usize, mut update: impl FnMut(&Candle), mut update_last: impl FnMut(&Candle), mut check: impl FnMut(&[Candle])) {
+    let mut candles = Vec::new();
+    for i in 0..n {
+        update(&candle(i, 0.7));
+        update_last(&candle(i, -0.3));
+        update_last(&candle(i, 0.0));
+        candles.push(candle(i, 0.0));
+        check(&candles);
+    }
+}
+
+#[test]
+fn macd_incremental_matches_batch() {
+    let inc = RefCell::new(MACD::new(5, 10, 5));
+    feed(80, |c| inc.borrow_mut().update(c), |c| inc.borrow_mut().update_last(c), |candles| {
+        let mut batch = MACD::new(5, 10, 5);
+        let (buy, sell) = batch.calculate(candles);
+        let inc = inc.borrow();
+        assert_eq!(inc.macd_line(), batch.macd_line());
+        assert_eq!(inc.signal_line(), batch.signal_line());
+        assert_eq!(inc.histogram(), batch.histogram());
+        assert_eq!(inc.buy_signals(), &buy[..]);
+        assert_eq!(inc.sell_signals(), &sell[..]);
+    });
+}
+
+#[test]
+fn kdj_incremental_matches_batch() {
+    let inc = RefCell::new(KDJ::new(9, 3, 3));
+    feed(80, |c| inc.borrow_mut().update(c), |c| inc.borrow_mut().update_last(c), |candles| {
+        let mut batch = KDJ::new(9, 3, 3);
+        let (k, d, j) = batch.calculate(candles);
+        let inc = inc.borrow();
+        assert_eq!(inc.k_values(), &k[..]);
+        assert_eq!(inc.d_values(), &d[..]);
+        assert_eq!(inc.j_values(), &j[..]);
+    });
+}
+
+#[test]
+fn utbot_incremental_matches_batch() {
+    for heikin in [false, true] {
+        let inc = RefCell::new(UTBot::new(1.5, 10, heikin));
+        feed(80, |c| inc.borrow_mut().update(c), |c| inc.borrow_mut().update_last(c), |candles| {
+            let mut batch = UTBot::new(1.5, 10, heikin);
+            batch.calculate(candles);
+            let inc = inc.borrow();
+            assert_eq!(inc.trailing_stops(), batch.trailing_stops());
+            assert_eq!(inc.positions(), batch.positions());
+            assert_eq!(inc.buy_signals(), batch.buy_signals());
+            assert_eq!(inc.sell_signals(), batch.sell_signals());
+        });
+    }
+}
+
+#[test]
+fn stc_incremental_matches_batch() {
+    let inc = RefCell::new(SchaffTrendCycle::new(12, 26, 50));
+    feed(120, |c| inc.borrow_mut().update(c), |c| inc.borrow_mut().update_last(c), |candles| {
+        let mut batch = SchaffTrendCycle::new(12, 26, 50);
+        let signals = batch.calculate(candles);
+        assert_eq!(inc.borrow().signals(), &signals[..]);
+    });
+}
+
+#[test]
+fn cyc_incremental_matches_batch() {
+    let inc = RefCell::new(CYC::default());
+    feed(80, |c| inc.borrow_mut().update(c), |c| inc.borrow_mut().update_last(c), |candles| {
+        let mut batch = CYC::default();
+        let (short, mid, long) = batch.calculate(candles);
+        let inc = inc.borrow();
+        assert_eq!(inc.short_cyc(), &short[..]);
+        assert_eq!(inc.mid_cyc(), &mid[..]);
+        assert_eq!(inc.long_cyc(), &long[..]);
+    });
+}
+
+#[test]
+fn indicator_state_decisions_match_batch() {
+    let profile = IndicatorParams::default().profile();
+    let mut state = IndicatorState::new();
+    let mut candles: Vec<Candle> = (0..60).map(|i| candle(i, 0.0)).collect();
+    for i in 60..120 {
+        // 未收盘的K线先推进一次，收盘后以最终数据修正
+        candles.push(candle(i, 0.7));
+        state.sync(&profile, &candles);
+        *candles.last_mut().unwrap() = candle(i, 0.0);
+        state.sync(&profile, &candles);
+        assert_eq!(state.last_timestamp(), Some(i as u64));
+        let incremental = profile.build_with_state(Some(&state), &candles, None, None).execute_rules();
+        assert_eq!(incremental, profile.build(&candles, None, None).execute_rules());
+    }
+}
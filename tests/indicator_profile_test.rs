@@ -0,0 +1,125 @@
+use aqt_stock::computes::params::IndicatorParams;
+use aqt_stock::computes::rules::RuleConfig;
+use aqt_stock::computes::profile::{IndicatorKind, IndicatorProfile, MacdParams, UtBotParams};
+use aqt_stock::config::config::Configs;
+use aqt_stock::indicators::candle::Candle;
+
+const CONFIG: &str = r#"
+profiles:
+  smallcap:
+    threshold: 4
+    indicators:
+      - kind: utbot
+        key: 2.0
+        atr: 14
+        weight: 2
+      - kind: macd
+        fast: 8
+      - kind: cyc
+symbols:
+  - symbol: TQQQ.US
+    symbol_type: NASDAQ
+    volume: 0.1
+    period: 1h
+    tp_ratio: 5
+    sl_ratio: 3
+    profile: smallcap
+  - symbol: SPY.US
+    symbol_type: AMEX
+    volume: 0.2
+    period: 1d
+    tp_ratio: 3
+    sl_ratio: 2
+    threshold: 1
+    indicators:
+      - kind: kdj
+      - kind: market
+  - symbol: AAPL.US
+    symbol_type: NASDAQ
+    volume: 0.1
+    period: 1h
+    tp_ratio: 5
+    sl_ratio: 3
+  - symbol: SOXL.US
+    symbol_type: NASDAQ
+    volume: 0.1
+    period: 1h
+    tp_ratio: 5
+    sl_ratio: 3
+    profile: smallcap
+    threshold: 2
+    rules:
+      type: majority
+"#;
+
+fn candles(n: usize) -> Vec<Candle> {
+    (0..n)
+        .map(|i| {
+            let close = 100.0 + 10.0 * (i as f64 / 8.0).sin();
+            Candle {
+                symbol: Some("TEST.US".to_string()),
+                timestamp: i as u64 * 3600,
+                open: close - 0.5,
+                high: close + 1.0,
+                low: close - 1.0,
+                close,
+                volume: 1000.0,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn symbols_resolve_their_own_profiles() {
+    let cfg: Configs = serde_yaml::from_str(CONFIG).unwrap();
+
+    let smallcap = cfg.symbols[0].indicator_profile(&cfg.profiles);
+    assert_eq!(smallcap.threshold, 4);
+    assert_eq!(smallcap.indicators.len(), 3);
+    assert_eq!(smallcap.indicators[0].kind, IndicatorKind::Utbot(UtBotParams { key: 2.0, atr: 14, heikin: false }));
+    assert_eq!(smallcap.indicators[0].weight, 2);
+    // 未配置的参数使用默认值
+    assert_eq!(smallcap.indicators[1].kind, IndicatorKind::Macd(MacdParams { fast: 8, ..Default::default() }));
+    assert_eq!(smallcap.indicators[1].weight, 1);
+    assert!(!smallcap.needs_technicals());
+
+    let etf = cfg.symbols[1].indicator_profile(&cfg.profiles);
+    assert_eq!(etf.threshold, 1);
+    assert!(etf.needs_market());
+
+    // 引用组合时 threshold、rules 覆盖组合中的值，指标不变
+    let overridden = cfg.symbols[3].indicator_profile(&cfg.profiles);
+    assert_eq!(overridden.threshold, 2);
+    assert_eq!(overridden.rules, RuleConfig::Majority);
+    assert_eq!(overridden.indicators, smallcap.indicators);
+
+    // 未配置指标的股票沿用原先的六个指标
+    let default = cfg.symbols[2].indicator_profile(&cfg.profiles);
+    assert_eq!(default, IndicatorProfile::default());
+    assert_eq!(default.indicators.len(), 6);
+    assert!(default.needs_technicals());
+}
+
+#[test]
+fn profile_builds_calculators_and_skips_missing_inputs() {
+    let cfg: Configs = serde_yaml::from_str(CONFIG).unwrap();
+    let candles = candles(120);
+
+    let smallcap = cfg.symbols[0].indicator_profile(&cfg.profiles).build(&candles, None, None);
+    assert_eq!(smallcap.names(), vec!["UTBOT", "MACD", "CYC"]);
+
+    // 市场温度缺失时跳过该指标
+    let etf = cfg.symbols[1].indicator_profile(&cfg.profiles).build(&candles, None, None);
+    assert_eq!(etf.names(), vec!["KDJ"]);
+}
+
+#[test]
+fn params_profile_matches_params() {
+    let params = IndicatorParams { macd_fast: 3, threshold: 2, ..Default::default() };
+    let profile = params.profile();
+    assert_eq!(profile.threshold, 2);
+    assert_eq!(profile.indicators[1].kind, IndicatorKind::Macd(MacdParams { fast: 3, slow: 10, signal: 5 }));
+
+    let candles = candles(120);
+    assert_eq!(params.calculate(&candles).execute_rules().signal, profile.build(&candles, None, None).execute_rules().signal);
+}
@@ -0,0 +1,235 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use aqt_stock::backtest::market::BacktestMarket;
+use aqt_stock::config::config::Configs;
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::models::bar::{BarEvent, BarEventKind};
+use aqt_stock::models::market::MarketData;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::strategys::multi_executor::MultiExecutor;
+use aqt_stock::strategys::strategy::Strategy;
+use tokio::sync::mpsc;
+
+mod common;
+use common::quote;
+
+/// 记录收到的行情，收到 `PANIC.US` 时模拟策略崩溃
+struct RecordingStrategy {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+    bar_delay: Duration,
+}
+
+impl Strategy for RecordingStrategy {
+    type Broker = PaperBroker;
+    type Market = BacktestMarket;
+
+    fn new(_broker: Arc<PaperBroker>, _market: Arc<BacktestMarket>) -> Self {
+        RecordingStrategy { name: "new", log: Arc::new(Mutex::new(Vec::new())), bar_delay: Duration::from_millis(1) }
+    }
+
+    async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    async fn execute(&mut self, event: &MarketData) -> Result<(), Box<dyn std::error::Error>> {
+        if event.symbol == "PANIC.US" {
+            panic!("策略崩溃");
+        }
+        self.log.lock().unwrap().push(format!("{}:{}", self.name, event.symbol));
+        Ok(())
+    }
+
+    async fn on_bar(&mut self, bar: &BarEvent) -> Result<(), Box<dyn std::error::Error>> {
+        // 处理较慢，K线通道会被填满
+        tokio::time::sleep(self.bar_delay).await;
+        let kind = if bar.is_closed() { "bar" } else { "update" };
+        self.log.lock().unwrap().push(format!("{}:{}:{}", self.name, kind, bar.candle.timestamp));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.log.lock().unwrap().push(format!("{}:stop", self.name));
+        Ok(())
+    }
+}
+
+fn strategy(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> RecordingStrategy {
+    RecordingStrategy { name, log: log.clone(), bar_delay: Duration::from_millis(1) }
+}
+
+fn bar(ts: u64, kind: BarEventKind) -> BarEvent {
+    let candle = Candle { timestamp: ts, open: 1.0, high: 1.0, low: 1.0, close: 1.0, volume: 0.0, symbol: None };
+    BarEvent::new("AAPL.US", "1h", kind, candle)
+}
+
+fn entries(log: &Arc<Mutex<Vec<String>>>, prefix: &str) -> Vec<String> {
+    log.lock().unwrap().iter().filter(|e| e.starts_with(prefix)).cloned().collect()
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[tokio::test]
+async fn routes_quotes_by_symbol() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut executor = MultiExecutor::new(16);
+    executor.start("tech", strings(&["AAPL.US", "MSFT.US"]), strategy("tech", &log));
+    executor.start("etf", strings(&["SPY.US", "AAPL.US"]), strategy("etf", &log));
+    assert_eq!(executor.names(), strings(&["tech", "etf"]));
+
+    let (sender, receiver) = mpsc::channel(16);
+    for symbol in ["AAPL.US", "SPY.US", "MSFT.US", "TSLA.US"] {
+        sender.send(quote(symbol, 1.0, 0)).await.unwrap();
+    }
+    drop(sender);
+    executor.run(receiver, None).await;
+
+    let mut log = log.lock().unwrap().clone();
+    log.sort();
+    assert_eq!(
+        log,
+        strings(&["etf:AAPL.US", "etf:SPY.US", "etf:stop", "tech:AAPL.US", "tech:MSFT.US", "tech:stop"])
+    );
+}
+
+#[tokio::test]
+async fn failed_strategy_is_isolated_and_strategies_stop_independently() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut executor = MultiExecutor::new(16);
+    executor.start("fragile", strings(&["PANIC.US", "AAPL.US"]), strategy("fragile", &log));
+    executor.start("steady", strings(&["AAPL.US"]), strategy("steady", &log));
+    executor.start("other", strings(&["SPY.US"]), strategy("other", &log));
+
+    assert_eq!(executor.route_quote(&quote("PANIC.US", 1.0, 0)), 1);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    // 崩溃的策略被移除，其他策略照常接收行情
+    assert_eq!(executor.route_quote(&quote("AAPL.US", 1.0, 0)), 1);
+    assert_eq!(executor.names(), strings(&["steady", "other"]));
+
+    // 单独停止一个策略
+    executor.stop("other").unwrap().await.unwrap();
+    assert_eq!(executor.route_quote(&quote("SPY.US", 1.0, 0)), 0);
+    executor.shutdown().await;
+
+    let mut log = log.lock().unwrap().clone();
+    log.sort();
+    assert_eq!(log, strings(&["other:stop", "steady:AAPL.US", "steady:stop"]));
+}
+
+#[tokio::test]
+async fn strategies_start_and_stop_through_handle_while_running() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut executor = MultiExecutor::new(1);
+    let handle = executor.handle();
+    let (sender, receiver) = mpsc::channel(16);
+    let (bar_sender, bar_receiver) = mpsc::channel(16);
+    let task = tokio::spawn(async move {
+        executor.run(receiver, Some(bar_receiver)).await;
+    });
+
+    assert!(handle.start("tech", strings(&["AAPL.US"]), strategy("tech", &log)));
+    assert_eq!(handle.names().await, strings(&["tech"]));
+    // 通道容量为 1，K线也不会丢失
+    for ts in 1..=20 {
+        bar_sender.send(bar(ts, BarEventKind::Closed)).await.unwrap();
+    }
+    for _ in 0..200 {
+        if log.lock().unwrap().len() == 20 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let expected: Vec<String> = (1..=20).map(|ts| format!("tech:bar:{}", ts)).collect();
+    assert_eq!(*log.lock().unwrap(), expected);
+
+    assert!(handle.stop("tech"));
+    assert!(handle.names().await.is_empty());
+    drop(sender);
+    task.await.unwrap();
+    assert!(handle.is_closed());
+    assert!(!handle.start("etf", strings(&["SPY.US"]), strategy("etf", &log)));
+    assert_eq!(log.lock().unwrap().last().unwrap(), "tech:stop");
+}
+
+#[tokio::test]
+async fn slow_strategy_does_not_stall_routing() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut executor = MultiExecutor::new(1);
+    let slow = RecordingStrategy { name: "slow", log: log.clone(), bar_delay: Duration::from_millis(20) };
+    executor.start("slow", strings(&["AAPL.US"]), slow);
+    executor.start("fast", strings(&["AAPL.US"]), strategy("fast", &log));
+
+    // 路由不等待策略处理，慢策略处理不过来时丢弃未收盘的K线更新和行情
+    let started = std::time::Instant::now();
+    for ts in 1..=10 {
+        executor.route_bar(&bar(ts, BarEventKind::Updated));
+        assert_eq!(executor.route_bar(&bar(ts, BarEventKind::Closed)), 2);
+        executor.route_quote(&quote("AAPL.US", 1.0, 0));
+    }
+    assert!(started.elapsed() < Duration::from_millis(100));
+    for _ in 0..100 {
+        if entries(&log, "fast:bar:").len() == 10 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    // 快策略先于慢策略处理完
+    assert_eq!(entries(&log, "fast:bar:").len(), 10);
+    assert!(entries(&log, "slow:bar:").len() < 10);
+
+    // 收盘K线都会按顺序送达慢策略
+    for _ in 0..100 {
+        if entries(&log, "slow:bar:").len() == 10 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let expected: Vec<String> = (1..=10).map(|ts| format!("slow:bar:{}", ts)).collect();
+    assert_eq!(entries(&log, "slow:bar:"), expected);
+    executor.shutdown().await;
+}
+
+#[test]
+fn strategies_from_config() {
+    let cfg: Configs = serde_yaml::from_str(
+        r#"
+symbols:
+  - { symbol: TQQQ.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3 }
+strategies:
+  - name: tech
+    symbols:
+      - { symbol: AAPL.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3 }
+      - { symbol: MSFT.US, symbol_type: NASDAQ, volume: 0.1, period: 1d, tp_ratio: 5, sl_ratio: 3 }
+  - name: swing
+    symbols:
+      - { symbol: AAPL.US, symbol_type: NASDAQ, volume: 0.2, period: 1d, tp_ratio: 8, sl_ratio: 4 }
+  - name: paused
+    enabled: false
+    symbols:
+      - { symbol: SPY.US, symbol_type: AMEX, volume: 0.2, period: 1d, tp_ratio: 3, sl_ratio: 2 }
+"#,
+    )
+    .unwrap();
+    let names: Vec<String> = cfg.strategy_configs().into_iter().map(|s| s.name).collect();
+    assert_eq!(names, strings(&["tech", "swing"]));
+    assert_eq!(
+        cfg.subscriptions(),
+        vec![
+            ("AAPL.US".to_string(), "1h".to_string()),
+            ("MSFT.US".to_string(), "1d".to_string()),
+            ("AAPL.US".to_string(), "1d".to_string()),
+        ]
+    );
+
+    // 未配置 strategies 时由默认策略负责顶层 symbols
+    let cfg: Configs = serde_yaml::from_str(
+        "symbols:\n  - { symbol: TQQQ.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3 }\n",
+    )
+    .unwrap();
+    let strategies = cfg.strategy_configs();
+    assert_eq!(strategies.len(), 1);
+    assert_eq!(strategies[0].name, "default");
+    assert_eq!(strategies[0].symbols[0].symbol, "TQQQ.US");
+}
@@ -0,0 +1,81 @@
+use aqt_stock::backtest::optimizer::{evaluate, grid_candidates, random_candidates, Optimizer};
+use aqt_stock::computes::params::IndicatorParams;
+use aqt_stock::config::config::{OptimizeMetric, OptimizerConfig, ParamGrid, SearchMode};
+use aqt_stock::indicators::candle::Candle;
+
+fn candles(n: usize) -> Vec<Candle> {
+    (0..n)
+        .map(|i| {
+            let close = 100.0 + 10.0 * (i as f64 / 8.0).sin() + i as f64 * 0.1;
+            Candle {
+                symbol: Some("TEST.US".to_string()),
+                timestamp: i as u64 * 86400,
+                open: close - 0.5,
+                high: close + 1.0,
+                low: close - 1.0,
+                close,
+                volume: 1000.0,
+            }
+        })
+        .collect()
+}
+
+fn config(grid: ParamGrid) -> OptimizerConfig {
+    OptimizerConfig {
+        grid,
+        search: SearchMode::Grid,
+        samples: 10,
+        seed: 7,
+        metric: OptimizeMetric::TotalReturn,
+        in_sample: Some(80),
+        out_of_sample: Some(40),
+        threads: Some(2),
+        top: 5,
+    }
+}
+
+#[test]
+fn grid_skips_invalid_combinations() {
+    let grid = ParamGrid {
+        macd_fast: vec![5, 12],
+        macd_slow: vec![10, 26],
+        threshold: vec![2, 3],
+        ..Default::default()
+    };
+    // (12, 10) 快线不小于慢线，被过滤
+    assert_eq!(grid_candidates(&grid).len(), 6);
+    assert_eq!(grid_candidates(&ParamGrid::default()), vec![IndicatorParams::default()]);
+
+    let sampled = random_candidates(&grid, 4, 1);
+    assert_eq!(sampled.len(), 4);
+    assert_eq!(sampled, random_candidates(&grid, 4, 1));
+}
+
+#[test]
+fn evaluate_waits_for_warmup() {
+    let params = IndicatorParams::default();
+    let data = candles(200);
+    assert_eq!(evaluate(&params, &data, 0, params.warmup() - 1).trades, 0);
+    let score = evaluate(&params, &data, 0, data.len());
+    assert!(score.max_drawdown >= 0.0);
+}
+
+#[test]
+fn optimizer_ranks_and_walks_forward() {
+    let grid = ParamGrid {
+        threshold: vec![1, 2, 3],
+        ..Default::default()
+    };
+    let optimizer = Optimizer::new(config(grid), vec![candles(200), candles(220)]);
+
+    let ranked = optimizer.rank();
+    assert_eq!(ranked.len(), 3);
+    for w in ranked.windows(2) {
+        assert!(w[0].score.total_return >= w[1].score.total_return);
+    }
+
+    // 200 根K线：窗口 [0,80,120)、[40,120,160)、[80,160,200)
+    let windows = optimizer.walk_forward();
+    assert_eq!(windows.len(), 3);
+    assert_eq!((windows[2].start, windows[2].split, windows[2].end), (80, 160, 200));
+}
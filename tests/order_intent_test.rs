@@ -0,0 +1,222 @@
+use aqt_stock::backtest::market::BacktestMarket;
+use aqt_stock::config::config::{Configs, PaperConfig};
+use aqt_stock::models::order::{Order, OrderIntent};
+use aqt_stock::services::broker::Broker;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::config::config::SymbolConfig;
+use aqt_stock::strategys::state::{PlacedOrder, StateStore, StrategyState};
+use aqt_stock::strategys::strategy::Strategy;
+use aqt_stock::strategys::vecor_v1::VecorStrategy;
+use longport::trade::{OrderSide, OrderStatus, OrderType};
+use longport::{decimal, Decimal};
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+mod common;
+use common::{quote_at, temp_dir};
+
+type Vecor = VecorStrategy<PaperBroker, BacktestMarket>;
+
+fn paper() -> PaperBroker {
+    PaperBroker::new(&PaperConfig {
+        initial_cash: 10000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    })
+}
+
+fn order(status: OrderStatus, submitted_at: OffsetDateTime) -> Order {
+    Order {
+        order_id: "1".to_string(),
+        symbol: "AAPL.US".to_string(),
+        side: OrderSide::Buy,
+        status,
+        order_type: OrderType::LO,
+        quantity: decimal!(10.0),
+        executed_quantity: Decimal::ZERO,
+        price: Some(decimal!(100.0)),
+        executed_price: None,
+        submitted_at,
+        remark: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn cooldown_blocks_orders_and_cancels_stale_ones_separately() {
+    let t0 = OffsetDateTime::from_unix_timestamp(1_704_157_200).unwrap(); // 2024-01-02 01:00 UTC
+    let now = (t0 + time::Duration::hours(3)).unix_timestamp();
+    let filled = [order(OrderStatus::Filled, t0)];
+    assert!(!Vecor::handler_orders(&filled, "AAPL.US", now, 36000));
+    assert!(Vecor::handler_orders(&filled, "AAPL.US", now, 2 * 3600));
+    assert!(Vecor::handler_orders(&filled, "MSFT.US", now, 36000));
+    // 冷却时间已过，但仍有未完成的订单
+    assert!(!Vecor::handler_orders(&[order(OrderStatus::New, t0)], "AAPL.US", now, 2 * 3600));
+
+    let broker = paper();
+    broker.on_market_data(&quote_at("AAPL.US", 100.0, 1000, t0));
+    // 委托价低于市价，订单一直未成交
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(90.0), decimal!(1.0)).await.unwrap();
+    let orders = broker.get_today_orders("AAPL.US").await.unwrap();
+    // 冷却时间内不撤单
+    Vecor::cancel_stale_orders(&broker, &orders, "AAPL.US", now, 36000).await;
+    assert_eq!(broker.get_today_orders("AAPL.US").await.unwrap().len(), 1);
+    Vecor::cancel_stale_orders(&broker, &orders, "AAPL.US", now, 2 * 3600).await;
+    assert_eq!(broker.get_today_orders("AAPL.US").await.unwrap()[0].status, OrderStatus::Canceled);
+}
+
+#[tokio::test]
+async fn intents_are_submitted_once_per_key() {
+    let t0 = OffsetDateTime::from_unix_timestamp(1_704_207_600).unwrap(); // 2024-01-02 15:00 UTC
+    let broker = paper();
+    broker.on_market_data(&quote_at("AAPL.US", 100.0, 1000, t0));
+    let intent = OrderIntent::new("main", "AAPL.US", 1704207600, "buy", OrderSide::Buy, decimal!(90.0), decimal!(1.0));
+    assert_eq!(intent.key, "main:AAPL.US:1704207600:buy");
+    broker.submit_intent(&intent).await.unwrap();
+    // 另一个策略在同一根K线的同一个操作
+    let other = OrderIntent::new("swing", "AAPL.US", 1704207600, "buy", OrderSide::Buy, decimal!(90.0), decimal!(1.0));
+    assert_ne!(other.key, intent.key);
+    broker.submit_intent(&other).await.unwrap();
+    let orders = broker.get_today_orders("AAPL.US").await.unwrap();
+    assert_eq!(orders[0].remark, intent.key);
+
+    // 提交后未保存状态就退出，重启对账时按备注找回本策略的订单，同一个意图不会再次提交
+    let mut state = StrategyState::default();
+    assert!(!state.has_intent(&intent.key));
+    assert!(state.reconcile("main", &orders, t0.unix_timestamp()).is_empty());
+    assert!(state.has_intent(&intent.key));
+    assert!(!state.has_intent(&other.key));
+    assert_eq!(state.orders.len(), 1);
+    assert_eq!(state.orders[0].order_id, orders[0].order_id);
+    assert!(!state.has_intent("main:AAPL.US:1704207600:sell"));
+    assert!(!state.has_intent(""));
+}
+
+#[tokio::test]
+async fn partially_filled_intents_survive_restart() {
+    let dir = temp_dir("aqt_partial_intent");
+    let t0 = OffsetDateTime::from_unix_timestamp(1_704_207_600).unwrap();
+    let broker = Arc::new(PaperBroker::new(&PaperConfig {
+        initial_cash: 10000.0,
+        currency: "USD".to_string(),
+        participation: Some(0.1),
+        short_margin: None,
+    }));
+    broker.on_market_data(&quote_at("AAPL.US", 100.0, 1000, t0));
+
+    // 两笔意图都只成交一部分，其中一笔随后被撤销
+    let canceled = OrderIntent::new("main", "AAPL.US", 1704200400, "buy", OrderSide::Buy, decimal!(100.0), decimal!(20.0));
+    let first = broker.submit_intent(&canceled).await.unwrap();
+    broker.on_market_data(&quote_at("AAPL.US", 100.0, 1100, t0));
+    broker.cancel_order(first.order_id.clone()).await.unwrap();
+    let working = OrderIntent::new("main", "AAPL.US", 1704204000, "buy", OrderSide::Buy, decimal!(100.0), decimal!(20.0));
+    let second = broker.submit_intent(&working).await.unwrap();
+    broker.on_market_data(&quote_at("AAPL.US", 100.0, 1200, t0));
+    let statuses: Vec<OrderStatus> = broker.get_today_orders("AAPL.US").await.unwrap().iter().map(|o| o.status).collect();
+    assert_eq!(statuses, vec![OrderStatus::Canceled, OrderStatus::PartialFilled]);
+
+    let store = StateStore::for_strategy(&dir, "main");
+    let mut state = StrategyState::default();
+    let now = t0.unix_timestamp();
+    state.record_order(PlacedOrder::from_intent(first.order_id, &canceled, now));
+    state.record_order(PlacedOrder::from_intent(second.order_id, &working, now));
+    store.save(&state).unwrap();
+
+    // 重启对账后两笔订单都保留，幂等键不会丢失
+    let mut sym = SymbolConfig::new();
+    sym.symbol = "AAPL.US".to_string();
+    let mut strategy = Vecor::with_config(broker, Arc::new(BacktestMarket::new()), vec![sym], Default::default())
+        .with_name("main")
+        .with_state(store);
+    strategy.run().await.unwrap();
+    let orders = &strategy.state().orders;
+    assert_eq!(orders.len(), 2);
+    assert!(orders.iter().all(|o| o.executed_quantity == decimal!(10.0)));
+    assert_eq!(orders.iter().map(|o| o.active).collect::<Vec<_>>(), vec![false, true]);
+    assert!(strategy.state().has_intent(&canceled.key));
+    assert!(strategy.state().has_intent(&working.key));
+}
+
+#[tokio::test]
+async fn exits_are_keyed_by_closed_bar_and_reason() {
+    let dir = temp_dir("aqt_exit_intent");
+    let t0 = OffsetDateTime::from_unix_timestamp(1_704_207_600).unwrap();
+    let broker = Arc::new(paper());
+    broker.on_market_data(&quote_at("AAPL.US", 100.0, 1000, t0));
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(10.0)).await.unwrap();
+    broker.on_market_data(&quote_at("AAPL.US", 100.0, 1000, t0));
+    assert_eq!(broker.stock_positions().await.unwrap()[0].quantity, decimal!(10.0));
+
+    // 上次运行已处理到 1704204000 收盘的K线
+    let store = StateStore::for_strategy(&dir, "main");
+    let mut state = StrategyState::default();
+    state.last_bars.insert("AAPL.US".to_string(), 1_704_204_000);
+    store.save(&state).unwrap();
+
+    let mut sym = SymbolConfig::new();
+    sym.symbol = "AAPL.US".to_string();
+    sym.sl_ratio = 3;
+    let market = Arc::new(BacktestMarket::new());
+    let mut strategy = Vecor::with_config(broker.clone(), market, vec![sym], Default::default())
+        .with_name("main")
+        .with_state(store);
+    strategy.run().await.unwrap();
+
+    // 同一根K线内多笔行情触发止损，只按收盘K线和原因提交一次
+    strategy.execute(&quote_at("AAPL.US", 96.0, 1000, t0 + time::Duration::seconds(10))).await.unwrap();
+    strategy.execute(&quote_at("AAPL.US", 95.0, 1000, t0 + time::Duration::seconds(20))).await.unwrap();
+    let exits: Vec<String> = broker
+        .get_today_orders("AAPL.US")
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|o| o.side == OrderSide::Sell)
+        .map(|o| o.remark)
+        .collect();
+    assert_eq!(exits, vec!["main:AAPL.US:1704204000:exit_stop_loss".to_string()]);
+}
+
+#[test]
+fn cooldown_from_symbol_config() {
+    let cfg: Configs = serde_yaml::from_str(
+        r#"
+symbols:
+  - { symbol: AAPL.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3, cooldown_secs: 7200 }
+  - { symbol: SPY.US, symbol_type: AMEX, volume: 0.1, period: 1d, tp_ratio: 5, sl_ratio: 3 }
+"#,
+    )
+    .unwrap();
+    assert_eq!(cfg.symbols[0].cooldown_secs, 7200);
+    assert_eq!(cfg.symbols[1].cooldown_secs, 36000);
+}
+
+#[tokio::test]
+async fn entry_fills_are_protected_before_the_next_bar() {
+    let dir = temp_dir("aqt_entry_sync");
+    let t0 = OffsetDateTime::from_unix_timestamp(1_704_207_600).unwrap();
+    let broker = Arc::new(paper());
+    broker.on_market_data(&quote_at("AAPL.US", 100.0, 1000, t0));
+    // 开仓订单委托价低于市价，重启时仍未成交
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(80.0), decimal!(10.0)).await.unwrap();
+    let store = StateStore::for_strategy(&dir, "main");
+    let mut state = StrategyState::default();
+    state.record_order(PlacedOrder::new(resp.order_id, "AAPL.US", OrderSide::Buy, decimal!(84.0), decimal!(10.0), t0.unix_timestamp()));
+    store.save(&state).unwrap();
+
+    let mut sym = SymbolConfig::new();
+    sym.symbol = "AAPL.US".to_string();
+    sym.sl_ratio = 3;
+    let mut strategy = Vecor::with_config(broker.clone(), Arc::new(BacktestMarket::new()), vec![sym], Default::default())
+        .with_name("main")
+        .with_state(store);
+    strategy.run().await.unwrap();
+
+    // 开仓订单成交后，下一笔行情同步持仓并触发止损，不等K线收盘
+    let filled = t0 + time::Duration::seconds(60);
+    broker.on_market_data(&quote_at("AAPL.US", 83.0, 1100, filled));
+    strategy.execute(&quote_at("AAPL.US", 80.0, 1200, filled + time::Duration::seconds(60))).await.unwrap();
+    let orders = broker.get_today_orders("AAPL.US").await.unwrap();
+    let exit = orders.iter().find(|o| o.side == OrderSide::Sell).unwrap();
+    assert!(exit.remark.ends_with(":exit_stop_loss"));
+    assert!(!strategy.state().has_open_entry("AAPL.US"));
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use aqt_stock::config::config::{Configs, PaperConfig};
+use aqt_stock::services::broker::Broker;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::services::pricing::{round_to_tick, tick_size, BestQuote, DepthBook, OrderPrice, Pricer, PricingPolicy};
+use longport::quote::{Depth, PushDepth};
+use longport::trade::{OrderSide, OrderType};
+use aqt_stock::utils::error::AqtError;
+use longport::{decimal, Decimal};
+use time::{Duration, OffsetDateTime};
+
+mod common;
+use common::quote;
+
+fn level(position: i32, price: f64) -> Depth {
+    Depth { position, price: Some(decimal!(price)), volume: 100, order_num: 1 }
+}
+
+#[test]
+fn policies_price_from_last_and_depth() {
+    let book = Arc::new(DepthBook::new());
+    let pricer = Pricer::default()
+        .with_policy("MKT.US", PricingPolicy::Market)
+        .with_policy("AAPL.US", PricingPolicy::BestQuote)
+        .with_policy("MSFT.US", PricingPolicy::Mid { bps: 10.0 })
+        .with_policy("TSLA.US", PricingPolicy::LastOffset { offset: 0.05 })
+        .with_book(book.clone());
+    let last = decimal!(100.0);
+
+    // 默认按现价上下浮动 5%
+    assert_eq!(pricer.price("SPY.US", OrderSide::Buy, last).unwrap(), OrderPrice::Limit(decimal!(105.0)));
+    assert_eq!(pricer.price("SPY.US", OrderSide::Sell, last).unwrap(), OrderPrice::Limit(decimal!(95.0)));
+    assert_eq!(pricer.price("MKT.US", OrderSide::Buy, last).unwrap(), OrderPrice::Market);
+    assert_eq!(pricer.price("TSLA.US", OrderSide::Sell, last).unwrap(), OrderPrice::Limit(decimal!(99.95)));
+    // 没有盘口时按默认方式委托
+    assert_eq!(pricer.price("AAPL.US", OrderSide::Buy, last).unwrap(), OrderPrice::Limit(decimal!(105.0)));
+
+    book.on_depth("AAPL.US", &PushDepth {
+        asks: vec![level(2, 100.06), level(1, 100.03)],
+        bids: vec![level(1, 99.98), level(2, 99.95)],
+    });
+    assert_eq!(book.best("AAPL.US"), Some(BestQuote { bid: Some(decimal!(99.98)), ask: Some(decimal!(100.03)) }));
+    assert_eq!(pricer.price("AAPL.US", OrderSide::Buy, last).unwrap(), OrderPrice::Limit(decimal!(100.03)));
+    assert_eq!(pricer.price("AAPL.US", OrderSide::Sell, last).unwrap(), OrderPrice::Limit(decimal!(99.98)));
+
+    // 中间价 200，加减 10 个基点后按最小价位取整
+    book.update("MSFT.US", BestQuote { bid: Some(decimal!(199.99)), ask: Some(decimal!(200.01)) });
+    assert_eq!(pricer.price("MSFT.US", OrderSide::Buy, decimal!(200.0)).unwrap(), OrderPrice::Limit(decimal!(200.2)));
+    assert_eq!(pricer.price("MSFT.US", OrderSide::Sell, decimal!(200.0)).unwrap(), OrderPrice::Limit(decimal!(199.8)));
+}
+
+#[test]
+fn stale_depth_and_non_positive_prices() {
+    let book = Arc::new(DepthBook::new());
+    let pricer = Pricer::default()
+        .with_policy("AAPL.US", PricingPolicy::BestQuote)
+        .with_policy("TSLA.US", PricingPolicy::LastOffset { offset: 150.0 })
+        .with_policy("SPY.US", PricingPolicy::Percent { pct: 100.0 })
+        .with_book(book.clone());
+    let last = decimal!(100.0);
+
+    // 盘口超过 10 秒未更新，按默认方式委托
+    let quote = BestQuote { bid: Some(decimal!(99.98)), ask: Some(decimal!(100.03)) };
+    book.update_at("AAPL.US", quote, OffsetDateTime::now_utc() - Duration::seconds(30));
+    assert_eq!(pricer.price("AAPL.US", OrderSide::Buy, last).unwrap(), OrderPrice::Limit(decimal!(105.0)));
+    book.update("AAPL.US", quote);
+    assert_eq!(pricer.price("AAPL.US", OrderSide::Buy, last).unwrap(), OrderPrice::Limit(decimal!(100.03)));
+    let patient = pricer.clone().with_max_quote_age(Duration::minutes(1));
+    book.update_at("AAPL.US", quote, OffsetDateTime::now_utc() - Duration::seconds(30));
+    assert_eq!(patient.price("AAPL.US", OrderSide::Buy, last).unwrap(), OrderPrice::Limit(decimal!(100.03)));
+
+    // 计算出的限价不是正数时拒绝
+    assert!(matches!(pricer.price("TSLA.US", OrderSide::Sell, last), Err(AqtError::Data(_))));
+    assert_eq!(pricer.price("TSLA.US", OrderSide::Buy, last).unwrap(), OrderPrice::Limit(decimal!(250.0)));
+    assert!(matches!(pricer.price("SPY.US", OrderSide::Sell, last), Err(AqtError::Data(_))));
+    assert!(matches!(pricer.price("QQQ.US", OrderSide::Buy, Decimal::ZERO), Err(AqtError::Data(_))));
+}
+
+#[test]
+fn prices_round_to_tick_size() {
+    assert_eq!(tick_size("AAPL.US", decimal!(150.0)), decimal!(0.01));
+    assert_eq!(tick_size("SNDL.US", decimal!(0.5)), decimal!(0.0001));
+    assert_eq!(tick_size("700.HK", decimal!(350.0)), decimal!(0.2));
+    assert_eq!(tick_size("700.HK", decimal!(8000.0)), decimal!(5));
+    // 买入向下取整，卖出向上取整
+    assert_eq!(round_to_tick(decimal!(350.33), decimal!(0.2), OrderSide::Buy), decimal!(350.2));
+    assert_eq!(round_to_tick(decimal!(350.33), decimal!(0.2), OrderSide::Sell), decimal!(350.4));
+    let pricer = Pricer::default().with_policy("700.HK", PricingPolicy::LastOffset { offset: 0.1 });
+    assert_eq!(pricer.price("700.HK", OrderSide::Buy, decimal!(350.0)).unwrap(), OrderPrice::Limit(decimal!(350.0)));
+}
+
+#[tokio::test]
+async fn paper_broker_fills_market_orders_at_next_quote() {
+    let paper = PaperBroker::new(&PaperConfig {
+        initial_cash: 10000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    })
+    .with_pricer(Pricer::default().with_policy("AAPL.US", PricingPolicy::Market));
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1000));
+    paper.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(10.0)).await.unwrap();
+    let orders = paper.get_today_orders("AAPL.US").await.unwrap();
+    assert_eq!((orders[0].order_type, orders[0].price), (OrderType::MO, None));
+
+    paper.on_market_data(&quote("AAPL.US", 101.0, 1100));
+    let fills = paper.fills();
+    assert_eq!((fills[0].price, fills[0].quantity), (decimal!(101.0), decimal!(10.0)));
+}
+
+#[test]
+fn pricing_from_symbol_config() {
+    let cfg: Configs = serde_yaml::from_str(
+        r#"
+symbols:
+  - { symbol: AAPL.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3, pricing: { kind: mid, bps: 5 } }
+  - { symbol: SPY.US, symbol_type: AMEX, volume: 0.1, period: 1d, tp_ratio: 5, sl_ratio: 3, pricing: { kind: market }, tick_size: 0.05 }
+  - { symbol: QQQ.US, symbol_type: NASDAQ, volume: 0.1, period: 1d, tp_ratio: 5, sl_ratio: 3 }
+"#,
+    )
+    .unwrap();
+    assert_eq!(cfg.symbols[0].pricing, PricingPolicy::Mid { bps: 5.0 });
+    assert_eq!(cfg.symbols[1].pricing, PricingPolicy::Market);
+    assert_eq!(cfg.symbols[1].tick_size, Some(0.05));
+    assert_eq!(cfg.symbols[2].pricing, PricingPolicy::Percent { pct: 5.0 });
+    let pricer = Pricer::from_config(&cfg);
+    assert_eq!(pricer.depth_symbols(), vec!["AAPL.US".to_string()]);
+}
@@ -0,0 +1,115 @@
+use aqt_stock::config::config::PaperConfig;
+use aqt_stock::services::broker::Broker;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::utils::error::AqtError;
+use longport::trade::{OrderSide, OrderStatus};
+use longport::{decimal, Decimal};
+
+mod common;
+use common::quote;
+
+fn paper(participation: Option<f64>) -> PaperBroker {
+    PaperBroker::new(&PaperConfig {
+        initial_cash: 10000.0,
+        currency: "USD".to_string(),
+        participation,
+        short_margin: None,
+    })
+}
+
+#[tokio::test]
+async fn paper_buy_then_sell() {
+    let broker = paper(None);
+    broker.on_market_data(&quote("AAPL.US", 100.0, 1000));
+
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(10.0)).await.unwrap();
+    assert!(!resp.order_id.is_empty());
+    broker.on_market_data(&quote("AAPL.US", 101.0, 1100));
+
+    let orders = broker.get_today_orders("AAPL.US").await.unwrap();
+    assert_eq!(orders[0].status, OrderStatus::Filled);
+    let positions = broker.stock_positions().await.unwrap();
+    assert_eq!(positions[0].quantity, decimal!(10.0));
+    assert_eq!(positions[0].cost_price, decimal!(101.0));
+    assert_eq!(broker.cash(), decimal!(8990.0));
+
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Sell, decimal!(110.0), decimal!(10.0)).await.unwrap();
+    assert!(!resp.order_id.is_empty());
+    broker.on_market_data(&quote("AAPL.US", 110.0, 1200));
+    assert!(broker.stock_positions().await.unwrap().is_empty());
+    assert_eq!(broker.cash(), decimal!(10090.0));
+    assert_eq!(broker.fills().len(), 2);
+}
+
+#[tokio::test]
+async fn paper_partial_fill_and_cancel() {
+    let broker = paper(Some(0.1));
+    broker.on_market_data(&quote("TSLA.US", 50.0, 1000));
+    let resp = broker.submit_order("TSLA.US".to_string(), OrderSide::Buy, decimal!(50.0), decimal!(20.0)).await.unwrap();
+
+    // 成交量增加 100，按 10% 参与率只能成交 10 股
+    broker.on_market_data(&quote("TSLA.US", 50.0, 1100));
+    let orders = broker.get_today_orders("TSLA.US").await.unwrap();
+    assert_eq!(orders[0].status, OrderStatus::PartialFilled);
+    assert_eq!(orders[0].executed_quantity, decimal!(10.0));
+
+    broker.cancel_order(resp.order_id).await.unwrap();
+    broker.on_market_data(&quote("TSLA.US", 50.0, 2000));
+    let balance = broker.account_balance().await.unwrap();
+    assert_eq!(balance[0].cash_infos[0].frozen_cash, Decimal::ZERO);
+    assert_eq!(broker.stock_positions().await.unwrap()[0].quantity, decimal!(10.0));
+}
+
+#[tokio::test]
+async fn paper_rejects_unaffordable_orders() {
+    let broker = paper(None);
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1000.0)).await;
+    assert!(matches!(resp, Err(AqtError::ApiRejected { .. })));
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Sell, decimal!(100.0), decimal!(1.0)).await;
+    assert!(matches!(resp, Err(AqtError::ApiRejected { .. })));
+    assert!(broker.cancel_order("PAPER-404".to_string()).await.is_err());
+}
+
+#[tokio::test]
+async fn paper_short_then_cover() {
+    let broker = PaperBroker::new(&PaperConfig {
+        initial_cash: 10000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: Some(0.5),
+    });
+    broker.on_market_data(&quote("AAPL.US", 100.0, 1000));
+    // 卖出委托价 95，净资产 10000 按 50% 保证金最多可卖空 210 股
+    let max = broker.estimate_max_quantity("AAPL.US", OrderSide::Sell, decimal!(100.0)).await.unwrap();
+    assert_eq!(max, decimal!(210));
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Sell, decimal!(100.0), decimal!(1000.0)).await;
+    assert!(matches!(resp, Err(AqtError::ApiRejected { .. })));
+
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Sell, decimal!(100.0), decimal!(50.0)).await.unwrap();
+    broker.on_market_data(&quote("AAPL.US", 100.0, 1100));
+    let positions = broker.stock_positions().await.unwrap();
+    assert_eq!(positions[0].quantity, decimal!(-50.0));
+    assert!(positions[0].is_short());
+    assert_eq!(positions[0].cost_price, decimal!(100.0));
+    assert_eq!(broker.cash(), decimal!(15000.0));
+    // 卖空所得冻结为保证金，不增加购买力
+    let balance = &broker.account_balance().await.unwrap()[0];
+    assert_eq!(balance.buy_power, decimal!(10000.0));
+    assert_eq!(balance.cash_infos[0].frozen_cash, decimal!(5000.0));
+    broker.on_market_data(&quote("MSFT.US", 100.0, 1000));
+    let resp = broker.submit_order("MSFT.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(100.0)).await;
+    assert!(matches!(resp, Err(AqtError::ApiRejected { .. })));
+
+    // 价格下跌后空头盈利
+    broker.on_market_data(&quote("AAPL.US", 90.0, 1200));
+    assert_eq!(broker.equity(), decimal!(10500.0));
+    // 平空可以使用保证金：委托价 94.5，50 股平空后剩余资金继续买入
+    let max = broker.estimate_max_quantity("AAPL.US", OrderSide::Buy, decimal!(90.0)).await.unwrap();
+    assert_eq!(max, decimal!(158));
+
+    // 买入平空
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(90.0), decimal!(50.0)).await.unwrap();
+    broker.on_market_data(&quote("AAPL.US", 90.0, 1300));
+    assert!(broker.stock_positions().await.unwrap().is_empty());
+    assert_eq!(broker.cash(), decimal!(10500.0));
+}
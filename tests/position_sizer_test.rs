@@ -0,0 +1,99 @@
+use aqt_stock::config::config::Configs;
+use aqt_stock::indicators::atr::atr;
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::strategys::sizer::{SizerConfig, SizingContext, TradeStats};
+use longport::{decimal, Decimal};
+
+/// 每根K线振幅固定为 `range`
+fn candles(count: usize, range: f64) -> Vec<Candle> {
+    (0..count)
+        .map(|i| Candle {
+            timestamp: i as u64 * 60,
+            open: 100.0,
+            high: 100.0 + range / 2.0,
+            low: 100.0 - range / 2.0,
+            close: 100.0,
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn quantity(config: &SizerConfig, candles: &[Candle], stats: &TradeStats) -> Decimal {
+    config.build(0.1).quantity(&SizingContext {
+        capital: decimal!(10000.0),
+        price: decimal!(30.0),
+        candles,
+        stats,
+    })
+}
+
+#[test]
+fn fixed_fraction_and_amount() {
+    let stats = TradeStats::default();
+    // 默认按 volume 比例开仓并向上取整，与原来的下单数量一致
+    assert_eq!(quantity(&SizerConfig::default(), &[], &stats), decimal!(34.0));
+    assert_eq!(quantity(&SizerConfig::FixedAmount { amount: 1000.0 }, &[], &stats), decimal!(33.0));
+}
+
+#[test]
+fn atr_volatility_targets_risk() {
+    let list = candles(20, 2.0);
+    assert_eq!(atr(&list, 14), Some(2.0));
+    assert_eq!(atr(&list[..14], 14), None);
+
+    // 亏损 1% 资金 = 100，止损距离 2 * ATR = 4
+    let config = SizerConfig::AtrVolatility { risk_pct: 1.0, period: 14, multiple: 2.0 };
+    assert_eq!(quantity(&config, &list, &TradeStats::default()), decimal!(25.0));
+    // K线不足时不开仓
+    assert_eq!(quantity(&config, &list[..5], &TradeStats::default()), Decimal::ZERO);
+    // 波动很小时不超过资金按下单余量可买的数量 floor(10000 / (30 * 1.05))
+    assert_eq!(quantity(&config, &candles(20, 0.01), &TradeStats::default()), decimal!(317.0));
+    // 止损倍数不是正数或 ATR 为 0 时不开仓
+    let flat = SizerConfig::AtrVolatility { risk_pct: 1.0, period: 14, multiple: 0.0 };
+    assert_eq!(quantity(&flat, &list, &TradeStats::default()), Decimal::ZERO);
+    let negative = SizerConfig::AtrVolatility { risk_pct: 1.0, period: 14, multiple: -2.0 };
+    assert_eq!(quantity(&negative, &list, &TradeStats::default()), Decimal::ZERO);
+    assert_eq!(quantity(&config, &candles(20, 0.0), &TradeStats::default()), Decimal::ZERO);
+}
+
+#[test]
+fn kelly_uses_trade_history() {
+    let config = SizerConfig::Kelly { multiplier: 0.5, cap: 0.25, min_trades: 4, fallback: None };
+    let mut stats = TradeStats::default();
+    for pnl in [200.0, 200.0, -100.0] {
+        stats.record(pnl);
+    }
+    // 交易次数不足时按 volume 比例
+    assert_eq!(quantity(&config, &[], &stats), decimal!(33.0));
+
+    stats.record(-100.0);
+    // 胜率 50%，盈亏比 2，凯利比例 0.25，减半后 0.125
+    assert_eq!(stats.kelly(), Some(0.25));
+    assert_eq!(quantity(&config, &[], &stats), decimal!(41.0));
+
+    // 没有正期望时不开仓
+    for _ in 0..4 {
+        stats.record(-300.0);
+    }
+    assert_eq!(quantity(&config, &[], &stats), Decimal::ZERO);
+}
+
+#[test]
+fn sizer_from_symbol_config() {
+    let cfg: Configs = serde_yaml::from_str(
+        r#"
+symbols:
+  - { symbol: AAPL.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3, sizer: { kind: atr_volatility, risk_pct: 0.5 } }
+  - { symbol: MSFT.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3, sizer: { kind: kelly, fallback: 0.05 } }
+  - { symbol: SPY.US, symbol_type: AMEX, volume: 0.1, period: 1d, tp_ratio: 5, sl_ratio: 3 }
+"#,
+    )
+    .unwrap();
+    let symbols = cfg.symbols;
+    assert_eq!(symbols[0].sizer, SizerConfig::AtrVolatility { risk_pct: 0.5, period: 14, multiple: 2.0 });
+    assert_eq!(
+        symbols[1].sizer,
+        SizerConfig::Kelly { multiplier: 0.5, cap: 0.25, min_trades: 20, fallback: Some(0.05) }
+    );
+    assert_eq!(symbols[2].sizer, SizerConfig::FixedFraction);
+}
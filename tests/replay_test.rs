@@ -0,0 +1,48 @@
+use std::fs;
+use aqt_stock::collect::recorder::QuoteRecorder;
+use aqt_stock::collect::replayer::{Pacing, QuoteReplayer};
+use longport::decimal;
+use tokio::sync::mpsc;
+
+mod common;
+use common::quote;
+
+#[tokio::test]
+async fn replay_recorded_quotes_in_order() {
+    let path = std::env::temp_dir().join("aqt_replay_test/quotes.jsonl");
+    let _ = fs::remove_file(&path);
+    {
+        let recorder = QuoteRecorder::new(&path, false).unwrap();
+        recorder.record_quote(&quote("AAPL.US", 100.0, 100));
+        recorder.record_quote(&quote("TSLA.US", 200.0, 100));
+        recorder.record_quote(&quote("AAPL.US", 101.5, 100));
+    }
+    // 模拟进程中断时写了一半的最后一行
+    let mut content = fs::read_to_string(&path).unwrap();
+    content.push_str("{\"type\":\"quote\",\"at\":");
+    fs::write(&path, content).unwrap();
+
+    let replayer = QuoteReplayer::load(&path).unwrap();
+    assert_eq!(replayer.quotes().count(), 3);
+
+    for pacing in [Pacing::AsFastAsPossible, Pacing::Accelerated(1000.0)] {
+        let (sender, mut receiver) = mpsc::channel(10);
+        assert_eq!(replayer.replay(&sender, pacing).await, 3);
+        drop(sender);
+        let mut prices = Vec::new();
+        while let Some(data) = receiver.recv().await {
+            prices.push((data.symbol, data.price));
+        }
+        assert_eq!(
+            prices,
+            vec![
+                ("AAPL.US".to_string(), decimal!(100.0)),
+                ("TSLA.US".to_string(), decimal!(200.0)),
+                ("AAPL.US".to_string(), decimal!(101.5)),
+            ]
+        );
+    }
+    assert_eq!(Pacing::parse("10"), Some(Pacing::Accelerated(10.0)));
+    assert_eq!(Pacing::parse("realtime"), Some(Pacing::RealTime));
+    assert_eq!(Pacing::parse("0"), None);
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use aqt_stock::config::config::{Configs, PaperConfig, RiskConfig};
+use aqt_stock::services::broker::Broker;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::services::risk::{correlation, RiskCheckedBroker, RiskReason};
+use aqt_stock::utils::error::AqtError;
+use longport::trade::OrderSide;
+use longport::decimal;
+
+mod common;
+use common::quote;
+
+fn risk_broker(limits: RiskConfig) -> (Arc<PaperBroker>, RiskCheckedBroker<PaperBroker>) {
+    let paper = Arc::new(PaperBroker::new(&PaperConfig {
+        initial_cash: 10000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    }));
+    (paper.clone(), RiskCheckedBroker::new(paper, limits))
+}
+
+fn rejected(result: Result<impl std::fmt::Debug, AqtError>) -> RiskReason {
+    match result {
+        Err(AqtError::RiskRejected(reason)) => reason,
+        other => panic!("订单没有被风控拒绝: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rejects_orders_over_notional_and_position_limits() {
+    let (paper, broker) = risk_broker(RiskConfig {
+        max_order_notional: Some(2000.0),
+        max_symbol_notional: Some(2500.0),
+        max_positions: Some(1),
+        ..Default::default()
+    });
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1000));
+    paper.on_market_data(&quote("MSFT.US", 100.0, 1000));
+
+    let reason = rejected(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(30.0)).await);
+    assert_eq!(reason, RiskReason::OrderNotional { notional: decimal!(3000.0), limit: decimal!(2000.0) });
+    // 被拒绝的订单不会提交给券商
+    assert!(paper.get_today_orders("AAPL.US").await.unwrap().is_empty());
+
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(20.0)).await.unwrap();
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1100));
+    let reason = rejected(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(10.0)).await);
+    assert!(matches!(reason, RiskReason::SymbolNotional { .. }));
+    let reason = rejected(broker.submit_order("MSFT.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(1.0)).await);
+    assert_eq!(reason, RiskReason::MaxPositions { count: 1, limit: 1 });
+
+    // 减仓不受限制
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Sell, decimal!(100.0), decimal!(20.0)).await.unwrap();
+    let rejections = broker.rejections();
+    assert_eq!(rejections.len(), 3);
+    assert_eq!(rejections[2].symbol, "MSFT.US");
+}
+
+#[tokio::test]
+async fn rejects_after_daily_order_count_and_loss() {
+    let (paper, broker) = risk_broker(RiskConfig {
+        max_gross_exposure: Some(5000.0),
+        max_daily_orders: Some(2),
+        max_daily_loss: Some(300.0),
+        ..Default::default()
+    });
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1000));
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(10.0)).await.unwrap();
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1100));
+
+    // 下跌后当日亏损 500，超过上限
+    paper.on_market_data(&quote("AAPL.US", 50.0, 1200));
+    let reason = rejected(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(50.0), decimal!(10.0)).await);
+    assert_eq!(reason, RiskReason::DailyLoss { loss: decimal!(500.0), limit: decimal!(300.0) });
+    let reason = rejected(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(50.0), decimal!(100.0)).await);
+    assert!(matches!(reason, RiskReason::GrossExposure { .. }));
+
+    // 止损平仓仍然可以提交，之后当日下单次数达到上限
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Sell, decimal!(50.0), decimal!(10.0)).await.unwrap();
+    paper.on_market_data(&quote("AAPL.US", 50.0, 1300));
+    let reason = rejected(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(50.0), decimal!(1.0)).await);
+    assert_eq!(reason, RiskReason::DailyOrders { count: 2, limit: 2 });
+}
+
+#[tokio::test]
+async fn canceled_orders_count_towards_daily_limit() {
+    let (paper, broker) = risk_broker(RiskConfig {
+        max_daily_orders: Some(2),
+        ..Default::default()
+    });
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1000));
+    // 委托价低于市价，撤单后重新下单
+    for _ in 0..2 {
+        let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(80.0), decimal!(1.0)).await.unwrap();
+        broker.cancel_order(resp.order_id).await.unwrap();
+    }
+    let reason = rejected(broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(80.0), decimal!(1.0)).await);
+    assert_eq!(reason, RiskReason::DailyOrders { count: 2, limit: 2 });
+}
+
+#[tokio::test]
+async fn rejects_group_concentration_and_correlated_entries() {
+    let (paper, broker) = risk_broker(RiskConfig {
+        group_limits: HashMap::from([("tech".to_string(), 3000.0)]),
+        max_correlation: Some(0.8),
+        correlation_window: 5,
+        ..Default::default()
+    });
+    let groups = HashMap::from([
+        ("AAPL.US".to_string(), vec!["tech".to_string()]),
+        ("MSFT.US".to_string(), vec!["tech".to_string()]),
+        ("XOM.US".to_string(), vec!["energy".to_string()]),
+        ("GLD.US".to_string(), vec![]),
+    ]);
+    let broker = broker.with_groups(groups);
+    for symbol in ["AAPL.US", "MSFT.US", "XOM.US", "GLD.US"] {
+        paper.on_market_data(&quote(symbol, 100.0, 1000));
+    }
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(20.0)).await.unwrap();
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1100));
+
+    // 同一分组合计超过上限
+    let reason = rejected(broker.submit_order("MSFT.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(15.0)).await);
+    let limit = decimal!(3000.0);
+    assert_eq!(reason, RiskReason::GroupExposure { group: "tech".to_string(), exposure: decimal!(3500.0), limit });
+
+    // XOM 与 AAPL 同涨同跌，GLD 走势相反
+    broker.update_closes("AAPL.US", &[100.0, 102.0, 101.0, 104.0, 103.0, 106.0]);
+    broker.update_closes("XOM.US", &[50.0, 51.0, 50.6, 52.0, 51.4, 53.0]);
+    broker.update_closes("GLD.US", &[80.0, 79.0, 79.8, 78.0, 78.6, 77.0]);
+    let reason = rejected(broker.submit_order("XOM.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(5.0)).await);
+    assert!(matches!(reason, RiskReason::Correlation { correlation, .. } if correlation > 0.9));
+    broker.submit_order("GLD.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(5.0)).await.unwrap();
+    assert!(correlation(&[0.01, -0.02, 0.03], &[0.01, 0.01, 0.01]).is_none());
+}
+
+#[tokio::test]
+async fn correlation_is_weighted_by_position_notional() {
+    let (paper, broker) = risk_broker(RiskConfig {
+        max_correlation: Some(0.5),
+        correlation_window: 5,
+        ..Default::default()
+    });
+    for symbol in ["AAPL.US", "XOM.US", "GLD.US"] {
+        paper.on_market_data(&quote(symbol, 100.0, 1000));
+    }
+    // AAPL 持仓 4000，GLD 持仓 500
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(40.0)).await.unwrap();
+    broker.submit_order("GLD.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(5.0)).await.unwrap();
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1100));
+    paper.on_market_data(&quote("GLD.US", 100.0, 1100));
+
+    // XOM 与 AAPL 同涨同跌、与 GLD 相反，等权平均接近 0，按持仓金额加权后超过上限
+    broker.update_closes("AAPL.US", &[100.0, 102.0, 101.0, 104.0, 103.0, 106.0]);
+    broker.update_closes("XOM.US", &[50.0, 51.0, 50.6, 52.0, 51.4, 53.0]);
+    broker.update_closes("GLD.US", &[80.0, 79.0, 79.8, 78.0, 78.6, 77.0]);
+    let reason = rejected(broker.submit_order("XOM.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(5.0)).await);
+    assert!(matches!(reason, RiskReason::Correlation { correlation, .. } if correlation > 0.7 && correlation < 0.9));
+}
+
+#[test]
+fn risk_limits_from_config() {
+    let cfg: Configs = serde_yaml::from_str("risk:\n  max_order_notional: 5000\n  max_positions: 3\n").unwrap();
+    let risk = cfg.risk.unwrap();
+    assert_eq!(risk.max_order_notional, Some(5000.0));
+    assert_eq!(risk.max_positions, Some(3));
+    assert_eq!(risk.max_daily_loss, None);
+    assert_eq!(risk.currency, "USD");
+    assert!(risk.group_limits.is_empty());
+    assert_eq!(risk.max_correlation, None);
+    assert_eq!((risk.correlation_window, risk.correlation_period.as_str()), (30, "1d"));
+
+    let cfg: Configs = serde_yaml::from_str(
+        r#"
+symbols:
+  - { symbol: AAPL.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3, groups: [tech, mega_cap] }
+  - { symbol: SPY.US, symbol_type: AMEX, volume: 0.1, period: 1d, tp_ratio: 5, sl_ratio: 3 }
+risk:
+  group_limits: { tech: 20000 }
+  max_correlation: 0.7
+"#,
+    )
+    .unwrap();
+    let groups = cfg.symbol_groups();
+    assert_eq!(groups["AAPL.US"], vec!["tech".to_string(), "mega_cap".to_string()]);
+    assert!(groups["SPY.US"].is_empty());
+    let risk = cfg.risk.unwrap();
+    assert_eq!(risk.group_limits["tech"], 20000.0);
+    assert_eq!(risk.max_correlation, Some(0.7));
+}
+
+#[tokio::test]
+async fn daily_loss_baseline_is_persisted_across_restarts() {
+    let path = std::env::temp_dir().join("aqt_risk_daily_loss").join("risk_daily_loss.json");
+    let _ = std::fs::remove_file(&path);
+    let limits = RiskConfig { max_daily_loss: Some(300.0), ..Default::default() };
+    let (paper, broker) = risk_broker(limits.clone());
+    let broker = broker.with_state_file(&path);
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1000));
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(10.0)).await.unwrap();
+    paper.on_market_data(&quote("AAPL.US", 100.0, 1100));
+    assert_eq!(broker.day_start().unwrap().equity, decimal!(10000.0));
+
+    // 下跌后重启，基准仍是交易日开始时的净资产，而不是重启时已经亏损的净资产
+    paper.on_market_data(&quote("AAPL.US", 50.0, 1200));
+    let restarted = RiskCheckedBroker::new(paper.clone(), limits).with_state_file(&path);
+    assert_eq!(restarted.day_start(), broker.day_start());
+    let reason = rejected(restarted.submit_order("MSFT.US".to_string(), OrderSide::Buy, decimal!(10.0), decimal!(1.0)).await);
+    assert_eq!(reason, RiskReason::DailyLoss { loss: decimal!(500.0), limit: decimal!(300.0) });
+}
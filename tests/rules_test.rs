@@ -0,0 +1,118 @@
+use aqt_stock::computes::defult_rules::{CulRules, DefultRules};
+use aqt_stock::computes::profile::IndicatorProfile;
+use aqt_stock::computes::rules::{MajorityRules, QuorumRules, RuleConfig, UnanimityRules};
+use aqt_stock::indicators::candle::Candle;
+
+fn candles(n: usize) -> Vec<Candle> {
+    (0..n)
+        .map(|i| {
+            let close = 100.0 + 10.0 * (i as f64 / 8.0).sin();
+            Candle { symbol: None, timestamp: i as u64 * 3600, open: close - 0.5, high: close + 1.0, low: close - 1.0, close, volume: 1000.0 }
+        })
+        .collect()
+}
+
+fn named(votes: &[(&str, i64)]) -> Vec<(String, i64)> {
+    votes.iter().map(|(name, vote)| (name.to_string(), *vote)).collect()
+}
+
+#[test]
+fn sum_rules_keep_default_behaviour() {
+    let rules = RuleConfig::default().build(3);
+    let votes = named(&[("KDJ", 1), ("MACD", 1), ("UTBOT", 1)]);
+    assert_eq!(rules.cul_named_rules(votes.clone()), 1);
+    assert_eq!(DefultRules::default().cul_rules(votes.iter().map(|(_, v)| *v).collect()), 1);
+    assert_eq!(rules.cul_named_rules(named(&[("KDJ", -1), ("MACD", -1)])), 0);
+}
+
+#[test]
+fn weighted_rules_replace_indicator_weights() {
+    let yaml = r#"
+threshold: 3
+rules:
+  type: weighted
+  weights:
+    UTBOT: 3
+    CYC: 0
+indicators:
+  - kind: utbot
+    weight: 2
+  - kind: kdj
+    weight: 2
+  - kind: cyc
+"#;
+    let profile: IndicatorProfile = serde_yaml::from_str(yaml).unwrap();
+    let decision = profile.build(&candles(120), None, None).execute_rules();
+    let weights: Vec<i64> = decision.signals.iter().map(|s| s.weight).collect();
+    // 规则配置的权重代替指标权重，不会再乘一次
+    assert_eq!(weights, vec![3, 2, 0]);
+    let sum: i64 = decision.signals.iter().map(|s| s.weighted_vote()).sum();
+    assert_eq!(decision.signal, if sum >= 3 { 1 } else if sum <= -3 { -1 } else { 0 });
+    // 规则只按阈值求和
+    let rules = profile.rules.build(3);
+    assert_eq!(rules.cul_named_rules(named(&[("UTBOT", 3), ("KDJ", -1)])), 0);
+    assert_eq!(rules.cul_named_rules(named(&[("UTBOT", 3), ("KDJ", 0)])), 1);
+}
+
+#[test]
+fn majority_and_unanimity() {
+    assert_eq!(MajorityRules.cul_rules(vec![1, 2, 0]), 1);
+    assert_eq!(MajorityRules.cul_rules(vec![1, -1, 0]), 0);
+    assert_eq!(MajorityRules.cul_rules(vec![-1, -3, -1, 1]), -1);
+    assert_eq!(UnanimityRules.cul_rules(vec![1, 3, 1]), 1);
+    assert_eq!(UnanimityRules.cul_rules(vec![1, 3, 0]), 0);
+    assert_eq!(UnanimityRules.cul_rules(vec![-1, -1]), -1);
+    assert_eq!(UnanimityRules.cul_rules(vec![]), 0);
+}
+
+#[test]
+fn quorum_requires_enough_votes() {
+    let rules = QuorumRules { min_votes: 3, threshold: 2 };
+    // 投票之和达到阈值，但只有两个指标表态
+    assert_eq!(rules.cul_rules(vec![3, 1, 0, 0]), 0);
+    assert_eq!(rules.cul_rules(vec![3, 1, -1, 0]), 1);
+}
+
+#[test]
+fn veto_blocks_opposite_signals() {
+    let yaml = "type: veto\nvetoes: [Market Temperature]\ninner:\n  type: majority\n";
+    let config: RuleConfig = serde_yaml::from_str(yaml).unwrap();
+    let rules = config.build(3);
+    // 市场过热否决买入
+    assert_eq!(rules.cul_named_rules(named(&[("KDJ", 1), ("MACD", 1), ("Market Temperature", -2)])), 0);
+    // 同向或中性时不否决
+    assert_eq!(rules.cul_named_rules(named(&[("KDJ", 1), ("MACD", 1), ("Market Temperature", 0)])), 1);
+    assert_eq!(rules.cul_named_rules(named(&[("KDJ", -1), ("MACD", -1), ("Market Temperature", -2)])), -1);
+}
+
+#[test]
+fn veto_only_blocks_entries() {
+    let yaml = "type: veto\nvetoes: [Market Temperature]\ninner:\n  type: majority\n";
+    let rules: RuleConfig = serde_yaml::from_str(yaml).unwrap();
+    let rules = rules.build(3);
+    // 持有多头时卖出用于平仓，市场看多也不否决
+    assert_eq!(rules.cul_position_rules(named(&[("KDJ", -1), ("MACD", -1), ("Market Temperature", 2)]), 1), -1);
+    // 持有空头时买入用于平空，市场过热也不否决
+    assert_eq!(rules.cul_position_rules(named(&[("KDJ", 1), ("MACD", 1), ("Market Temperature", -2)]), -1), 1);
+    // 同方向加仓仍然否决
+    assert_eq!(rules.cul_position_rules(named(&[("KDJ", 1), ("MACD", 1), ("Market Temperature", -2)]), 1), 0);
+    assert_eq!(rules.cul_position_rules(named(&[("KDJ", -1), ("MACD", -1), ("Market Temperature", 2)]), 0), 0);
+}
+
+#[test]
+fn profile_selects_rules_from_config() {
+    let yaml = r#"
+threshold: 2
+rules:
+  type: quorum
+  min_votes: 2
+indicators:
+  - kind: kdj
+  - kind: cyc
+"#;
+    let profile: IndicatorProfile = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(profile.rules, RuleConfig::Quorum { min_votes: 2 });
+    assert_eq!(profile.threshold, 2);
+    // 未配置规则时默认求和
+    assert_eq!(IndicatorProfile::default().rules, RuleConfig::Sum);
+}
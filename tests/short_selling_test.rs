@@ -0,0 +1,67 @@
+use aqt_stock::backtest::market::BacktestMarket;
+use aqt_stock::config::config::{Configs, SymbolConfig};
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::models::position::Position;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::strategys::vecor_v1::VecorStrategy;
+use longport::decimal;
+
+type Vecor = VecorStrategy<PaperBroker, BacktestMarket>;
+
+fn candles(closes: &[f64]) -> Vec<Candle> {
+    closes
+        .iter()
+        .enumerate()
+        .map(|(i, close)| Candle { timestamp: i as u64 * 3600, close: *close, ..Default::default() })
+        .collect()
+}
+
+fn short(quantity: f64, cost: f64) -> Position {
+    Position {
+        symbol: "AAPL.US".to_string(),
+        quantity: decimal!(quantity),
+        available_quantity: decimal!(quantity),
+        cost_price: decimal!(cost),
+        ..Default::default()
+    }
+}
+
+fn symbol() -> SymbolConfig {
+    let mut sym = SymbolConfig::new();
+    sym.symbol = "AAPL.US".to_string();
+    sym.tp_ratio = 5;
+    sym.sl_ratio = 3;
+    sym.allow_short = true;
+    sym
+}
+
+#[test]
+fn short_positions_cover_on_take_profit() {
+    let sym = symbol();
+    // 止损由平仓管理处理
+    assert!(!Vecor::handler_cover_position(sym.clone(), &candles(&[100.0, 102.0, 103.5]), short(-10.0, 100.0)));
+    // 止盈：低于开空价格 5% 后开始反弹
+    assert!(Vecor::handler_cover_position(sym.clone(), &candles(&[100.0, 93.0, 94.0]), short(-10.0, 100.0)));
+    assert!(!Vecor::handler_cover_position(sym.clone(), &candles(&[100.0, 94.0, 93.0]), short(-10.0, 100.0)));
+    // 多头持仓不平空，空头持仓不触发多头止盈
+    assert!(!Vecor::handler_cover_position(sym.clone(), &candles(&[100.0, 102.0, 110.0]), short(10.0, 100.0)));
+    assert!(!Vecor::handler_close_position(sym, &candles(&[100.0, 120.0, 110.0]), short(-10.0, 100.0)));
+}
+
+#[test]
+fn allow_short_is_opt_in() {
+    let cfg: Configs = serde_yaml::from_str(
+        r#"
+symbols:
+  - { symbol: AAPL.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3, allow_short: true }
+  - { symbol: SPY.US, symbol_type: AMEX, volume: 0.1, period: 1d, tp_ratio: 5, sl_ratio: 3 }
+paper:
+  initial_cash: 10000
+  short_margin: 0.5
+"#,
+    )
+    .unwrap();
+    assert!(cfg.symbols[0].allow_short);
+    assert!(!cfg.symbols[1].allow_short);
+    assert_eq!(cfg.paper.unwrap().short_margin, Some(0.5));
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use aqt_stock::calculates::base_calculate::BaseCalculate;
+use aqt_stock::calculates::kdj_calculate::KdjCalculate;
+use aqt_stock::calculates::macd_calculate::MacdCalculate;
+use aqt_stock::calculates::signal::Signal;
+use aqt_stock::calculates::utbot_calculate::UTBotCalculate;
+use aqt_stock::computes::calculate::Calculate;
+use aqt_stock::computes::defult_rules::DefultRules;
+use aqt_stock::indicators::candle::Candle;
+use longport::trade::OrderSide;
+
+fn candles(n: usize) -> Arc<Vec<Candle>> {
+    let candles = (0..n)
+        .map(|i| {
+            let close = 100.0 + 10.0 * (i as f64 / 8.0).sin();
+            Candle {
+                symbol: Some("TEST.US".to_string()),
+                timestamp: i as u64 * 3600,
+                open: close - 0.5,
+                high: close + 1.0,
+                low: close - 1.0,
+                close,
+                volume: 1000.0,
+            }
+        })
+        .collect();
+    Arc::new(candles)
+}
+
+#[test]
+fn signal_builder_and_display() {
+    let signal = Signal::new("KDJ", -1)
+        .with_value("K", 85.0)
+        .with_value("D", 82.5)
+        .with_strength(1.5)
+        .with_reason("超买");
+    assert_eq!(signal.direction(), OrderSide::Sell);
+    assert_eq!(signal.strength, 1.0);
+    assert_eq!(signal.value("D"), Some(82.5));
+    assert_eq!(signal.value("J"), None);
+    assert_eq!(signal.to_string(), "KDJ 投票 -1 强度 1.00 [K=85.00, D=82.50] 超买");
+
+    let neutral = Signal::new("CYC", 0);
+    assert_eq!(neutral.direction(), OrderSide::Unknown);
+    assert_eq!(neutral.strength, 0.0);
+}
+
+#[test]
+fn calculators_expose_indicator_values() {
+    let candles = candles(120);
+
+    let kdj = KdjCalculate::new(candles.clone()).signal();
+    assert_eq!(kdj.name, "KDJ");
+    assert!(kdj.value("K").is_some() && kdj.value("D").is_some() && kdj.value("J").is_some());
+    assert!(!kdj.reason.is_empty());
+
+    let macd = MacdCalculate::new(candles.clone()).signal();
+    assert!(macd.value("Hist").is_some());
+
+    let utbot = UTBotCalculate::new(candles.clone());
+    let signal = utbot.signal();
+    assert!(signal.value("Stop").is_some());
+    // calculate 与信号的投票保持一致
+    assert_eq!(utbot.calculate(), signal.vote);
+}
+
+#[test]
+fn decision_keeps_component_signals() {
+    let candles = candles(120);
+    let mut calculate = Calculate::new(Box::new(DefultRules { threshold: 1 }));
+    calculate.add_weighted_calculator(Box::new(UTBotCalculate::new(candles.clone())), 2);
+    calculate.add_calculator(Box::new(KdjCalculate::new(candles.clone())));
+
+    let decision = calculate.execute_rules();
+    assert_eq!(decision.signals.len(), 2);
+    let utbot = decision.signal_of("UTBOT").unwrap();
+    assert_eq!(utbot.weight, 2);
+
+    let sum: i64 = decision.signals.iter().map(|s| s.weighted_vote()).sum();
+    let expected = if sum >= 1 { 1 } else if sum <= -1 { -1 } else { 0 };
+    assert_eq!(decision.signal, expected);
+
+    let explain = decision.explain();
+    assert!(explain.contains("UTBOT 投票"));
+    assert!(explain.contains("KDJ 投票"));
+}
@@ -0,0 +1,149 @@
+use std::sync::{Arc, Mutex};
+use aqt_stock::backtest::market::BacktestMarket;
+use aqt_stock::config::config::{Configs, PaperConfig};
+use aqt_stock::models::market::MarketData;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::strategys::multi_executor::MultiExecutor;
+use aqt_stock::strategys::registry::StrategyRegistry;
+use aqt_stock::strategys::strategy::Strategy;
+use aqt_stock::utils::error::AqtError;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+mod common;
+use common::quote;
+
+const CONFIG: &str = r#"
+strategies:
+  - name: main
+    symbols:
+      - { symbol: AAPL.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3 }
+  - name: echo
+    strategy: echo
+    params:
+      prefix: "echo"
+    symbols:
+      - { symbol: SPY.US, symbol_type: AMEX, volume: 0.1, period: 1d, tp_ratio: 5, sl_ratio: 3 }
+  - name: missing
+    strategy: grid
+    symbols: []
+"#;
+
+#[derive(Deserialize, Default)]
+struct EchoParams {
+    prefix: String,
+}
+
+/// 把收到的行情写入日志的测试策略
+struct EchoStrategy {
+    prefix: String,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Strategy for EchoStrategy {
+    type Broker = PaperBroker;
+    type Market = BacktestMarket;
+
+    fn new(_broker: Arc<PaperBroker>, _market: Arc<BacktestMarket>) -> Self {
+        EchoStrategy { prefix: String::new(), log: Arc::new(Mutex::new(Vec::new())) }
+    }
+
+    async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    async fn execute(&mut self, event: &MarketData) -> Result<(), Box<dyn std::error::Error>> {
+        self.log.lock().unwrap().push(format!("{}:{}", self.prefix, event.symbol));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+fn broker() -> Arc<PaperBroker> {
+    Arc::new(PaperBroker::new(&PaperConfig {
+        initial_cash: 100000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    }))
+}
+
+#[tokio::test]
+async fn registry_builds_strategies_by_name() {
+    let cfg: Configs = serde_yaml::from_str(CONFIG).unwrap();
+    let strategies = cfg.strategy_configs();
+    assert_eq!(strategies[0].strategy, "vecor_v1");
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut registry = StrategyRegistry::<PaperBroker, BacktestMarket>::new();
+    let echo_log = log.clone();
+    registry.register("echo", move |ctx| {
+        let params: EchoParams = ctx.params()?;
+        Ok(Box::new(EchoStrategy { prefix: params.prefix, log: echo_log.clone() }))
+    });
+    assert_eq!(registry.names(), vec!["echo", "vecor_v1"]);
+
+    let market = Arc::new(BacktestMarket::new());
+    let mut executor = MultiExecutor::new(16);
+    for strategy in &strategies[..2] {
+        let built = registry.build(strategy, broker(), market.clone(), &cfg.profiles).unwrap();
+        let symbols = strategy.symbols.iter().map(|s| s.symbol.clone()).collect();
+        executor.start(&strategy.name, symbols, built);
+    }
+
+    let (sender, receiver) = mpsc::channel(16);
+    sender.send(quote("SPY.US", 1.0, 0)).await.unwrap();
+    drop(sender);
+    executor.run(receiver, None).await;
+    assert_eq!(*log.lock().unwrap(), vec!["echo:SPY.US".to_string()]);
+
+    // 未注册的策略类型
+    let err = registry.build(&strategies[2], broker(), market, &cfg.profiles).err().unwrap();
+    assert!(matches!(err, AqtError::Config(ref msg) if msg.contains("grid")));
+}
+
+#[test]
+fn vecor_rejects_unknown_params() {
+    let cfg: Configs = serde_yaml::from_str(CONFIG).unwrap();
+    let registry = StrategyRegistry::<PaperBroker, BacktestMarket>::new();
+    let market = Arc::new(BacktestMarket::new());
+    let mut main = cfg.strategy_configs()[0].clone();
+
+    main.params = serde_yaml::from_str("{}").unwrap();
+    assert!(registry.build(&main, broker(), market.clone(), &cfg.profiles).is_ok());
+    main.params = serde_yaml::from_str("entry_sync_secs: 10").unwrap();
+    assert!(registry.build(&main, broker(), market.clone(), &cfg.profiles).is_ok());
+
+    // 不支持的参数不会被静默忽略
+    main.params = serde_yaml::from_str("threshold: 4").unwrap();
+    let err = registry.build(&main, broker(), market, &cfg.profiles).err().unwrap();
+    assert!(matches!(err, AqtError::Config(ref msg) if msg.contains("threshold")));
+}
+
+#[test]
+fn top_level_strategy_selects_default_strategy() {
+    let cfg: Configs = serde_yaml::from_str(
+        "strategy: echo\nparams:\n  prefix: top\nsymbols:\n  - { symbol: TQQQ.US, symbol_type: NASDAQ, volume: 0.1, period: 1h, tp_ratio: 5, sl_ratio: 3 }\n",
+    )
+    .unwrap();
+    let strategies = cfg.strategy_configs();
+    assert_eq!(strategies[0].strategy, "echo");
+    assert_eq!(strategies[0].params["prefix"].as_str(), Some("top"));
+
+    // 参数类型错误时返回配置错误
+    let mut registry = StrategyRegistry::<PaperBroker, BacktestMarket>::empty();
+    registry.register("echo", |ctx| {
+        let params: EchoParams = ctx.params()?;
+        Ok(Box::new(EchoStrategy { prefix: params.prefix, log: Arc::new(Mutex::new(Vec::new())) }))
+    });
+    let mut bad = strategies[0].clone();
+    bad.params = serde_yaml::from_str("prefix: [1, 2]").unwrap();
+    let market = Arc::new(BacktestMarket::new());
+    assert!(matches!(
+        registry.build(&bad, broker(), market, &cfg.profiles).err(),
+        Some(AqtError::Config(_))
+    ));
+}
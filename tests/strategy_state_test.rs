@@ -0,0 +1,284 @@
+use std::fs;
+use std::sync::Arc;
+use aqt_stock::backtest::market::BacktestMarket;
+use aqt_stock::computes::profile::{IndicatorConfig, IndicatorKind};
+use aqt_stock::config::config::{PaperConfig, SymbolConfig};
+use aqt_stock::indicators::candle::Candle;
+use aqt_stock::models::bar::{BarEvent, BarEventKind};
+use aqt_stock::models::market::MarketData;
+use aqt_stock::models::order::Order;
+use aqt_stock::models::position::Position;
+use aqt_stock::services::broker::Broker;
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::strategys::exit_manager::ExitReason;
+use aqt_stock::strategys::sizer::TradeStats;
+use aqt_stock::strategys::state::{PlacedOrder, StateStore, StrategyState};
+use aqt_stock::strategys::strategy::Strategy;
+use aqt_stock::strategys::vecor_v1::VecorStrategy;
+use aqt_stock::utils::error::AqtError;
+use longport::decimal;
+use longport::trade::{OrderSide, OrderStatus, OrderType};
+use time::OffsetDateTime;
+
+mod common;
+use common::temp_dir;
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn placed(order_id: &str, submitted_at: i64) -> PlacedOrder {
+    PlacedOrder::new(order_id.to_string(), "AAPL.US", OrderSide::Buy, decimal!(100.0), decimal!(10.0), submitted_at)
+}
+
+fn order(order_id: &str, status: OrderStatus, executed: f64) -> Order {
+    Order {
+        order_id: order_id.to_string(),
+        symbol: "AAPL.US".to_string(),
+        side: OrderSide::Buy,
+        status,
+        order_type: OrderType::LO,
+        quantity: decimal!(10.0),
+        executed_quantity: decimal!(executed),
+        price: Some(decimal!(100.0)),
+        executed_price: None,
+        submitted_at: OffsetDateTime::now_utc(),
+        remark: String::new(),
+    }
+}
+
+#[test]
+fn state_store_round_trip() {
+    let dir = temp_dir("aqt_state_store");
+    let store = StateStore::for_strategy(&dir, "main");
+    assert!(store.load().unwrap().is_none());
+
+    let mut state = StrategyState::default();
+    state.bar_driven.insert("AAPL.US".to_string());
+    state.last_bars.insert("AAPL.US".to_string(), 3600);
+    state.record_order(placed("1", 100));
+    store.save(&state).unwrap();
+    // 写入完成后不留下临时文件
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let loaded = store.load().unwrap().unwrap();
+    assert!(loaded.is_bar_driven("AAPL.US"));
+    assert!(!loaded.is_bar_driven("MSFT.US"));
+    assert!(loaded.is_processed("AAPL.US", 3600));
+    assert!(!loaded.is_processed("AAPL.US", 7200));
+    assert_eq!(loaded.orders, state.orders);
+
+    // 旧版本的状态文件中 `bar_driven` 为布尔值
+    fs::write(store.path(), r#"{ "bar_driven": true }"#).unwrap();
+    assert!(store.load().unwrap().unwrap().bar_driven.is_empty());
+
+    fs::write(store.path(), "{ not json").unwrap();
+    assert!(matches!(store.load(), Err(AqtError::Data(_))));
+}
+
+#[test]
+fn reconcile_with_broker_orders() {
+    let now = 10 * DAY + 3600;
+    let mut state = StrategyState::default();
+    state.record_order(placed("filled", now - 60));
+    state.record_order(placed("missing", now - 120));
+    state.record_order(placed("yesterday", now - DAY));
+    state.record_order(placed("expired", now - 8 * DAY));
+
+    let dropped = state.reconcile("main", &[order("filled", OrderStatus::Filled, 10.0)], now);
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].order_id, "missing");
+
+    let ids: Vec<&str> = state.orders.iter().map(|o| o.order_id.as_str()).collect();
+    assert_eq!(ids, vec!["filled", "yesterday"]);
+    assert_eq!(state.orders[0].executed_quantity, decimal!(10.0));
+    assert!(state.orders.iter().all(|o| !o.active));
+}
+
+#[tokio::test]
+async fn strategy_restores_and_reconciles_state() {
+    let dir = temp_dir("aqt_state_restore");
+    let store = StateStore::for_strategy(&dir, "main");
+    let broker = Arc::new(PaperBroker::new(&PaperConfig {
+        initial_cash: 100000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    }));
+    let resp = broker
+        .submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(10.0))
+        .await
+        .unwrap();
+
+    // 上次运行记录的订单：一笔仍在券商，一笔券商没有记录
+    let now = broker.now().unix_timestamp();
+    let mut state = StrategyState::default();
+    state.last_bars.insert("AAPL.US".to_string(), 3600);
+    state.record_order(placed(&resp.order_id, now));
+    state.record_order(placed("PAPER-LOST", now));
+    store.save(&state).unwrap();
+
+    let mut sym = SymbolConfig::new();
+    sym.symbol = "AAPL.US".to_string();
+    let market = Arc::new(BacktestMarket::new());
+    let mut strategy = VecorStrategy::with_config(broker, market, vec![sym], Default::default()).with_state(store.clone());
+    strategy.run().await.unwrap();
+
+    assert!(strategy.state().is_processed("AAPL.US", 3600));
+    assert_eq!(strategy.state().orders.len(), 1);
+    assert_eq!(strategy.state().orders[0].order_id, resp.order_id);
+    assert!(strategy.state().orders[0].active);
+    // 对账结果写回状态文件
+    assert_eq!(store.load().unwrap().unwrap().orders, strategy.state().orders);
+}
+
+#[tokio::test]
+async fn bar_driven_mode_is_per_symbol() {
+    let broker = Arc::new(PaperBroker::new(&PaperConfig {
+        initial_cash: 100000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    }));
+    let candles: Vec<Candle> = (0..40)
+        .map(|i| Candle { timestamp: 3600 * (i + 1), open: 100.0, high: 101.0, low: 99.0, close: 100.0, volume: 1000.0, symbol: None })
+        .collect();
+    let mut market = BacktestMarket::new();
+    let mut symbols = Vec::new();
+    for symbol in ["AAPL.US", "MSFT.US"] {
+        market.insert(symbol, "1h", candles.clone());
+        let mut sym = SymbolConfig::new();
+        sym.symbol = symbol.to_string();
+        sym.period = "1h".to_string();
+        sym.indicators = vec![IndicatorConfig::new(IndicatorKind::Kdj(Default::default()))];
+        symbols.push(sym);
+    }
+    market.set_time(3600 * 41);
+    let mut strategy = VecorStrategy::with_config(broker, Arc::new(market), symbols, Default::default());
+
+    // 只有 AAPL 收到K线收盘事件，MSFT 的K线订阅没有推送
+    let bar = BarEvent::new("AAPL.US", "1h", BarEventKind::Closed, candles[39].clone());
+    strategy.on_bar(&bar).await.unwrap();
+    assert!(strategy.state().is_bar_driven("AAPL.US"));
+    assert!(!strategy.state().is_bar_driven("MSFT.US"));
+
+    let quote = |symbol: &str| MarketData {
+        symbol: symbol.to_string(),
+        price: decimal!(100.0),
+        change: decimal!(0.0),
+        volume: 1000,
+        high: decimal!(100.0),
+        low: decimal!(100.0),
+        open: decimal!(100.0),
+        close: decimal!(100.0),
+        ts: OffsetDateTime::from_unix_timestamp(3600 * 41).unwrap(),
+    };
+    // MSFT 仍按行情推测K线收盘，AAPL 只由K线事件驱动
+    strategy.execute(&quote("MSFT.US")).await.unwrap();
+    strategy.execute(&quote("AAPL.US")).await.unwrap();
+    let timed: Vec<&str> = strategy.state().next_run_time.iter().map(|t| t.symbol.as_str()).collect();
+    assert_eq!(timed, vec!["MSFT.US"]);
+}
+
+#[test]
+fn exit_state_survives_restart() {
+    let dir = temp_dir("aqt_state_exits");
+    let store = StateStore::for_strategy(&dir, "main");
+    let mut sym = SymbolConfig::new();
+    sym.symbol = "AAPL.US".to_string();
+    sym.sl_ratio = 10;
+    sym.exit.breakeven_ratio = Some(5.0);
+    sym.exit.max_holding_secs = Some(3600);
+    let t0 = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+    let position = Position {
+        symbol: "AAPL.US".to_string(),
+        quantity: decimal!(10.0),
+        available_quantity: decimal!(10.0),
+        cost_price: decimal!(100.0),
+        ..Default::default()
+    };
+
+    let mut state = StrategyState::default();
+    state.exits.update_position("AAPL.US", &position, &[], t0);
+    // 盈利达到 5%，移到保本止损
+    assert_eq!(state.exits.check(&sym, decimal!(105.0), t0), None);
+    assert!(state.exits.take_changed());
+    store.save(&state).unwrap();
+
+    // 重启后同步持仓不会重新计时，保本止损仍然生效
+    let mut restored = store.load().unwrap().unwrap();
+    let later = OffsetDateTime::from_unix_timestamp(1_700_000_000 + 1800).unwrap();
+    restored.exits.update_position("AAPL.US", &position, &[], later);
+    assert_eq!(restored.exits.check(&sym, decimal!(99.5), later).unwrap().reason, ExitReason::Breakeven);
+    restored.exits.reset_pending("AAPL.US");
+    let expired = OffsetDateTime::from_unix_timestamp(1_700_000_000 + 3600).unwrap();
+    assert_eq!(restored.exits.check(&sym, decimal!(101.0), expired).unwrap().reason, ExitReason::TimeStop);
+}
+
+#[test]
+fn exit_fills_update_trade_stats() {
+    let mut state = StrategyState::default();
+    state.record_order(placed("cover", 100));
+    state.track_exit("cover", decimal!(110.0));
+    state.record_order(placed("working", 100));
+    state.track_exit("working", decimal!(90.0));
+    // 开仓订单不计入盈亏统计
+    state.record_order(placed("entry", 100));
+
+    let mut filled = order("cover", OrderStatus::Filled, 10.0);
+    filled.executed_price = Some(decimal!(105.0));
+    let orders = [filled, order("working", OrderStatus::New, 0.0), order("entry", OrderStatus::Filled, 10.0)];
+    assert_eq!(state.record_fills(&orders), 1);
+    // 买入平空：成本 110，成交均价 105，盈利 50
+    assert_eq!(state.trades["AAPL.US"], TradeStats { wins: 1, losses: 0, total_win: 50.0, total_loss: 0.0 });
+    // 已计入的订单不重复计入
+    assert_eq!(state.record_fills(&orders), 0);
+
+    // 挂单中的平仓订单成交后计入，没有成交均价时按委托价计算
+    assert_eq!(state.record_fills(&[order("working", OrderStatus::Filled, 10.0)]), 1);
+    assert_eq!(state.trades["AAPL.US"].losses, 1);
+    assert_eq!(state.trades["AAPL.US"].total_loss, 100.0);
+}
+
+#[tokio::test]
+async fn exit_filled_on_a_later_day_updates_trade_stats() {
+    let dir = temp_dir("aqt_state_late_exit");
+    let store = StateStore::for_strategy(&dir, "main");
+    let broker = Arc::new(PaperBroker::new(&PaperConfig {
+        initial_cash: 100000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    }));
+    let quote = |price: f64, volume: i64, ts: i64| MarketData {
+        symbol: "AAPL.US".to_string(),
+        price: decimal!(price),
+        change: decimal!(0.0),
+        volume,
+        high: decimal!(price),
+        low: decimal!(price),
+        open: decimal!(price),
+        close: decimal!(price),
+        ts: OffsetDateTime::from_unix_timestamp(ts).unwrap(),
+    };
+    let day1 = 10 * DAY + 3600;
+    broker.on_market_data(&quote(100.0, 1000, day1));
+    broker.submit_order("AAPL.US".to_string(), OrderSide::Buy, decimal!(100.0), decimal!(10.0)).await.unwrap();
+    broker.on_market_data(&quote(100.0, 1100, day1 + 60));
+    // 止盈卖单当天没有成交，次日价格上涨后成交
+    let resp = broker.submit_order("AAPL.US".to_string(), OrderSide::Sell, decimal!(120.0), decimal!(10.0)).await.unwrap();
+    broker.on_market_data(&quote(115.0, 1200, day1 + DAY));
+    assert!(broker.get_today_orders("AAPL.US").await.unwrap().is_empty());
+
+    let mut state = StrategyState::default();
+    let mut placed = PlacedOrder::new(resp.order_id, "AAPL.US", OrderSide::Sell, decimal!(114.0), decimal!(10.0), day1 + 60);
+    placed.exit_cost = Some(decimal!(100.0));
+    state.record_order(placed);
+    store.save(&state).unwrap();
+
+    let mut sym = SymbolConfig::new();
+    sym.symbol = "AAPL.US".to_string();
+    let mut strategy = VecorStrategy::with_config(broker, Arc::new(BacktestMarket::new()), vec![sym], Default::default()).with_state(store);
+    strategy.run().await.unwrap();
+    // 不在今日订单中的平仓订单按历史订单计入盈亏统计
+    assert_eq!(strategy.state().trades["AAPL.US"].wins, 1);
+    assert!(strategy.state().orders.iter().all(|o| o.exit_cost.is_none()));
+}
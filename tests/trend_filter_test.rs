@@ -0,0 +1,168 @@
+use std::sync::Arc;
+use aqt_stock::backtest::market::BacktestMarket;
+use aqt_stock::computes::profile::{IndicatorKind, MacdParams, UtBotParams};
+use aqt_stock::config::config::{Configs, PaperConfig, SymbolConfig};
+use aqt_stock::indicators::candle::{aggregate_candles, Candle};
+use aqt_stock::services::paper_broker::PaperBroker;
+use aqt_stock::strategys::vecor_v1::VecorStrategy;
+
+/// 2024-01-01 00:00:00 UTC，周一
+const MONDAY: u64 = 1704067200;
+const HOUR: u64 = 3600;
+
+fn candle(ts: u64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+    Candle {
+        symbol: Some("AAPL.US".to_string()),
+        timestamp: ts,
+        open,
+        high,
+        low,
+        close,
+        volume: 10.0,
+    }
+}
+
+#[test]
+fn aggregates_lower_timeframe_candles() {
+    let candles = vec![
+        candle(3600, 10.0, 11.0, 9.5, 10.5),
+        candle(3600 + 900, 10.5, 12.0, 10.0, 11.5),
+        candle(3600 + 2700, 11.5, 11.8, 9.0, 9.2),
+        candle(7200, 9.2, 9.4, 8.8, 9.0),
+    ];
+    let hours = aggregate_candles(&candles, "1h");
+    assert_eq!(hours.len(), 2);
+    let first = &hours[0];
+    assert_eq!(first.timestamp, 3600);
+    assert_eq!((first.open, first.high, first.low, first.close), (10.0, 12.0, 9.0, 9.2));
+    assert_eq!(first.volume, 30.0);
+    // 最后一根尚未走完的K线只包含已有数据
+    assert_eq!((hours[1].open, hours[1].close, hours[1].volume), (9.2, 9.0, 10.0));
+
+    let days = aggregate_candles(&candles, "1d");
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].timestamp, 0);
+    assert!(aggregate_candles(&candles, "1y").is_empty());
+}
+
+#[test]
+fn weekly_candles_start_on_monday() {
+    let candles = vec![
+        candle(MONDAY - HOUR, 9.0, 9.0, 9.0, 9.0),
+        candle(MONDAY, 10.0, 10.0, 10.0, 10.0),
+        // 1970-01-01 是周四，周线不能在周四切分
+        candle(MONDAY + 3 * 24 * HOUR, 11.0, 11.0, 11.0, 11.0),
+        candle(MONDAY + 7 * 24 * HOUR - HOUR, 12.0, 12.0, 12.0, 12.0),
+        candle(MONDAY + 7 * 24 * HOUR, 13.0, 13.0, 13.0, 13.0),
+    ];
+    let weeks = aggregate_candles(&candles, "1w");
+    let starts: Vec<u64> = weeks.iter().map(|c| c.timestamp).collect();
+    assert_eq!(starts, vec![MONDAY - 7 * 24 * HOUR, MONDAY, MONDAY + 7 * 24 * HOUR]);
+    assert_eq!((weeks[1].open, weeks[1].close), (10.0, 12.0));
+}
+
+fn trend_strategy(candles: Vec<Candle>) -> (VecorStrategy<PaperBroker, BacktestMarket>, SymbolConfig) {
+    let sym: SymbolConfig = serde_yaml::from_str(
+        r#"
+symbol: AAPL.US
+symbol_type: NASDAQ
+volume: 0.1
+period: 1h
+tp_ratio: 5
+sl_ratio: 3
+trend:
+  period: 1w
+  aggregate: true
+"#,
+    )
+    .unwrap();
+    let broker = Arc::new(PaperBroker::new(&PaperConfig {
+        initial_cash: 100000.0,
+        currency: "USD".to_string(),
+        participation: None,
+        short_margin: None,
+    }));
+    let mut market = BacktestMarket::new();
+    market.set_time(candles.last().unwrap().timestamp + HOUR);
+    market.insert("AAPL.US", "1h", candles);
+    let strategy = VecorStrategy::with_config(broker, Arc::new(market), vec![sym.clone()], Default::default());
+    (strategy, sym)
+}
+
+#[tokio::test]
+async fn trend_decision_with_entry_bars() {
+    // 100 天持续上涨的小时线，入场周期只取最近 365 根，只能聚合出 3 根周线
+    let hours: Vec<Candle> = (0..2400u64)
+        .map(|i| {
+            let price = 100.0 + i as f64 * 0.1;
+            candle(MONDAY + i * HOUR, price, price + 0.5, price - 0.5, price + 0.2)
+        })
+        .collect();
+    let entry = hours[hours.len() - 365..].to_vec();
+    assert!(aggregate_candles(&entry, "1w").len() < 11);
+
+    // 补取更早的小时线后聚合出足够的周线，趋势看多
+    let (mut strategy, sym) = trend_strategy(hours);
+    let decision = strategy.trend_decision("AAPL.US", &sym, &entry).await.unwrap().unwrap();
+    assert_eq!(decision.signal, 1);
+
+    // 没有更早的K线时跳过趋势过滤，不阻止开仓
+    let (mut strategy, sym) = trend_strategy(entry.clone());
+    assert!(strategy.trend_decision("AAPL.US", &sym, &entry).await.unwrap().is_none());
+}
+
+#[test]
+fn trend_filter_from_config() {
+    let cfg: Configs = serde_yaml::from_str(
+        r#"
+symbols:
+  - symbol: AAPL.US
+    symbol_type: NASDAQ
+    volume: 0.1
+    period: 15m
+    tp_ratio: 5
+    sl_ratio: 3
+    trend:
+      period: 1d
+  - symbol: TSLA.US
+    symbol_type: NASDAQ
+    volume: 0.1
+    period: 15m
+    tp_ratio: 5
+    sl_ratio: 3
+    trend:
+      period: 4h
+      aggregate: true
+      threshold: 2
+      indicators:
+        - kind: macd
+        - kind: utbot
+          key: 2.0
+  - symbol: SPY.US
+    symbol_type: AMEX
+    volume: 0.1
+    period: 1h
+    tp_ratio: 5
+    sl_ratio: 3
+"#,
+    )
+    .unwrap();
+
+    let daily = cfg.symbols[0].trend.as_ref().unwrap();
+    assert_eq!(daily.period, "1d");
+    assert!(!daily.aggregate);
+    // 未配置指标时使用 UTBot 判断趋势
+    let profile = daily.profile();
+    assert_eq!(profile.threshold, 1);
+    assert_eq!(profile.indicators.len(), 1);
+    assert_eq!(profile.indicators[0].kind, IndicatorKind::Utbot(UtBotParams::default()));
+    assert_eq!(profile.warmup(), 11);
+
+    let four_hours = cfg.symbols[1].trend.as_ref().unwrap();
+    assert!(four_hours.aggregate);
+    let profile = four_hours.profile();
+    assert_eq!(profile.threshold, 2);
+    assert_eq!(profile.indicators[0].kind, IndicatorKind::Macd(MacdParams::default()));
+
+    assert!(cfg.symbols[2].trend.is_none());
+}
@@ -4,6 +4,7 @@ use longport::{Config, Decimal, QuoteContext, TradeContext};
 use tokio::sync::mpsc;
 use aqt_stock::config::config::Configs;
 use aqt_stock::models::market::MarketData;
+use aqt_stock::services::service::Service;
 use aqt_stock::strategys::executor::Executor;
 
 #[tokio::test]
@@ -17,18 +18,21 @@ async fn test_vecor_executor() {
 
     // 创建一个简单的策略实现
     struct MockStrategy {
-        quote_ctx: Arc<QuoteContext>,
-        trade_ctx: Arc<TradeContext>,
+        broker: Arc<Service>,
+        market: Arc<Service>,
     }
 
     impl Strategy for MockStrategy {
+        type Broker = Service;
+        type Market = Service;
+
         fn new(
-            quote_ctx: Arc<QuoteContext>,
-            trade_ctx: Arc<TradeContext>,
+            broker: Arc<Service>,
+            market: Arc<Service>,
         ) -> Self {
             MockStrategy {
-                quote_ctx,
-                trade_ctx,
+                broker,
+                market,
             }
         }
 
@@ -52,9 +56,10 @@ async fn test_vecor_executor() {
     // 使用封装函数创建 Executor 实例
     let (sender, receiver) = mpsc::channel(10);
     // 创建执行器并保存在变量中
+    let service = Arc::new(Service::new(Arc::new(quote_ctx), Arc::new(trade_ctx)));
     let mut executor = Executor::<MockStrategy>::new(
-        Arc::new(quote_ctx),
-        Arc::new(trade_ctx),
+        service.clone(),
+        service,
         receiver
     );
 